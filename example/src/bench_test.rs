@@ -3,7 +3,7 @@ use fast_log::appender::{FastLogFormatRecord, LogAppender, FastLogRecord};
 use std::time::{Instant, Duration};
 use may::coroutine::sleep;
 
-use fast_log::bencher::QPS;
+use fast_log::bencher::{BenchRecorder, BenchResult, QPS};
 
 struct BenchRecvLog {}
 
@@ -15,12 +15,12 @@ impl LogAppender for BenchRecvLog {
 
 // this example should be   "cargo run --release --package example --bin bench_test"
 fn main(){
-    fast_log::init_custom_log(
+    let _guard = fast_log::init_custom_log(
         vec![Box::new(BenchRecvLog {})],
         log::Level::Info,
         Box::new(NoFilter {}),
         Box::new(FastLogFormatRecord::new()),
-    );
+    ).unwrap();
     let total = 10000;
     let now = Instant::now();
     for index in 0..total {
@@ -28,5 +28,17 @@ fn main(){
     }
     now.time(total);
     now.qps(total);
+
+    // latency percentiles, saved and compared with the last run
+    let mut recorder = BenchRecorder::new("bench_test");
+    for index in 0..total {
+        recorder.time(|| log::info!("Commencing yak shaving{}", index));
+    }
+    let result = recorder.finish();
+    if let Ok(base) = BenchResult::load("target/bench_test.json") {
+        println!("{}", result.diff(&base));
+    }
+    result.save("target/bench_test.json").unwrap();
+    result.save("target/bench_test.csv").unwrap();
     may::coroutine::sleep(Duration::from_secs(1));
 }
\ No newline at end of file
@@ -34,12 +34,12 @@ impl LogAppender for CustomLog {
 }
 
 fn main(){
-    fast_log::init_custom_log(
+    let _guard = fast_log::init_custom_log(
         vec![Box::new(CustomLog {})],
         log::Level::Info,
         Box::new(NoFilter {}),
         Box::new(FastLogFormatRecord::new()),
-    );
+    ).unwrap();
     log::info!("Commencing yak shaving");
     log::error!("Commencing error");
     may::coroutine::sleep(Duration::from_secs(1));
@@ -0,0 +1,18 @@
+use fast_log::flight_recorder::FlightRecorder;
+use log::LevelFilter;
+
+fn main() {
+    let _guard = fast_log::init_log("target/logs/flight.log", log::Level::Info, None, None, true).unwrap();
+    //keep the last 1000 Debug records in memory, dump them when a panic happens
+    fast_log::enable_flight_recorder(FlightRecorder {
+        capacity: 1000,
+        level: LevelFilter::Debug,
+        dump_path: Some("target/logs/flight_dump.log".to_string()),
+    });
+    fast_log::install_panic_hook();
+    for index in 0..5 {
+        log::debug!("not written to disk, only in the flight recorder {}", index);
+    }
+    log::info!("written to disk");
+    panic!("crash");
+}
@@ -19,6 +19,7 @@ fn main() {
         &"/tmp/flush_later_log.log",
         log::Level::Trace,
         None,
+        None,
         true,
     )
     .unwrap();
@@ -0,0 +1,10 @@
+fn main() {
+    let _guard = fast_log::init_log("target/logs/kv.log", log::Level::Info, None, None, true).unwrap();
+    let user_id = 42;
+    let latency_ms = 3.5;
+    //2026-01-01 00:00:00.000000000 INFO kv:6 - login alice user_id=42 latency_ms=3.5 ok=true
+    fast_log::info_kv!("login {}", "alice"; user_id = user_id, latency_ms = latency_ms, ok = true);
+    fast_log::warn_kv!("slow request"; path = "/api/users", latency_ms = 1200);
+    fast_log::info_kv!("no key-values");
+    fast_log::flush().unwrap();
+}
@@ -2,7 +2,7 @@ use std::thread::sleep;
 use std::time::Duration;
 
 fn main(){
-    fast_log::init_log("requests.log", log::Level::Debug, None, true).unwrap();
+    let _guard = fast_log::init_log("requests.log", log::Level::Debug, None, None, true).unwrap();
     log::debug!("Commencing yak shaving{}", 0);
     sleep(Duration::from_secs(1));
 }
\ No newline at end of file
@@ -0,0 +1,23 @@
+use fast_log::appender::FastLogFormatRecord;
+use fast_log::filter::NoFilter;
+use fast_log::plugin::console::ConsoleAppender;
+use fast_log::relay::RelayAppender;
+use std::time::Duration;
+
+fn main() {
+    //the collector process: records from other processes go to its console
+    fast_log::relay::serve("127.0.0.1:9002", vec![Box::new(ConsoleAppender {})]).unwrap();
+
+    //an application process: ship records to the collector
+    let _guard = fast_log::init_custom_log(
+        vec![Box::new(RelayAppender::new("127.0.0.1:9002", "app-1", 10000))],
+        log::Level::Info,
+        Box::new(NoFilter {}),
+        Box::new(FastLogFormatRecord::new()),
+    )
+    .unwrap();
+    log::info!("Commencing yak shaving");
+    log::error!("Commencing error");
+    fast_log::flush().unwrap();
+    std::thread::sleep(Duration::from_secs(1));
+}
@@ -5,15 +5,16 @@ use std::thread::sleep;
 use std::time::Duration;
 
 fn main(){
-    fast_log::init_split_log(
+    let _guard = fast_log::init_split_log(
         "target/logs/",
         LogSize::MB(1),
         RollingType::All,
         log::Level::Info,
         None,
+        None,
         Box::new(LogPacker{}),
         true,
-    );
+    ).unwrap();
     for _ in 0..20000 {
         log::info!("Commencing yak shaving");
     }
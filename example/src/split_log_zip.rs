@@ -5,15 +5,16 @@ use std::thread::sleep;
 use std::time::Duration;
 
 fn main(){
-    fast_log::init_split_log(
+    let _guard = fast_log::init_split_log(
         "target/logs/",
         LogSize::KB(50),
         RollingType::KeepNum(5),
         log::Level::Info,
         None,
+        None,
         Box::new(ZipPacker{}),
         true,
-    );
+    ).unwrap();
     for _ in 0..20000 {
         log::info!("Commencing yak shaving");
     }
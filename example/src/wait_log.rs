@@ -1,5 +1,5 @@
 fn main(){
-    let wait=fast_log::init_log("requests.log", log::Level::Debug, None, true).unwrap();
+    let wait=fast_log::init_log("requests.log", log::Level::Debug, None, None, true).unwrap();
     log::debug!("Commencing yak shaving{}", 0);
     wait.wait();
 }
\ No newline at end of file
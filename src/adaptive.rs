@@ -0,0 +1,115 @@
+use log::LevelFilter;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// raise the level for a while when errors burst
+#[derive(Clone, Debug)]
+pub struct AdaptiveLevel {
+    /// errors in `window` that start the boost
+    pub threshold: usize,
+    pub window: Duration,
+    /// the level while boosted, for example Debug
+    pub boost_level: LevelFilter,
+    /// how long the boost lasts after the last burst
+    pub boost_for: Duration,
+}
+
+impl Default for AdaptiveLevel {
+    fn default() -> Self {
+        Self {
+            threshold: 10,
+            window: Duration::from_secs(10),
+            boost_level: LevelFilter::Debug,
+            boost_for: Duration::from_secs(60),
+        }
+    }
+}
+
+struct AdaptiveState {
+    config: AdaptiveLevel,
+    errors: VecDeque<Instant>,
+    boosted_until: Option<Instant>,
+    saved_level: LevelFilter,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static BOOSTED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref STATE: Mutex<Option<AdaptiveState>> = Mutex::new(None);
+}
+
+/// start the controller, it watches Error records on the producer side.
+/// the level is restored by the first record after the boost ends
+pub fn enable_adaptive_level(config: AdaptiveLevel) {
+    *STATE.lock() = Some(AdaptiveState {
+        config,
+        errors: VecDeque::new(),
+        boosted_until: None,
+        saved_level: crate::fast_log::logger().get_level(),
+    });
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// stop the controller and restore the level if boosted
+pub fn disable_adaptive_level() {
+    ENABLED.store(false, Ordering::Relaxed);
+    if let Some(state) = STATE.lock().take() {
+        if state.boosted_until.is_some() {
+            set_level(state.saved_level);
+        }
+    }
+    BOOSTED.store(false, Ordering::Relaxed);
+}
+
+pub fn is_boosted() -> bool {
+    BOOSTED.load(Ordering::Relaxed)
+}
+
+fn set_level(level: LevelFilter) {
+    crate::fast_log::set_level_filter(level);
+}
+
+/// called by the logger for every record
+pub(crate) fn on_record(level: log::Level) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if level != log::Level::Error && !BOOSTED.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut state = STATE.lock();
+    let state = match state.as_mut() {
+        Some(v) => v,
+        None => return,
+    };
+    let now = Instant::now();
+    if level == log::Level::Error {
+        state.errors.push_back(now);
+        while let Some(first) = state.errors.front() {
+            if now.duration_since(*first) > state.config.window {
+                state.errors.pop_front();
+            } else {
+                break;
+            }
+        }
+        if state.errors.len() >= state.config.threshold {
+            if state.boosted_until.is_none() {
+                state.saved_level = crate::fast_log::logger().get_level();
+                set_level(state.config.boost_level.max(state.saved_level));
+                BOOSTED.store(true, Ordering::Relaxed);
+            }
+            state.boosted_until = Some(now + state.config.boost_for);
+        }
+    }
+    if let Some(until) = state.boosted_until {
+        if now >= until {
+            state.boosted_until = None;
+            state.errors.clear();
+            set_level(state.saved_level);
+            BOOSTED.store(false, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::error::LogError;
+use log::LevelFilter;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::str::FromStr;
+
+/// admin commands, one command per line, answered with one line:
+///
+/// level            get level
+/// level <level>    set level (off,error,warn,info,debug,trace)
+/// flush            flush buffered records
+/// rotate           flush, the split log appender packs temp.log
+/// reopen           reopen log files
+/// mute / unmute    stop/resume logging
+/// metrics          dump metrics as json
+/// health           dump health as json
+/// appenders        list appenders
+pub fn handle_command(line: &str) -> String {
+    let mut items = line.split_whitespace();
+    let command = items.next().unwrap_or("");
+    let arg = items.next();
+    let result = match (command, arg) {
+        ("level", None) => Ok(crate::fast_log::get_level().to_string()),
+        ("level", Some(level)) => match LevelFilter::from_str(level) {
+            Ok(level) => {
+                crate::fast_log::set_level_filter(level);
+                Ok(level.to_string())
+            }
+            Err(_) => Err(LogError::from(format!("bad level: {}", level))),
+        },
+        ("flush", None) | ("rotate", None) => crate::fast_log::flush().map(|_| "ok".to_string()),
+        ("reopen", None) => crate::fast_log::reopen().map(|_| "ok".to_string()),
+        ("mute", None) => {
+            crate::fast_log::mute();
+            Ok("ok".to_string())
+        }
+        ("unmute", None) => {
+            crate::fast_log::unmute();
+            Ok("ok".to_string())
+        }
+        ("metrics", None) => {
+            serde_json::to_string(&crate::metrics::metrics()).map_err(|e| LogError::from(e.to_string()))
+        }
+        ("health", None) => {
+            serde_json::to_string(&crate::health::health()).map_err(|e| LogError::from(e.to_string()))
+        }
+        ("appenders", None) => Ok(crate::fast_log::appender_names().join(",")),
+        _ => Err(LogError::from(format!("unknown command: {}", line.trim()))),
+    };
+    match result {
+        Ok(v) => v,
+        Err(LogError::E(e)) => format!("error: {}", e),
+    }
+}
+
+fn serve_conn<S: std::io::Read + Write>(stream: S) {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = handle_command(&line);
+        let stream = reader.get_mut();
+        if writeln!(stream, "{}", response).is_err() {
+            return;
+        }
+    }
+}
+
+/// serve admin commands on a loopback tcp address, for example "127.0.0.1:9001". the commands have no auth,
+/// an address other hosts can reach(for example "0.0.0.0:9001") is refused.
+/// for example: echo "level debug" | nc 127.0.0.1 9001
+pub fn serve_tcp(addr: &str) -> Result<(), LogError> {
+    let addrs: Vec<SocketAddr> = addr
+        .to_socket_addrs()
+        .map_err(|e| LogError::from(format!("[fast_log] admin addr({}) fail:{}", addr, e)))?
+        .collect();
+    if addrs.is_empty() || addrs.iter().any(|v| !v.ip().is_loopback()) {
+        return Err(LogError::from(format!(
+            "[fast_log] admin addr({}) is not a loopback address, the commands have no auth",
+            addr
+        )));
+    }
+    let listener = TcpListener::bind(&addrs[..])
+        .map_err(|e| LogError::from(format!("[fast_log] admin bind({}) fail:{}", addr, e)))?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || serve_conn(stream));
+        }
+    });
+    Ok(())
+}
+
+/// serve admin commands on a unix socket, an exist socket file is replaced, an other file is an error.
+/// for example: echo "flush" | nc -U /tmp/app.log.sock
+#[cfg(unix)]
+pub fn serve_unix(path: &str) -> Result<(), LogError> {
+    remove_stale_socket(path)?;
+    let listener = std::os::unix::net::UnixListener::bind(path)
+        .map_err(|e| LogError::from(format!("[fast_log] admin bind({}) fail:{}", path, e)))?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || serve_conn(stream));
+        }
+    });
+    Ok(())
+}
+
+/// remove the socket file an earlier run left at `path`, an other file there is an error and stays
+#[cfg(unix)]
+pub(crate) fn remove_stale_socket(path: &str) -> Result<(), LogError> {
+    use std::os::unix::fs::FileTypeExt;
+    match std::fs::symlink_metadata(path) {
+        Ok(m) if m.file_type().is_socket() => std::fs::remove_file(path)
+            .map_err(|e| LogError::from(format!("[fast_log] remove socket {} fail:{}", path, e))),
+        Ok(_) => Err(LogError::from(format!("[fast_log] {} exists and is not a socket", path))),
+        Err(_) => Ok(()),
+    }
+}
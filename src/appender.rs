@@ -1,7 +1,37 @@
-use chrono::{DateTime, Local, Utc, Timelike, Duration};
+use chrono::{DateTime, Local, NaiveDateTime, Utc, Timelike, Duration};
 use log::Level;
+use crate::small_string::SmallString;
+use crate::trace::TraceContext;
+use parking_lot::Mutex;
+use std::fmt;
+use std::fmt::Write;
+use std::sync::Arc;
 use std::time::SystemTime;
-use std::ops::{Add, Sub};
+use std::ops::Add;
+
+/// the current time, wasm32 has no SystemTime::now() so it reads the js clock.
+/// the clock of clock::set_clock or the cached time with clock::enable_coarse_time
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now() -> SystemTime {
+    crate::clock::now()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn now() -> SystemTime {
+    SystemTime::UNIX_EPOCH + std::time::Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+}
+
+/// time since the first record of the process on a monotonic clock, the record times are compared
+/// with it to find wall clock jumps(see clock::set_clock_jump_threshold). zero on wasm32
+#[cfg(not(target_arch = "wasm32"))]
+pub fn monotonic() -> std::time::Duration {
+    crate::clock::monotonic()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn monotonic() -> std::time::Duration {
+    std::time::Duration::ZERO
+}
 
 /// LogAppender append logs
 /// Appender will be running on single main thread,please do_log for new thread or new an Future
@@ -10,17 +40,121 @@ pub trait LogAppender: Send {
     /// so. if you want  access the network, you can launch a coroutine using go! (| | {});
     fn do_log(&self, record: &mut FastLogRecord);
 
+    /// the formatted records(only CommandRecord) buffered between two commands, at most MAX_BATCH_SIZE,
+    /// for bulk writes or requests. commands still go to do_log
+    fn do_logs(&self, records: &mut [FastLogRecord]) {
+        for record in records {
+            self.do_log(record);
+        }
+    }
+
+    /// false: the appender ignores the record, for example a record filtered out by AppenderExt::filtered
+    fn accepts(&self, record: &FastLogRecord) -> bool {
+        true
+    }
+
+    /// called once before on_init with the pipeline running the appender. keep it to send commands from the
+    /// threads of the appender(for example a timer) into this pipeline, not into the global logger
+    fn attach(&self, pipeline: &PipelineSender) {}
+
+    /// called once on the back worker before the first record, for example to write a session header
+    fn on_init(&self) {}
+
+    /// called after log files were rotated(a split or CommandReopen), the new file is empty
+    fn on_rotate(&self) {}
+
+    /// called on fast_log::config_changed() and level changes, for example to re-read credentials
+    fn on_config_change(&self) {}
+
+    /// called on CommandFlush after do_log, write out buffered records here
+    fn flush(&self) {}
+
+    /// called once on CommandExit before the appender is dropped, drain buffers and close connections
+    fn shutdown(&self) {}
+
     fn type_name(&self) -> &'static str {
         std::any::type_name::<Self>()
     }
 }
 
+/// a boxed appender is an appender, so the combinators of AppenderExt work on it
+impl LogAppender for Box<dyn LogAppender> {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        (**self).do_log(record);
+    }
+
+    fn do_logs(&self, records: &mut [FastLogRecord]) {
+        (**self).do_logs(records);
+    }
+
+    fn accepts(&self, record: &FastLogRecord) -> bool {
+        (**self).accepts(record)
+    }
+
+    fn attach(&self, pipeline: &PipelineSender) {
+        (**self).attach(pipeline);
+    }
+
+    fn on_init(&self) {
+        (**self).on_init();
+    }
+
+    fn on_rotate(&self) {
+        (**self).on_rotate();
+    }
+
+    fn on_config_change(&self) {
+        (**self).on_config_change();
+    }
+
+    fn flush(&self) {
+        (**self).flush();
+    }
+
+    fn shutdown(&self) {
+        (**self).shutdown();
+    }
+
+    fn type_name(&self) -> &'static str {
+        (**self).type_name()
+    }
+}
+
+/// sends commands into the pipeline which runs an appender, see LogAppender::attach
+#[derive(Clone)]
+pub struct PipelineSender {
+    send: Arc<dyn Fn(FastLogRecord) -> bool + Send + Sync>,
+}
+
+impl PipelineSender {
+    /// send: puts the record into the channel of the pipeline, false if it exited
+    pub fn new(send: impl Fn(FastLogRecord) -> bool + Send + Sync + 'static) -> Self {
+        Self { send: Arc::new(send) }
+    }
+
+    /// false if the pipeline exited
+    pub fn send(&self, command: Command) -> bool {
+        (self.send)(FastLogRecord::new_command(command))
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Command {
     CommandRecord,
     CommandExit,
     /// Ensure that the log splitter forces splitting and saves the log
     CommandFlush,
+    /// reopen log files, for example after logrotate moved them
+    CommandReopen,
+    /// app-specific control message (id, data) for custom appenders,
+    /// sent in order with records by fast_log::send_command()
+    CommandCustom(u32, String),
+    /// a log file was rotated, sent by FileSplitAppender after a split
+    CommandRotate,
+    /// the configuration changed, sent by fast_log::config_changed()
+    CommandConfig,
+    /// a timer of an appender is due, for example the time split of FileSplitAppender(SplitType::ByTime)
+    CommandTick,
 }
 
 #[derive(Clone, Debug)]
@@ -28,15 +162,52 @@ pub struct FastLogRecord {
     pub command: Command,
     pub level: log::Level,
     pub target: String,
-    pub args: String,
+    /// the message, inline up to small_string::INLINE_CAP bytes
+    pub args: SmallString,
     pub module_path: String,
     pub file: String,
     pub line: Option<u32>,
     pub now: SystemTime,
-    pub formated: String,
+    /// the monotonic time(see monotonic()) read with `now`, not moved by wall clock changes.
+    /// None if `now` was not read from the clock, for example a record of an other process or a replayed one
+    pub monotonic: Option<std::time::Duration>,
+    /// set by the RecordFormat, a record enqueued with formated set(emit_raw) is not formatted again
+    pub formated: SmallString,
+    /// only set on CommandFlush, notified once every appender handled the flush
+    pub flush_ack: Option<crossbeam::channel::Sender<()>>,
+    /// the active trace/span when the record was logged
+    pub trace: Option<TraceContext>,
+    /// typed key-values, for example from `fast_log::info_kv!("msg"; user_id = 1)`
+    pub kv: Vec<(String, serde_json::Value)>,
+    /// tags of fast_log::tagged! or tags::with_tags, for example "audit"
+    pub tags: Vec<String>,
 }
 
 impl FastLogRecord {
+    /// a record carry a command, it has no log data
+    pub fn new_command(command: Command) -> FastLogRecord {
+        FastLogRecord {
+            command,
+            level: log::Level::Info,
+            target: String::new(),
+            args: SmallString::new(),
+            module_path: String::new(),
+            file: String::new(),
+            line: None,
+            now: now(),
+            monotonic: Some(monotonic()),
+            formated: SmallString::new(),
+            flush_ack: None,
+            trace: None,
+            kv: vec![],
+            tags: vec![],
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|v| v == tag)
+    }
+
     pub fn format_line(&self) -> String {
         match (self.file.as_str(), self.line.unwrap_or(0)) {
             (file, line) => format!("({}:{})", file, line),
@@ -44,68 +215,145 @@ impl FastLogRecord {
     }
 }
 
-/// format record data
+thread_local! {
+    /// the RecordFormat running the default format, (address, type name)
+    static DEFAULT_FORMAT: std::cell::Cell<Option<(usize, &'static str)>> = const { std::cell::Cell::new(None) };
+}
+
+/// format record data, implement format or do_format(the defaults call each other)
 pub trait RecordFormat: Send + Sync {
-    fn do_format(&self, arg: &mut FastLogRecord);
+    /// write the formatted record into the sink, for example the buffer of an appender.
+    /// the default formats a copy of the record with do_format, fmt::Error if neither is implemented
+    fn format(&self, record: &FastLogRecord, sink: &mut dyn fmt::Write) -> fmt::Result {
+        let this = Some((self as *const Self as *const () as usize, std::any::type_name::<Self>()));
+        if DEFAULT_FORMAT.with(|v| v.get()) == this {
+            return Err(fmt::Error);
+        }
+        let outer = DEFAULT_FORMAT.with(|v| v.replace(this));
+        let mut record = record.clone();
+        self.do_format(&mut record);
+        DEFAULT_FORMAT.with(|v| v.set(outer));
+        sink.write_str(&record.formated)
+    }
+
+    /// set record.formated, the pipeline calls this for every record
+    fn do_format(&self, arg: &mut FastLogRecord) {
+        //reuse the buffer of the record
+        let mut buf = std::mem::take(&mut arg.formated);
+        buf.clear();
+        self.format(arg, &mut buf);
+        arg.formated = buf;
+    }
 }
 
 pub struct FastLogFormatRecord {
     pub duration: Duration,
+    //the date and time without the fraction of the current second
+    second: Mutex<(i64, String)>,
 }
 
 impl RecordFormat for FastLogFormatRecord {
-    fn do_format(&self, arg: &mut FastLogRecord) {
-        let data;
+    fn format(&self, arg: &FastLogRecord, sink: &mut dyn fmt::Write) -> fmt::Result {
         let now: DateTime<Utc> = chrono::DateTime::from(arg.now);
         let now = now.add(self.duration).naive_utc();
-        match arg.level {
-            Level::Warn | Level::Error => {
-                if arg.line.is_some(){
-                    data = format!(
-                        "{:36} {} {}:{} - {}  {}\n",
-                        &now,
-                        arg.level,
-                        arg.module_path,
-                        arg.line.unwrap(),
-                        arg.args,
-                        arg.format_line()
-                    );
-                } else {
-                    data = format!(
-                        "{:36} {} {} - {}  {}\n",
-                        &now,
-                        arg.level,
-                        arg.module_path,
-                        arg.args,
-                        arg.format_line()
-                    );
-                }
-            }
-            _ => {
-                if arg.line.is_some(){
-                    data = format!(
-                        "{:36} {} {}:{} - {}\n",
-                        &now, arg.level, arg.module_path, arg.line.unwrap(), arg.args
-                    );
-                } else {
-                    data = format!(
-                        "{:36} {} {} - {}\n",
-                        &now, arg.level, arg.module_path, arg.args
-                    );
-                }
-            }
+        self.write_time(&now, sink)?;
+        write!(sink, " {} {}", arg.level, arg.module_path)?;
+        if let Some(line) = arg.line {
+            write!(sink, ":{}", line)?;
         }
-        arg.formated = data;
+        write!(sink, " - {}", arg.args)?;
+        if !arg.kv.is_empty() {
+            sink.write_str(&crate::kv::format_kv(&arg.kv))?;
+        }
+        for tag in &arg.tags {
+            write!(sink, " #{}", tag)?;
+        }
+        //records of emit() may have no file
+        if matches!(arg.level, Level::Warn | Level::Error) && !arg.file.is_empty() {
+            write!(sink, "  {}", arg.format_line())?;
+        }
+        if let Some(trace) = &arg.trace {
+            write!(sink, " trace_id={} span_id={}", trace.trace_id, trace.span_id)?;
+        }
+        sink.write_str("\n")
     }
 }
 
 impl FastLogFormatRecord {
     pub fn new() -> FastLogFormatRecord {
-        let utc = chrono::Utc::now().naive_utc();
-        let tz = chrono::Local::now().naive_local();
-        let d = tz.sub(utc);
+        //whole seconds, two clock reads would add the time between them
+        let d = Duration::seconds(chrono::Local::now().offset().local_minus_utc() as i64);
         Self {
-            duration: d
+            duration: d,
+            second: Mutex::new((i64::MIN, String::new())),
+        }
+    }
+
+    /// the same text as the Display of NaiveDateTime, the part up to the seconds is formatted once a second
+    fn write_time(&self, now: &NaiveDateTime, sink: &mut dyn fmt::Write) -> fmt::Result {
+        let nanos = now.nanosecond();
+        if nanos >= 1_000_000_000 {
+            //leap second
+            return write!(sink, "{}", now);
+        }
+        {
+            let secs = now.and_utc().timestamp();
+            let mut second = self.second.lock();
+            if second.0 != secs {
+                second.1.clear();
+                write!(second.1, "{}", now.format("%Y-%m-%d %H:%M:%S"))?;
+                second.0 = secs;
+            }
+            sink.write_str(&second.1)?;
+        }
+        if nanos == 0 {
+            Ok(())
+        } else if nanos % 1_000_000 == 0 {
+            write!(sink, ".{:03}", nanos / 1_000_000)
+        } else if nanos % 1_000 == 0 {
+            write!(sink, ".{:06}", nanos / 1_000)
+        } else {
+            write!(sink, ".{:09}", nanos)
+        }
+    }
+}
+
+/// one json object per line, for ELK/Loki/Vector:
+/// {"v":1,"timestamp":"2024-01-01T00:00:00.000000001Z","level":"INFO","target","module_path","file","line","message",
+/// "trace_id","span_id","fields":{key-values},"tags":[..]}. the trace, fields and tags only if the record has them,
+/// see reader::json_record to read it back
+#[derive(Default)]
+pub struct JsonFormatRecord {}
+
+impl JsonFormatRecord {
+    pub fn new() -> JsonFormatRecord {
+        Self {}
+    }
+}
+
+impl RecordFormat for JsonFormatRecord {
+    fn format(&self, arg: &FastLogRecord, sink: &mut dyn fmt::Write) -> fmt::Result {
+        let time: DateTime<Utc> = DateTime::from(arg.now);
+        let mut json = serde_json::json!({
+            "v": crate::consts::SCHEMA_VERSION,
+            "timestamp": time.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true),
+            "level": arg.level.as_str(),
+            "target": arg.target,
+            "module_path": arg.module_path,
+            "file": arg.file,
+            "line": arg.line,
+            "message": arg.args.as_str(),
+        });
+        if let Some(trace) = &arg.trace {
+            json["trace_id"] = trace.trace_id.clone().into();
+            json["span_id"] = trace.span_id.clone().into();
+        }
+        if !arg.kv.is_empty() {
+            json["fields"] = serde_json::Value::Object(arg.kv.iter().cloned().collect());
+        }
+        if !arg.tags.is_empty() {
+            json["tags"] = arg.tags.clone().into();
         }
+        writeln!(sink, "{}", json)
     }
 }
@@ -0,0 +1,103 @@
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// the backlog crossed a watermark, see on_backpressure()
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Pressure {
+    /// the backlog reached the high watermark
+    High,
+    /// the backlog fell to the low watermark again
+    Low,
+}
+
+struct Watermarks {
+    high: usize,
+    low: usize,
+    callback: Arc<dyn Fn(Pressure, usize) + Send + Sync>,
+}
+
+//records sent but not yet written by the appenders
+static BACKLOG: AtomicUsize = AtomicUsize::new(0);
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static HIGH: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref WATERMARKS: RwLock<Option<Watermarks>> = RwLock::new(None);
+}
+
+/// call `f(Pressure::High, backlog)` when the records waiting for the appenders reach `high`,
+/// and `f(Pressure::Low, backlog)` once they fall to `low` again, for example to lower the verbosity
+/// or reject requests before the logger falls behind. `f` runs on the logging thread or the log worker,
+/// keep it short and do not log in it
+pub fn on_backpressure<F>(high: usize, low: usize, f: F)
+where
+    F: Fn(Pressure, usize) + Send + Sync + 'static,
+{
+    *WATERMARKS.write() = Some(Watermarks {
+        high: high.max(1),
+        low: low.min(high),
+        callback: Arc::new(f),
+    });
+    HIGH.store(false, Ordering::SeqCst);
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// remove the callback of on_backpressure()
+pub fn clear_backpressure() {
+    ENABLED.store(false, Ordering::SeqCst);
+    *WATERMARKS.write() = None;
+}
+
+/// records sent to the pipeline and not yet written by the appenders
+pub fn backlog() -> usize {
+    BACKLOG.load(Ordering::Relaxed)
+}
+
+/// a record was sent
+pub(crate) fn enqueued() {
+    let backlog = BACKLOG.fetch_add(1, Ordering::Relaxed) + 1;
+    if ENABLED.load(Ordering::Relaxed) {
+        check(backlog);
+    }
+}
+
+/// the appenders wrote `n` records
+pub(crate) fn dequeued(n: usize) {
+    let backlog = match BACKLOG.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| Some(v.saturating_sub(n))) {
+        Ok(v) => v.saturating_sub(n),
+        Err(v) => v,
+    };
+    if ENABLED.load(Ordering::Relaxed) {
+        check(backlog);
+    }
+}
+
+fn check(backlog: usize) {
+    let callback = match WATERMARKS.read().as_ref() {
+        Some(w) => {
+            if backlog >= w.high && !HIGH.load(Ordering::Relaxed) {
+                Some((Pressure::High, w.callback.clone()))
+            } else if backlog <= w.low && HIGH.load(Ordering::Relaxed) {
+                Some((Pressure::Low, w.callback.clone()))
+            } else {
+                None
+            }
+        }
+        None => None,
+    };
+    if let Some((pressure, callback)) = callback {
+        //only the thread which flips the state calls back
+        let high = pressure == Pressure::High;
+        if HIGH.compare_exchange(!high, high, Ordering::SeqCst, Ordering::Relaxed).is_ok() {
+            callback(pressure, backlog);
+            if high {
+                let args = format!("queue saturated: {} records waiting", backlog);
+                crate::diagnostics::emit(log::Level::Warn, "saturated", args);
+            } else {
+                let args = format!("queue drained: {} records waiting", backlog);
+                crate::diagnostics::emit(log::Level::Info, "drained", args);
+            }
+        }
+    }
+}
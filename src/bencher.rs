@@ -1,3 +1,10 @@
+use crate::error::LogError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::Display;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
 pub trait QPS {
     fn qps(&self, total: u64);
     fn time(&self, total: u64);
@@ -27,3 +34,185 @@ impl QPS for std::time::Instant {
         println!("cost:{:?}", time);
     }
 }
+
+/// record each op latency of a bench run
+pub struct BenchRecorder {
+    name: String,
+    start: Instant,
+    latencies: Vec<u64>,
+    allocations: Option<u64>,
+}
+
+impl BenchRecorder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            start: Instant::now(),
+            latencies: vec![],
+            allocations: None,
+        }
+    }
+
+    /// record one op latency
+    pub fn record(&mut self, latency: Duration) {
+        self.latencies.push(latency.as_nanos() as u64);
+    }
+
+    /// time one op
+    pub fn time<R, F: FnOnce() -> R>(&mut self, f: F) -> R {
+        let now = Instant::now();
+        let r = f();
+        self.record(now.elapsed());
+        r
+    }
+
+    /// allocations counted by your allocator, if any
+    pub fn set_allocations(&mut self, allocations: u64) {
+        self.allocations = Some(allocations);
+    }
+
+    pub fn finish(mut self) -> BenchResult {
+        let elapsed = self.start.elapsed();
+        self.latencies.sort_unstable();
+        let total = self.latencies.len() as u64;
+        let percentile = |p: f64| -> u64 {
+            if self.latencies.is_empty() {
+                return 0;
+            }
+            let index = ((self.latencies.len() as f64 * p).ceil() as usize).max(1) - 1;
+            self.latencies[index.min(self.latencies.len() - 1)]
+        };
+        BenchResult {
+            name: self.name.clone(),
+            total,
+            elapsed_ns: elapsed.as_nanos() as u64,
+            ops_per_sec: if elapsed.as_nanos() == 0 {
+                0.0
+            } else {
+                total as f64 * 1e9 / elapsed.as_nanos() as f64
+            },
+            p50_ns: percentile(0.5),
+            p99_ns: percentile(0.99),
+            p999_ns: percentile(0.999),
+            max_ns: self.latencies.last().copied().unwrap_or(0),
+            allocations: self.allocations,
+        }
+    }
+}
+
+/// result of a bench run, can save as csv/json and diff with an other run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub name: String,
+    pub total: u64,
+    pub elapsed_ns: u64,
+    pub ops_per_sec: f64,
+    pub p50_ns: u64,
+    pub p99_ns: u64,
+    pub p999_ns: u64,
+    pub max_ns: u64,
+    pub allocations: Option<u64>,
+}
+
+pub const BENCH_CSV_HEAD: &str = "name,total,elapsed_ns,ops_per_sec,p50_ns,p99_ns,p999_ns,max_ns,allocations";
+
+impl BenchResult {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, LogError> {
+        serde_json::from_str(data).map_err(|e| LogError::from(format!("[fast_log] parse bench result fail:{}", e)))
+    }
+
+    /// one csv row, the head is BENCH_CSV_HEAD
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{:.2},{},{},{},{},{}",
+            self.name,
+            self.total,
+            self.elapsed_ns,
+            self.ops_per_sec,
+            self.p50_ns,
+            self.p99_ns,
+            self.p999_ns,
+            self.max_ns,
+            self.allocations.map(|v| v.to_string()).unwrap_or_default()
+        )
+    }
+
+    /// append to a .csv(with head if new) or write a .json file
+    pub fn save(&self, path: &str) -> Result<(), LogError> {
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(parent);
+        }
+        let r = if path.ends_with(".csv") {
+            let exists = std::path::Path::new(path).exists();
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut f| {
+                    if !exists {
+                        writeln!(f, "{}", BENCH_CSV_HEAD)?;
+                    }
+                    writeln!(f, "{}", self.to_csv())
+                })
+        } else {
+            std::fs::write(path, self.to_json())
+        };
+        r.map_err(|e| LogError::from(format!("[fast_log] save bench result fail:{}", e)))
+    }
+
+    /// load a .json file saved by save()
+    pub fn load(path: &str) -> Result<Self, LogError> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| LogError::from(format!("[fast_log] load bench result fail:{}", e)))?;
+        Self::from_json(&data)
+    }
+
+    /// compare self(new run) with base(old run)
+    pub fn diff(&self, base: &BenchResult) -> BenchDiff {
+        let change = |new: f64, old: f64| -> f64 {
+            if old == 0.0 {
+                0.0
+            } else {
+                (new - old) * 100.0 / old
+            }
+        };
+        BenchDiff {
+            ops_per_sec: change(self.ops_per_sec, base.ops_per_sec),
+            p50: change(self.p50_ns as f64, base.p50_ns as f64),
+            p99: change(self.p99_ns as f64, base.p99_ns as f64),
+            p999: change(self.p999_ns as f64, base.p999_ns as f64),
+            allocations: match (self.allocations, base.allocations) {
+                (Some(new), Some(old)) => Some(change(new as f64, old as f64)),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// change percent of new run from base run, ops_per_sec up and latency down is better
+#[derive(Clone, Debug)]
+pub struct BenchDiff {
+    pub ops_per_sec: f64,
+    pub p50: f64,
+    pub p99: f64,
+    pub p999: f64,
+    pub allocations: Option<f64>,
+}
+
+impl Display for BenchDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ops/sec: {:+.2}%, p50: {:+.2}%, p99: {:+.2}%, p999: {:+.2}%",
+            self.ops_per_sec, self.p50, self.p99, self.p999
+        )?;
+        if let Some(allocations) = self.allocations {
+            write!(f, ", allocations: {:+.2}%", allocations)?;
+        }
+        Ok(())
+    }
+}
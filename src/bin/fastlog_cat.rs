@@ -0,0 +1,144 @@
+//! print fast_log files and split log dirs, archives are decompressed.
+//!
+//! fastlog-cat [--level info] [--target my_app] [--since "2021-01-01 00:00:00"] [--until ...] [--grep text] [--no-color] <dir or file>...
+use chrono::NaiveDateTime;
+use fast_log::reader::{line_level, line_module, line_time, log_files, LogReader};
+use log::Level;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+const USAGE: &str = "usage: fastlog-cat [--level LEVEL] [--target TARGET] [--since TIME] [--until TIME] [--grep TEXT] [--no-color] <dir or file>...
+  --level   print records at LEVEL or more important (error,warn,info,debug,trace)
+  --target  print records whose module starts with TARGET
+  --since   print records at or after TIME, for example \"2021-01-01 00:00:00\"
+  --until   print records before TIME
+  --grep    print records contains TEXT";
+
+#[derive(Default)]
+struct Args {
+    level: Option<Level>,
+    target: Option<String>,
+    since: Option<NaiveDateTime>,
+    until: Option<NaiveDateTime>,
+    grep: Option<String>,
+    color: bool,
+    paths: Vec<String>,
+}
+
+fn parse_time(arg: &str) -> Result<NaiveDateTime, String> {
+    for fmt in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M"] {
+        if let Ok(v) = NaiveDateTime::parse_from_str(arg, fmt) {
+            return Ok(v);
+        }
+    }
+    if let Ok(v) = chrono::NaiveDate::parse_from_str(arg, "%Y-%m-%d") {
+        return Ok(v.and_hms_opt(0, 0, 0).unwrap());
+    }
+    Err(format!("bad time: {}", arg))
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        color: std::io::stdout().is_terminal(),
+        ..Default::default()
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("{} need a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Err(String::new()),
+            "--level" => {
+                let v = value("--level")?;
+                args.level = Some(Level::from_str(&v).map_err(|_| format!("bad level: {}", v))?);
+            }
+            "--target" => args.target = Some(value("--target")?),
+            "--since" => args.since = Some(parse_time(&value("--since")?)?),
+            "--until" => args.until = Some(parse_time(&value("--until")?)?),
+            "--grep" => args.grep = Some(value("--grep")?),
+            "--no-color" => args.color = false,
+            _ => args.paths.push(arg),
+        }
+    }
+    if args.paths.is_empty() {
+        return Err(String::new());
+    }
+    Ok(args)
+}
+
+fn color(level: Level) -> &'static str {
+    match level {
+        Level::Error => "\x1b[31m",
+        Level::Warn => "\x1b[33m",
+        Level::Info => "\x1b[32m",
+        Level::Debug => "\x1b[36m",
+        Level::Trace => "\x1b[90m",
+    }
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(v) => v,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("{}", e);
+            }
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+    let mut files = vec![];
+    for path in &args.paths {
+        if std::path::Path::new(path).is_dir() {
+            match log_files(path) {
+                Ok(v) => files.extend(v),
+                Err(e) => eprintln!("{}", e),
+            }
+        } else {
+            files.push(PathBuf::from(path));
+        }
+    }
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    //lines without a head (for example a backtrace) follow the record before
+    let mut print_record = false;
+    let mut record_level = None;
+    for line in LogReader::new(files) {
+        let line = match line {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+        let text = line.line.as_str();
+        if let Some(time) = line_time(text) {
+            let level = line_level(text);
+            record_level = level;
+            print_record = match (args.level, level) {
+                (Some(max), Some(level)) => level <= max,
+                _ => true,
+            } && match &args.target {
+                Some(target) => line_module(text).unwrap_or("").starts_with(target.as_str()),
+                None => true,
+            } && args.since.map(|v| time >= v).unwrap_or(true)
+                && args.until.map(|v| time < v).unwrap_or(true);
+        }
+        if !print_record {
+            continue;
+        }
+        if let Some(grep) = &args.grep {
+            if !text.contains(grep.as_str()) {
+                continue;
+            }
+        }
+        let r = match (args.color, record_level) {
+            (true, Some(level)) => writeln!(out, "{}{}\x1b[0m", color(level), text),
+            _ => writeln!(out, "{}", text),
+        };
+        if r.is_err() {
+            //stdout closed, for example `fastlog-cat logs/ | head`
+            break;
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::appender::FastLogRecord;
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// what a record does when the pipeline is over its memory budget
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BudgetPolicy {
+    /// drop every new record
+    DropNewest,
+    /// drop the records less severe than the level, the others still pass
+    DropBelow(log::Level),
+}
+
+struct Budget {
+    max_bytes: usize,
+    policy: BudgetPolicy,
+}
+
+//bytes of the queued records and pending pack jobs
+static USED: AtomicUsize = AtomicUsize::new(0);
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref BUDGET: RwLock<Option<Budget>> = RwLock::new(None);
+}
+
+/// cap the memory of the records waiting in the channels and batches and of the pending pack jobs,
+/// over `max_bytes` new records are dropped by `policy` and counted in Metrics::budget_dropped,
+/// so a logger that falls behind can not run its host out of memory
+pub fn set_memory_budget(max_bytes: usize, policy: BudgetPolicy) {
+    *BUDGET.write() = Some(Budget { max_bytes, policy });
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+pub fn clear_memory_budget() {
+    ENABLED.store(false, Ordering::SeqCst);
+    *BUDGET.write() = None;
+}
+
+/// bytes held by the pipeline now
+pub fn memory_used() -> usize {
+    USED.load(Ordering::Relaxed)
+}
+
+/// the heap and inline size of a queued record
+pub(crate) fn record_size(record: &FastLogRecord) -> usize {
+    std::mem::size_of::<FastLogRecord>()
+        + record.target.len()
+        + record.args.len()
+        + record.module_path.len()
+        + record.file.len()
+        + record.formated.len()
+        + record.kv.iter().map(|(k, v)| k.len() + value_size(v)).sum::<usize>()
+        + record.tags.iter().map(|v| v.len()).sum::<usize>()
+}
+
+//an estimate, the budget is checked for every record
+fn value_size(v: &serde_json::Value) -> usize {
+    match v {
+        serde_json::Value::String(s) => s.len(),
+        serde_json::Value::Array(a) => a.iter().map(value_size).sum(),
+        serde_json::Value::Object(o) => o.iter().map(|(k, v)| k.len() + value_size(v)).sum(),
+        _ => 8,
+    }
+}
+
+/// account a record entering the pipeline, false: it is over budget and must be dropped
+pub(crate) fn admit(record: &FastLogRecord) -> bool {
+    let size = record_size(record);
+    if ENABLED.load(Ordering::Relaxed) {
+        if let Some(budget) = BUDGET.read().as_ref() {
+            let over = USED.load(Ordering::Relaxed) + size > budget.max_bytes;
+            let drop = over
+                && match budget.policy {
+                    BudgetPolicy::DropNewest => true,
+                    BudgetPolicy::DropBelow(level) => record.level > level,
+                };
+            if drop {
+                crate::metrics::inc_budget_dropped();
+                return false;
+            }
+        }
+    }
+    USED.fetch_add(size, Ordering::Relaxed);
+    true
+}
+
+/// account memory which can not be dropped, for example a pending pack job
+pub(crate) fn charge(bytes: usize) {
+    USED.fetch_add(bytes, Ordering::Relaxed);
+}
+
+pub(crate) fn release(bytes: usize) {
+    USED.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| Some(v.saturating_sub(bytes)));
+}
@@ -0,0 +1,129 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::appender::FastLogRecord;
+#[cfg(not(target_arch = "wasm32"))]
+use std::cell::Cell;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+
+/// a blocked sender checks at this interval whether the pipeline still runs
+#[cfg(not(target_arch = "wasm32"))]
+const BLOCK_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+//set on the back worker: it drains the channel, a record it logs(from an appender) never waits for a place
+#[cfg(not(target_arch = "wasm32"))]
+may::coroutine_local!(static BACK_WORKER: Cell<bool> = Cell::new(false));
+
+/// mark the current coroutine as the back worker of a pipeline
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn set_back_worker() {
+    BACK_WORKER.with(|v| v.set(true));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn is_back_worker() -> bool {
+    BACK_WORKER.with(|v| v.get())
+}
+
+/// the capacity of the channel of a logger(see Config::channel). wasm dispatches on the caller, it is unbounded there
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub enum ChannelMode {
+    /// a burst of records is kept whatever its size(the default)
+    #[default]
+    Unbounded,
+    /// at most this many records wait for the appenders
+    Bounded(usize),
+}
+
+/// what a record does when a bounded channel is full
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub enum OverflowPolicy {
+    /// the logging thread(or coroutine) waits until the appenders caught up
+    #[default]
+    Block,
+    /// the new record is dropped
+    DropNewest,
+    /// the oldest record still waiting in the channel is dropped
+    DropOldest,
+}
+
+/// the records waiting in a bounded channel, shared by the sender and the pipeline
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct Bound {
+    pub(crate) cap: usize,
+    pub(crate) policy: OverflowPolicy,
+    //records sent and not yet dispatched or dropped
+    queued: AtomicUsize,
+    //OverflowPolicy::Block waits on it for free places, may's sync works in threads and coroutines
+    lock: may::sync::Mutex<()>,
+    space: may::sync::Condvar,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Bound {
+    pub(crate) fn new(mode: ChannelMode, policy: OverflowPolicy) -> Option<Bound> {
+        match mode {
+            ChannelMode::Unbounded => None,
+            ChannelMode::Bounded(cap) => Some(Bound {
+                cap: cap.max(1),
+                policy,
+                queued: AtomicUsize::new(0),
+                lock: may::sync::Mutex::new(()),
+                space: may::sync::Condvar::new(),
+            }),
+        }
+    }
+
+    /// take a place for a record, false: drop it(DropNewest, or the pipeline exited while blocked)
+    pub(crate) fn admit(&self, running: &AtomicBool) -> bool {
+        loop {
+            let queued = self.queued.load(Ordering::Relaxed);
+            if queued < self.cap || self.policy == OverflowPolicy::DropOldest {
+                if self
+                    .queued
+                    .compare_exchange_weak(queued, queued + 1, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    return true;
+                }
+                continue;
+            }
+            if self.policy == OverflowPolicy::DropNewest || !running.load(Ordering::Relaxed) {
+                return false;
+            }
+            let guard = self.lock.lock().unwrap();
+            //checked again under the lock, done() notifies under it
+            if self.queued.load(Ordering::Relaxed) >= self.cap {
+                let _ = self.space.wait_timeout(guard, BLOCK_CHECK_INTERVAL);
+            }
+        }
+    }
+
+    /// take a place whatever the policy, for records which must neither wait nor be dropped
+    /// (the internal records of the appenders, the drop summary)
+    pub(crate) fn force(&self) {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// `n` records left the channel(dispatched or dropped)
+    pub(crate) fn done(&self, n: usize) {
+        self.queued.fetch_sub(n, Ordering::Relaxed);
+        if self.policy == OverflowPolicy::Block {
+            let _guard = self.lock.lock().unwrap();
+            self.space.notify_all();
+        }
+    }
+
+    /// more records than the capacity wait, DropOldest drops the oldest ones
+    pub(crate) fn is_over(&self) -> bool {
+        self.queued.load(Ordering::Relaxed) > self.cap
+    }
+}
+
+/// a record was dropped by the OverflowPolicy
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn dropped(record: &FastLogRecord) {
+    crate::metrics::inc_channel_dropped();
+    crate::drop_summary::dropped(record.level);
+}
@@ -0,0 +1,207 @@
+use crate::appender::FastLogRecord;
+use parking_lot::{Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// the source of the record times, the rotation times and the rate limits.
+/// SystemClock by default, a MockClock makes tests of them deterministic
+pub trait Clock: Send + Sync {
+    /// the wall clock time
+    fn now(&self) -> SystemTime;
+    /// time on a clock which only moves forward, for elapsed times
+    fn monotonic(&self) -> Duration;
+}
+
+/// the clock of the os
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn monotonic(&self) -> Duration {
+        BASE.elapsed()
+    }
+}
+
+/// a clock which only moves when told, for tests:
+/// let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+/// fast_log::clock::set_clock(Arc::new(clock.clone()));
+/// clock.advance(Duration::from_secs(3600));
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    time: Arc<Mutex<(SystemTime, Duration)>>,
+}
+
+impl MockClock {
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            time: Arc::new(Mutex::new((start, Duration::ZERO))),
+        }
+    }
+
+    /// move the wall and the monotonic time forward
+    pub fn advance(&self, d: Duration) {
+        let mut time = self.time.lock();
+        time.0 += d;
+        time.1 += d;
+    }
+
+    /// set the wall time only, like a step of the system clock
+    pub fn set(&self, now: SystemTime) {
+        self.time.lock().0 = now;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        self.time.lock().0
+    }
+
+    fn monotonic(&self) -> Duration {
+        self.time.lock().1
+    }
+}
+
+static COARSE: AtomicBool = AtomicBool::new(false);
+//nanos since UNIX_EPOCH
+static CACHED: AtomicU64 = AtomicU64::new(0);
+//nanos since BASE, read with CACHED
+static CACHED_MONOTONIC: AtomicU64 = AtomicU64::new(0);
+//stops the refresh thread of an older enable_coarse_time
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+//millis
+static JUMP_THRESHOLD: AtomicU64 = AtomicU64::new(1000);
+//a clock of set_clock is installed
+static CUSTOM: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref BASE: Instant = Instant::now();
+    static ref CLOCK: RwLock<Option<Arc<dyn Clock>>> = RwLock::new(None);
+}
+
+/// take the times from `clock` instead of the system clock, process wide. also see Config::clock
+pub fn set_clock(clock: Arc<dyn Clock>) {
+    *CLOCK.write() = Some(clock);
+    CUSTOM.store(true, Ordering::SeqCst);
+}
+
+/// the system clock again
+pub fn reset_clock() {
+    CUSTOM.store(false, Ordering::SeqCst);
+    *CLOCK.write() = None;
+}
+
+/// the wall time of the records: the clock of set_clock, the cached time of enable_coarse_time
+/// or the system time
+pub fn now() -> SystemTime {
+    if CUSTOM.load(Ordering::Relaxed) {
+        if let Some(clock) = CLOCK.read().as_ref() {
+            return clock.now();
+        }
+    }
+    coarse_now().unwrap_or_else(SystemTime::now)
+}
+
+fn refresh() {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+    CACHED_MONOTONIC.store(BASE.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    CACHED.store(nanos, Ordering::Relaxed);
+}
+
+/// the records take a cached time, refreshed every `interval`(for example 1ms) by a thread,
+/// instead of reading the clock for every record. for millions of records per second
+/// where the clock calls show up, the record times are at most `interval` old
+pub fn enable_coarse_time(interval: Duration) {
+    refresh();
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    COARSE.store(true, Ordering::SeqCst);
+    std::thread::spawn(move || {
+        while GENERATION.load(Ordering::Relaxed) == generation {
+            std::thread::sleep(interval);
+            refresh();
+        }
+    });
+}
+
+/// read the clock for every record again
+pub fn disable_coarse_time() {
+    COARSE.store(false, Ordering::SeqCst);
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// the cached time if enable_coarse_time is on
+pub(crate) fn coarse_now() -> Option<SystemTime> {
+    if COARSE.load(Ordering::Relaxed) {
+        Some(UNIX_EPOCH + Duration::from_nanos(CACHED.load(Ordering::Relaxed)))
+    } else {
+        None
+    }
+}
+
+/// time since the first call on a monotonic clock, the clock of set_clock or the cached one with enable_coarse_time
+pub fn monotonic() -> Duration {
+    if CUSTOM.load(Ordering::Relaxed) {
+        if let Some(clock) = CLOCK.read().as_ref() {
+            return clock.monotonic();
+        }
+    }
+    if COARSE.load(Ordering::Relaxed) {
+        Duration::from_nanos(CACHED_MONOTONIC.load(Ordering::Relaxed))
+    } else {
+        BASE.elapsed()
+    }
+}
+
+/// a wall clock step(NTP, a manual change) or a suspend/resume moves the record time against the
+/// monotonic time, a move of at least `threshold` is a jump: the first record after it gets the
+/// kv `clock_jump_ms`(the signed jump) and an internal record "clock_jump" is logged. default 1s
+pub fn set_clock_jump_threshold(threshold: Duration) {
+    JUMP_THRESHOLD.store(threshold.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// finds wall clock jumps in the records of one pipeline, in the order they are dispatched
+#[derive(Default)]
+pub(crate) struct JumpDetector {
+    //wall millis - monotonic millis and monotonic millis of the newest record
+    last: Option<(i64, i64)>,
+}
+
+impl JumpDetector {
+    pub(crate) fn check(&mut self, record: &mut FastLogRecord) {
+        let monotonic = match record.monotonic {
+            Some(v) => v.as_millis() as i64,
+            None => return,
+        };
+        let wall = match record.now.duration_since(UNIX_EPOCH) {
+            Ok(v) => v.as_millis() as i64,
+            Err(e) => -(e.duration().as_millis() as i64),
+        };
+        let offset = wall - monotonic;
+        let (last_offset, last_monotonic) = match self.last {
+            Some(v) => v,
+            None => {
+                self.last = Some((offset, monotonic));
+                return;
+            }
+        };
+        let jump = offset - last_offset;
+        if jump.unsigned_abs() >= JUMP_THRESHOLD.load(Ordering::Relaxed) {
+            record.kv.push(("clock_jump_ms".to_string(), jump.into()));
+            //a record read before the jump and dispatched after it keeps the current offset
+            if monotonic >= last_monotonic {
+                crate::diagnostics::emit(
+                    log::Level::Warn,
+                    "clock_jump",
+                    format!("wall clock jumped {:+}ms", jump),
+                );
+            }
+        }
+        if monotonic >= last_monotonic {
+            self.last = Some((offset, monotonic));
+        }
+    }
+}
@@ -1,3 +1,8 @@
+/// version of the record structure in the json outputs(relay frames, json lines), the field "v".
+/// json without "v" is version 1 too, readers skip the fields a newer version adds
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Copy, Clone, Debug)]
 pub enum LogSize {
     KB(usize),
     MB(usize),
@@ -0,0 +1,136 @@
+use crate::appender::{FastLogRecord, RecordFormat};
+use std::fmt;
+use std::io::BufRead;
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-32(IEEE, the one of zip and gzip)
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for b in data {
+        crc = CRC_TABLE[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// bytes of "{len:08x} {crc:08x} "
+const HEADER_LEN: usize = 18;
+
+/// frame every record of the inner format as "{len:08x} {crc32:08x} {record}\n",
+/// len and crc are of the record text without the line end. the files stay readable as text,
+/// and CrcReader finds records torn by a crash or corrupted on disk
+pub struct CrcFormat<F: RecordFormat> {
+    inner: F,
+}
+
+impl<F: RecordFormat> CrcFormat<F> {
+    pub fn new(inner: F) -> Self {
+        Self { inner }
+    }
+}
+
+impl<F: RecordFormat> RecordFormat for CrcFormat<F> {
+    fn format(&self, record: &FastLogRecord, sink: &mut dyn fmt::Write) -> fmt::Result {
+        let mut body = String::new();
+        self.inner.format(record, &mut body)?;
+        let body = body.strip_suffix('\n').unwrap_or(&body);
+        writeln!(sink, "{:08x} {:08x} {}", body.len(), crc32(body.as_bytes()), body)
+    }
+}
+
+/// a frame of a file written with CrcFormat
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Frame {
+    /// a record with a good crc, without the line end
+    Record(String),
+    /// a bad header or crc at the byte offset, the reader goes on at the next line
+    Corrupt { offset: u64 },
+    /// the file ends inside a frame at the byte offset, for example a write torn by a crash
+    TornTail { offset: u64 },
+}
+
+/// read the frames of CrcFormat, for example
+/// for frame in CrcReader::new(BufReader::new(File::open("requests.log")?)) { ... }
+pub struct CrcReader<R: BufRead> {
+    inner: R,
+    offset: u64,
+    done: bool,
+}
+
+impl<R: BufRead> CrcReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            offset: 0,
+            done: false,
+        }
+    }
+}
+
+fn parse_header(header: &[u8]) -> Option<(usize, u32)> {
+    let header = std::str::from_utf8(header).ok()?;
+    if header.as_bytes()[8] != b' ' || header.as_bytes()[17] != b' ' {
+        return None;
+    }
+    let len = usize::from_str_radix(&header[0..8], 16).ok()?;
+    let crc = u32::from_str_radix(&header[9..17], 16).ok()?;
+    Some((len, crc))
+}
+
+impl<R: BufRead> Iterator for CrcReader<R> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        if self.done {
+            return None;
+        }
+        let offset = self.offset;
+        //a record may have line ends, the header tells its length
+        let mut frame = vec![];
+        loop {
+            let n = self.inner.read_until(b'\n', &mut frame).unwrap_or(0);
+            if n == 0 || frame.last() != Some(&b'\n') {
+                self.done = true;
+                return if frame.is_empty() { None } else { Some(Frame::TornTail { offset }) };
+            }
+            match frame.get(..HEADER_LEN).and_then(parse_header) {
+                //the frame is complete or too long
+                Some((len, _)) if frame.len() > HEADER_LEN + len => break,
+                Some(_) => {}
+                None => break,
+            }
+        }
+        self.offset += frame.len() as u64;
+        let (len, crc) = match frame.get(..HEADER_LEN).and_then(parse_header) {
+            Some(v) => v,
+            None => return Some(Frame::Corrupt { offset }),
+        };
+        if frame.len() != HEADER_LEN + len + 1 {
+            return Some(Frame::Corrupt { offset });
+        }
+        let body = &frame[HEADER_LEN..HEADER_LEN + len];
+        if crc32(body) != crc {
+            return Some(Frame::Corrupt { offset });
+        }
+        match std::str::from_utf8(body) {
+            Ok(v) => Some(Frame::Record(v.to_string())),
+            Err(_) => Some(Frame::Corrupt { offset }),
+        }
+    }
+}
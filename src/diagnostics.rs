@@ -0,0 +1,42 @@
+use crate::appender::{Command, FastLogRecord};
+use log::{Level, LevelFilter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// the target of the internal diagnostic records
+pub const INTERNAL_TARGET: &str = "fast_log::internal";
+
+//LevelFilter as usize, default Warn
+static LEVEL: AtomicUsize = AtomicUsize::new(2);
+
+/// the max level of the internal records(kv "event"):
+/// Warn: appender errors("appender_error", see health::report_error, a failed pack too), queue saturated("saturated").
+/// Info: rotations("rotate"), finished packs("pack"), queue drained("drained").
+/// default Warn, Off turns them off. they go to the appenders whatever the logger level is
+pub fn set_internal_level(level: LevelFilter) {
+    LEVEL.store(level as usize, Ordering::Relaxed);
+}
+
+pub fn internal_level() -> LevelFilter {
+    match LEVEL.load(Ordering::Relaxed) {
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        5 => LevelFilter::Trace,
+        _ => LevelFilter::Off,
+    }
+}
+
+/// send an internal record to the global logger if `level` is enabled
+pub(crate) fn emit(level: Level, event: &str, args: String) {
+    if level > internal_level() {
+        return;
+    }
+    let mut record = FastLogRecord::new_command(Command::CommandRecord);
+    record.level = level;
+    record.target = INTERNAL_TARGET.to_string();
+    record.module_path = INTERNAL_TARGET.to_string();
+    record.args = args.into();
+    record.kv.push(("event".to_string(), event.into()));
+    crate::fast_log::forward_internal(record);
+}
@@ -0,0 +1,81 @@
+use crate::appender::{Command, FastLogRecord};
+use chrono::{DateTime, Local};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// the target of the summary records
+pub const DROP_SUMMARY_TARGET: &str = "fast_log::drop_summary";
+
+struct Drops {
+    //by Level as usize - 1
+    counts: [u64; 5],
+    first: SystemTime,
+    last: SystemTime,
+}
+
+static PENDING: AtomicBool = AtomicBool::new(false);
+//millis
+static INTERVAL: AtomicU64 = AtomicU64::new(1000);
+
+lazy_static! {
+    static ref DROPS: Mutex<Drops> = Mutex::new(Drops {
+        counts: [0; 5],
+        first: SystemTime::UNIX_EPOCH,
+        last: SystemTime::UNIX_EPOCH,
+    });
+}
+
+/// at most one summary of the dropped records every `interval`, default 1s
+pub fn set_drop_summary_interval(interval: Duration) {
+    INTERVAL.store(interval.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// the pipeline dropped a record, for example over the memory budget
+pub(crate) fn dropped(level: log::Level) {
+    let now = crate::appender::now();
+    let mut drops = DROPS.lock();
+    if !PENDING.load(Ordering::Relaxed) {
+        drops.first = now;
+    }
+    drops.counts[level as usize - 1] += 1;
+    drops.last = now;
+    PENDING.store(true, Ordering::Relaxed);
+}
+
+/// a WARN record "dropped N records between T1 and T2 (by level: INFO=n, ...)" once the interval
+/// since the first drop passed
+pub(crate) fn take() -> Option<FastLogRecord> {
+    if !PENDING.load(Ordering::Relaxed) {
+        return None;
+    }
+    let now = crate::appender::now();
+    let mut drops = DROPS.lock();
+    let interval = Duration::from_millis(INTERVAL.load(Ordering::Relaxed));
+    if !PENDING.load(Ordering::Relaxed) || now.duration_since(drops.first).unwrap_or_default() < interval {
+        return None;
+    }
+    let counts = std::mem::take(&mut drops.counts);
+    PENDING.store(false, Ordering::Relaxed);
+    let total: u64 = counts.iter().sum();
+    let by_level: Vec<String> = log::Level::iter()
+        .zip(counts.iter())
+        .filter(|(_, n)| **n > 0)
+        .map(|(level, n)| format!("{}={}", level, n))
+        .collect();
+    let time = |t: SystemTime| DateTime::<Local>::from(t).format("%Y-%m-%d %H:%M:%S%.3f").to_string();
+    let mut record = FastLogRecord::new_command(Command::CommandRecord);
+    record.level = log::Level::Warn;
+    record.target = DROP_SUMMARY_TARGET.to_string();
+    record.module_path = DROP_SUMMARY_TARGET.to_string();
+    record.args = format!(
+        "dropped {} records between {} and {} (by level: {})",
+        total,
+        time(drops.first),
+        time(drops.last),
+        by_level.join(", ")
+    )
+    .into();
+    record.kv.push(("dropped".to_string(), total.into()));
+    Some(record)
+}
@@ -0,0 +1,137 @@
+use crate::appender::{Command, FastLogRecord};
+use crate::error::LogError;
+use crate::small_string::SmallString;
+use crate::trace::TraceContext;
+use std::time::SystemTime;
+
+/// build a record without the `log` facade, for bridging the events of other systems, for example
+/// fast_log::emit(LevelRecordBuilder::new(Level::Warn, "disk full").target("agent").time(event_time).kv("free", 0))
+pub struct LevelRecordBuilder {
+    record: FastLogRecord,
+}
+
+impl LevelRecordBuilder {
+    /// the time is now and the trace context is the one of the current thread
+    pub fn new(level: log::Level, args: impl Into<SmallString>) -> Self {
+        let mut record = FastLogRecord::new_command(Command::CommandRecord);
+        record.level = level;
+        record.args = args.into();
+        record.trace = crate::trace::current_trace_context();
+        record.tags = crate::tags::current_tags();
+        Self { record }
+    }
+
+    /// the target, also the module_path if it is not set
+    pub fn target(mut self, target: &str) -> Self {
+        if self.record.module_path.is_empty() || self.record.module_path == self.record.target {
+            self.record.module_path = target.to_string();
+        }
+        self.record.target = target.to_string();
+        self
+    }
+
+    pub fn module_path(mut self, module_path: &str) -> Self {
+        self.record.module_path = module_path.to_string();
+        self
+    }
+
+    pub fn file(mut self, file: &str, line: u32) -> Self {
+        self.record.file = file.to_string();
+        self.record.line = Some(line);
+        self
+    }
+
+    /// the time the event happened
+    pub fn time(mut self, time: SystemTime) -> Self {
+        self.record.now = time;
+        self.record.monotonic = None;
+        self
+    }
+
+    pub fn kv(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.record.kv.push((key.to_string(), value.into()));
+        self
+    }
+
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.record.tags.push(tag.to_string());
+        self
+    }
+
+    pub fn trace(mut self, trace: Option<TraceContext>) -> Self {
+        self.record.trace = trace;
+        self
+    }
+
+    pub fn build(self) -> FastLogRecord {
+        self.record
+    }
+}
+
+/// enqueue a line formatted elsewhere, for example the stdout of a child process.
+/// the RecordFormat is skipped and the appenders write the bytes as is(invalid utf-8 is replaced), ending with '\n'
+pub fn emit_raw(level: log::Level, target: &str, data: &[u8]) -> Result<(), LogError> {
+    let mut formated = String::from_utf8_lossy(data).into_owned();
+    if !formated.ends_with('\n') {
+        formated.push('\n');
+    }
+    let mut record = LevelRecordBuilder::new(level, formated.trim_end()).target(target).build();
+    record.formated = formated.into();
+    crate::fast_log::send_record(record)
+}
+
+/// send the record to the pipeline. the level, mute and ignore targets apply, the Filter does not(it reads log::Record)
+pub fn emit(builder: LevelRecordBuilder) -> Result<(), LogError> {
+    crate::fast_log::send_record(builder.build())
+}
+
+/// a std::io::Write sink logging every written line, for example for libraries writing access logs:
+/// `let mut w = fast_log::writer(Level::Info, "access"); writeln!(w, "GET / 200")?;`
+pub fn writer(level: log::Level, target: &str) -> LogWriter {
+    LogWriter {
+        level,
+        target: target.to_string(),
+        buf: vec![],
+    }
+}
+
+/// see writer(), a line without '\n' is logged on flush or drop
+pub struct LogWriter {
+    level: log::Level,
+    target: String,
+    buf: Vec<u8>,
+}
+
+impl LogWriter {
+    fn emit_line(&self, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        emit(LevelRecordBuilder::new(self.level, line.trim_end_matches('\r')).target(&self.target));
+    }
+}
+
+impl std::io::Write for LogWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if let Some(end) = self.buf.iter().rposition(|v| *v == b'\n') {
+            let lines: Vec<u8> = self.buf.drain(..=end).collect();
+            for line in lines[..end].split(|v| *v == b'\n') {
+                self.emit_line(line);
+            }
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.buf.is_empty() {
+            let line = std::mem::take(&mut self.buf);
+            self.emit_line(&line);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LogWriter {
+    fn drop(&mut self) {
+        std::io::Write::flush(self);
+    }
+}
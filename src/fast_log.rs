@@ -1,115 +1,644 @@
-use std::sync::atomic::AtomicI32;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
 use may::sync::mpsc::{Receiver, Sender};
-use log::{Level, Metadata, Record};
+#[cfg(target_arch = "wasm32")]
+use crossbeam::channel::{Receiver, Sender};
+use log::{LevelFilter, Metadata, Record};
 use parking_lot::RwLock;
 
-use crate::appender::{Command, FastLogFormatRecord, FastLogRecord, LogAppender, RecordFormat};
-use crate::consts::LogSize;
+use crate::appender::{Command, FastLogFormatRecord, FastLogRecord, LogAppender, PipelineSender, RecordFormat};
+use crate::channel::{ChannelMode, OverflowPolicy};
 use crate::error::LogError;
 use crate::filter::{Filter, NoFilter};
 use crate::plugin::console::ConsoleAppender;
 use crate::plugin::file::FileAppender;
-use crate::plugin::file_split::{FileSplitAppender, RollingType, Packer};
+use crate::plugin::file_split::{FileSplitAppender, RollingType, Packer, SplitType};
+use crate::fast_logger::{Config, FastLogger};
+use crate::guard::FastLogGuard;
+use crate::small_string::SmallString;
 use crate::wait::FastLogWaitGroup;
 use std::result::Result::Ok;
-use std::time::{SystemTime, Duration};
+use std::time::Duration;
+use std::sync::Arc;
+use std::str::FromStr;
 use std::sync::mpsc::SendError;
+#[cfg(not(target_arch = "wasm32"))]
 use std::collections::VecDeque;
-use may::go;
+#[cfg(not(target_arch = "wasm32"))]
+use may::go_with;
 
 lazy_static! {
-    static ref LOG_SENDER: RwLock<Option<LoggerSender>> = RwLock::new(Option::None);
+    static ref LOG_SENDER: RwLock<Option<Arc<LoggerSender>>> = RwLock::new(Option::None);
+    static ref IGNORE_TARGETS: RwLock<Vec<String>> = RwLock::new(default_ignore_targets());
+    static ref APPENDER_NAMES: RwLock<Vec<String>> = RwLock::new(vec![]);
+}
+
+/// type names of the appenders running in the pipeline
+pub fn appender_names() -> Vec<String> {
+    APPENDER_NAMES.read().clone()
+}
+
+/// targets never logged, so the logger can not feed back into itself
+/// (fast_log internals, the may runtime it runs on and the clients of the enabled appenders)
+pub fn default_ignore_targets() -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut targets = vec!["fast_log".to_string(), "may".to_string()];
+    //ureq logs every request of the http appenders at debug level
+    #[cfg(feature = "http")]
+    targets.push("ureq".to_string());
+    #[cfg(feature = "tls")]
+    targets.push("rustls".to_string());
+    #[cfg(feature = "amqp")]
+    targets.push("amiquip".to_string());
+    #[cfg(feature = "mongo")]
+    targets.push("mongodb".to_string());
+    targets
+}
+
+/// replace the self-suppression list. a record is dropped when its target
+/// equals an entry or is a submodule of it, for example "may" drops "may::io::sys::select"
+pub fn set_ignore_targets(targets: Vec<String>) {
+    *IGNORE_TARGETS.write() = targets;
+}
+
+/// append a target to the self-suppression list
+pub fn add_ignore_target(target: &str) {
+    IGNORE_TARGETS.write().push(target.to_string());
+}
+
+pub fn get_ignore_targets() -> Vec<String> {
+    IGNORE_TARGETS.read().clone()
+}
+
+pub(crate) fn is_ignore_target(target: &str) -> bool {
+    for x in IGNORE_TARGETS.read().iter() {
+        if target.starts_with(x.as_str())
+            && (target.len() == x.len() || target[x.len()..].starts_with("::"))
+        {
+            return true;
+        }
+    }
+    false
 }
 
 pub struct LoggerSender {
     pub filter: Box<dyn Filter>,
     pub inner: Sender<FastLogRecord>,
+    /// false once the pipeline exited
+    running: Arc<AtomicBool>,
+    /// the capacity of a bounded channel, see Config::channel
+    #[cfg(not(target_arch = "wasm32"))]
+    bound: Option<Arc<crate::channel::Bound>>,
+    /// done once the pipeline exited, its other clone is on the back worker. taken by shutdown
+    #[cfg(not(target_arch = "wasm32"))]
+    stopped: parking_lot::Mutex<Option<FastLogWaitGroup>>,
+    /// wasm runs the pipeline of the sender on the caller
+    #[cfg(target_arch = "wasm32")]
+    pipeline: parking_lot::Mutex<Option<SyncPipeline>>,
 }
 
 impl LoggerSender {
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn new(filter: Box<dyn Filter>) -> (Self, Receiver<FastLogRecord>) {
         let (s, r) = may::sync::mpsc::channel();
-        (Self { inner: s, filter }, r)
+        (
+            Self {
+                inner: s,
+                filter,
+                running: Arc::new(AtomicBool::new(true)),
+                bound: None,
+                stopped: parking_lot::Mutex::new(None),
+            },
+            r,
+        )
+    }
+    #[cfg(target_arch = "wasm32")]
+    pub fn new(filter: Box<dyn Filter>) -> (Self, Receiver<FastLogRecord>) {
+        let (s, r) = crossbeam::channel::unbounded();
+        (
+            Self {
+                inner: s,
+                filter,
+                running: Arc::new(AtomicBool::new(true)),
+                pipeline: parking_lot::Mutex::new(None),
+            },
+            r,
+        )
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn send(&self, data: FastLogRecord) -> Result<(), SendError<FastLogRecord>> {
+        let record = data.command.eq(&Command::CommandRecord);
+        if record && !self.admit(&data, true) {
+            return Ok(());
+        }
+        self.inner.send(data)?;
+        if record {
+            crate::backpressure::enqueued();
+        }
+        Ok(())
+    }
+
+    /// an internal record, it can come from an appender on the back worker, so it never waits
+    /// for a place in a bounded channel. false: the pipeline is closed
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn send_internal(&self, data: FastLogRecord) -> bool {
+        if !self.admit(&data, false) {
+            return true;
+        }
+        let sent = self.inner.send(data).is_ok();
+        if sent {
+            crate::backpressure::enqueued();
+        }
+        sent
+    }
+
+    /// a place in the bounded channel and the memory budget for a record, false: it is dropped
+    #[cfg(not(target_arch = "wasm32"))]
+    fn admit(&self, data: &FastLogRecord, wait: bool) -> bool {
+        self.send_drop_summary();
+        if let Some(bound) = &self.bound {
+            //the back worker would wait for itself
+            if !wait || crate::channel::is_back_worker() {
+                bound.force();
+            } else if !bound.admit(&self.running) {
+                crate::channel::dropped(data);
+                return false;
+            }
+        }
+        if !crate::budget::admit(data) {
+            if let Some(bound) = &self.bound {
+                bound.done(1);
+            }
+            crate::drop_summary::dropped(data.level);
+            return false;
+        }
+        true
     }
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn send_internal(&self, data: FastLogRecord) -> bool {
+        self.send(data).is_ok()
+    }
+    /// wasm has no threads, the record is handled on the caller
+    #[cfg(target_arch = "wasm32")]
     pub fn send(&self, data: FastLogRecord) -> Result<(), SendError<FastLogRecord>> {
-        self.inner.send(data)
+        let record = data.command.eq(&Command::CommandRecord);
+        if record {
+            self.send_drop_summary();
+            if !crate::budget::admit(&data) {
+                crate::drop_summary::dropped(data.level);
+                return Ok(());
+            }
+        }
+        self.inner.send(data).map_err(|e| SendError(e.into_inner()))?;
+        if record {
+            crate::backpressure::enqueued();
+        }
+        sync_dispatch(&self.pipeline, &self.running);
+        Ok(())
+    }
+}
+
+impl LoggerSender {
+    /// the summary of the dropped records goes before the next record, it is never dropped
+    fn send_drop_summary(&self) {
+        if let Some(summary) = crate::drop_summary::take() {
+            crate::budget::charge(crate::budget::record_size(&summary));
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(bound) = &self.bound {
+                bound.force();
+            }
+            if self.inner.send(summary).is_ok() {
+                crate::backpressure::enqueued();
+            }
+        }
     }
 }
 
-fn set_log(level: log::Level, filter: Box<dyn Filter>) -> Receiver<FastLogRecord> {
-    LOGGER.set_level(level);
-    let mut w = LOG_SENDER.write();
-    let (log, recv) = LoggerSender::new(filter);
-    *w = Some(log);
-    return recv;
+/// records per LogAppender::do_logs call
+pub const MAX_BATCH_SIZE: usize = 1024;
+
+/// format the records and pass them to the appenders in batches
+fn dispatch_records(format: &dyn RecordFormat, appenders: &[Box<dyn LogAppender>], records: &mut [FastLogRecord]) {
+    for chunk in records.chunks_mut(MAX_BATCH_SIZE) {
+        //the sizes admitted by LoggerSender::send, before formatting
+        let size: usize = chunk.iter().map(crate::budget::record_size).sum();
+        let subscribed = crate::subscriber::has_subscribers();
+        for record in chunk.iter_mut() {
+            //set by emit_raw: already formatted. a record no appender accepts is not formatted
+            if record.formated.is_empty() && (subscribed || appenders.iter().any(|x| x.accepts(record))) {
+                crate::spill::apply(record);
+                format.do_format(record);
+            }
+        }
+        for x in appenders {
+            x.do_logs(chunk);
+        }
+        for record in chunk.iter() {
+            crate::subscriber::publish(record);
+        }
+        crate::backpressure::dequeued(chunk.len());
+        crate::budget::release(size);
+    }
+}
+
+/// format the record and send it to appenders, run on the back worker
+fn dispatch(format: &dyn RecordFormat, appenders: &[Box<dyn LogAppender>], data: &mut FastLogRecord) {
+    if data.command.eq(&Command::CommandRecord) && data.formated.is_empty() {
+        format.do_format(data);
+    }
+    dispatch_formatted(appenders, data);
+}
+
+/// send a formatted record or a command to appenders, the hooks of the command are called
+pub(crate) fn dispatch_formatted(appenders: &[Box<dyn LogAppender>], data: &mut FastLogRecord) {
+    for x in appenders {
+        x.do_log(data);
+        match data.command {
+            Command::CommandFlush => x.flush(),
+            Command::CommandReopen | Command::CommandRotate => x.on_rotate(),
+            Command::CommandConfig => x.on_config_change(),
+            _ => {}
+        }
+    }
+    if data.command.eq(&Command::CommandRecord) {
+        crate::subscriber::publish(data);
+    }
+    if let Some(ack) = data.flush_ack.take() {
+        ack.send(());
+    }
+}
+
+/// the pipeline of wasm, without coroutines records are dispatched on the caller
+#[cfg(target_arch = "wasm32")]
+struct SyncPipeline {
+    recv: Receiver<FastLogRecord>,
+    log_stack: Vec<FastLogRecord>,
+    appenders: Vec<Box<dyn LogAppender>>,
+    format: Box<dyn RecordFormat>,
+    //records dispatched, for the session stop record
+    written: u64,
+}
+
+#[cfg(target_arch = "wasm32")]
+fn sync_dispatch(pipeline: &parking_lot::Mutex<Option<SyncPipeline>>, running: &AtomicBool) {
+    //an appender logging inside dispatch: the running loop handles it
+    if let Some(mut pipeline) = pipeline.try_lock() {
+        let mut exit = false;
+        if let Some(p) = pipeline.as_mut() {
+            while let Ok(mut s) = p.recv.try_recv() {
+                if s.command.eq(&Command::CommandExit) {
+                    //the records before the exit are written first
+                    dispatch_records(p.format.as_ref(), &p.appenders, &mut p.log_stack);
+                    p.written += p.log_stack.len() as u64;
+                    p.log_stack.clear();
+                    if let Some(mut record) = crate::session::stopping(p.written) {
+                        dispatch(p.format.as_ref(), &p.appenders, &mut record);
+                    }
+                    for x in &p.appenders {
+                        x.shutdown();
+                    }
+                    exit = true;
+                    break;
+                } else if s.command.ne(&Command::CommandRecord) {
+                    dispatch_records(p.format.as_ref(), &p.appenders, &mut p.log_stack);
+                    p.written += p.log_stack.len() as u64;
+                    p.log_stack.clear();
+                    dispatch(p.format.as_ref(), &p.appenders, &mut s);
+                } else {
+                    p.log_stack.push(s);
+                }
+            }
+        }
+        if exit {
+            *pipeline = None;
+            running.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// start a pipeline(channels, workers) running the appenders, the records sent to the returned sender go to it
+pub(crate) fn start_pipeline(
+    appenders: Vec<Box<dyn LogAppender>>,
+    filter: Box<dyn Filter>,
+    format: Box<dyn RecordFormat>,
+    level: LevelFilter,
+    channel: (ChannelMode, OverflowPolicy),
+    wait_group: &FastLogWaitGroup,
+) -> LoggerSender {
+    #[allow(unused_mut)]
+    let (mut sender, recv) = LoggerSender::new(filter);
+    //commands of the appenders(split timers, rotations) go into this pipeline
+    #[cfg(not(target_arch = "wasm32"))]
+    let commands = {
+        let inner = parking_lot::Mutex::new(sender.inner.clone());
+        PipelineSender::new(move |record| inner.lock().send(record).is_ok())
+    };
+    #[cfg(target_arch = "wasm32")]
+    let commands = {
+        let inner = sender.inner.clone();
+        PipelineSender::new(move |record| inner.send(record).is_ok())
+    };
+    for x in &appenders {
+        x.attach(&commands);
+    }
+    let names: Vec<String> = appenders.iter().map(|x| x.type_name().to_string()).collect();
+    let start = crate::session::started(level, &names);
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        sender.bound = crate::channel::Bound::new(channel.0, channel.1).map(Arc::new);
+        let stopped = FastLogWaitGroup::new();
+        spawn_pipeline(
+            recv,
+            appenders,
+            format,
+            start,
+            sender.running.clone(),
+            sender.bound.clone(),
+            wait_group,
+            stopped.clone(),
+        );
+        *sender.stopped.lock() = Some(stopped);
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        //the records are dispatched on the caller
+        let _ = channel;
+        for x in &appenders {
+            x.on_init();
+        }
+        if let Some(mut record) = start {
+            dispatch(format.as_ref(), &appenders, &mut record);
+        }
+        *sender.pipeline.lock() = Some(SyncPipeline {
+            recv,
+            log_stack: Vec::with_capacity(16),
+            appenders,
+            format,
+            written: 0,
+        });
+    }
+    sender
+}
+
+/// the sender of the global logger
+pub(crate) fn global_sender() -> Option<Arc<LoggerSender>> {
+    LOG_SENDER.read().clone()
+}
+
+/// the global logger is installed and its pipeline did not exit
+pub fn is_running() -> bool {
+    LOG_SENDER.read().as_ref().map(|v| v.running.load(Ordering::Relaxed)).unwrap_or(false)
+}
+
+/// make the sender of a FastLogger the global logger
+pub(crate) fn install(sender: Arc<LoggerSender>, level: LevelFilter, appender_names: Vec<String>) -> Result<(), LogError> {
+    *APPENDER_NAMES.write() = appender_names;
+    LOGGER.set_level_filter(level);
+    *LOG_SENDER.write() = Some(sender);
+    log::set_logger(&LOGGER)
+        .map(|()| log::set_max_level(max_level(level)))
+        .map_err(LogError::from)
 }
 
 pub struct Logger {
     level: AtomicI32,
+    muted: AtomicBool,
 }
 
 impl Logger {
+    pub(crate) fn new(level: LevelFilter) -> Self {
+        Self {
+            level: AtomicI32::new(level as i32),
+            muted: AtomicBool::new(false),
+        }
+    }
+
     pub fn set_level(&self, level: log::Level) {
+        self.set_level_filter(level.to_level_filter());
+    }
+
+    pub fn set_level_filter(&self, level: LevelFilter) {
         self.level
             .swap(level as i32, std::sync::atomic::Ordering::Relaxed);
     }
 
-    pub fn get_level(&self) -> log::Level {
+    pub fn get_level(&self) -> LevelFilter {
         match self.level.load(std::sync::atomic::Ordering::Relaxed) {
-            1 => Level::Error,
-            2 => Level::Warn,
-            3 => Level::Info,
-            4 => Level::Debug,
-            5 => Level::Trace,
-            _ => panic!("error log level!"),
+            1 => LevelFilter::Error,
+            2 => LevelFilter::Warn,
+            3 => LevelFilter::Info,
+            4 => LevelFilter::Debug,
+            5 => LevelFilter::Trace,
+            _ => LevelFilter::Off,
         }
     }
+
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.store(muted, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted.load(std::sync::atomic::Ordering::Relaxed)
+    }
 }
 
 impl log::Log for Logger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= self.get_level()
+        !self.is_muted()
+            && metadata.level() <= log::STATIC_MAX_LEVEL
+            && metadata.level() <= self.get_level().max(crate::scoped_level::scope_level())
     }
     fn log(&self, record: &Record) {
+        //a fatal record which was not sent(muted, filtered, paused...) does not stop the process
+        let fatal = record.level() == log::Level::Error && record.target() == crate::fatal::FATAL_TARGET;
+        if self.log_record(record) && fatal {
+            crate::fatal::die();
+        }
+    }
+    fn flush(&self) {}
+}
+
+impl Logger {
+    /// true if the record was sent to the pipeline
+    fn log_record(&self, record: &Record) -> bool {
+        if self.is_muted() {
+            return false;
+        }
+        if record.level() <= crate::flight_recorder::level() && !is_ignore_target(record.target()) {
+            crate::flight_recorder::record(new_record(record));
+        }
+        if !log::Log::enabled(self, record.metadata()) {
+            return false;
+        }
+        crate::adaptive::on_record(record.level());
         //send
         if let Some(sender) = LOG_SENDER.read().as_ref() {
             if !sender.filter.filter(record) {
-                if let Some(v) = record.module_path() {
-                    if v == "may::io::sys::select" {
-                        return;
-                    }
+                if is_ignore_target(record.target()) {
+                    return false;
                 }
-                let fast_log_record = FastLogRecord {
-                    command: Command::CommandRecord,
-                    level: record.level(),
-                    target: record.metadata().target().to_string(),
-                    args: record.args().to_string(),
-                    module_path: record.module_path().unwrap_or_default().to_string(),
-                    file: record.file().unwrap_or_default().to_string(),
-                    line: record.line().clone(),
-                    now: SystemTime::now(),
-                    formated: String::new(),
+                let record = match crate::pause::hold(new_record(record)) {
+                    Some(v) => v,
+                    None => return false,
                 };
-                sender.send(fast_log_record);
+                if sender.send(record).is_ok() {
+                    crate::metrics::inc_logged();
+                    return true;
+                }
+                crate::metrics::inc_send_fail();
             }
         }
+        false
     }
-    fn flush(&self) {}
+}
+
+/// send a record built without the `log` facade, see emit()
+pub(crate) fn send_record(record: FastLogRecord) -> Result<(), LogError> {
+    if LOGGER.is_muted() || is_ignore_target(&record.target) {
+        return Ok(());
+    }
+    if record.level <= crate::flight_recorder::level() {
+        crate::flight_recorder::record(record.clone());
+    }
+    if record.level > LOGGER.get_level().max(crate::scoped_level::scope_level()) {
+        return Ok(());
+    }
+    crate::adaptive::on_record(record.level);
+    match crate::pause::hold(record) {
+        Some(record) => forward(record),
+        None => Ok(()),
+    }
+}
+
+/// send a record to the global pipeline
+pub(crate) fn forward(record: FastLogRecord) -> Result<(), LogError> {
+    if let Some(sender) = LOG_SENDER.read().as_ref() {
+        if sender.send(record).is_ok() {
+            crate::metrics::inc_logged();
+            return Ok(());
+        }
+        crate::metrics::inc_send_fail();
+    }
+    Err(LogError::E("[fast_log] emit fail!".to_string()))
+}
+
+/// send an internal record(see diagnostics) to the global pipeline. it can run inside LoggerSender::send,
+/// so the sender lock is taken recursive
+pub(crate) fn forward_internal(record: FastLogRecord) {
+    if let Some(sender) = LOG_SENDER.read_recursive().as_ref() {
+        if sender.send_internal(record) {
+            crate::metrics::inc_logged();
+        } else {
+            crate::metrics::inc_send_fail();
+        }
+    }
+}
+
+pub(crate) fn new_record(record: &Record) -> FastLogRecord {
+    FastLogRecord {
+        command: Command::CommandRecord,
+        level: record.level(),
+        target: record.metadata().target().to_string(),
+        args: SmallString::from_args(record.args()),
+        module_path: record.module_path().unwrap_or_default().to_string(),
+        file: record.file().unwrap_or_default().to_string(),
+        line: record.line().clone(),
+        now: crate::appender::now(),
+        monotonic: Some(crate::appender::monotonic()),
+        formated: SmallString::new(),
+        flush_ack: None,
+        trace: crate::trace::current_trace_context(),
+        kv: crate::kv::collect(record.key_values()),
+        tags: crate::tags::current_tags(),
+    }
+}
+
+/// the `log` max level for a logger level, raised while the flight recorder runs, a with_level guard
+/// or a FastLogger instance is alive
+pub(crate) fn max_level(level: LevelFilter) -> LevelFilter {
+    level
+        .max(crate::flight_recorder::level())
+        .max(crate::scoped_level::max_level())
+        .max(crate::fast_logger::max_level())
 }
 
 static LOGGER: Logger = Logger {
     level: AtomicI32::new(1),
+    muted: AtomicBool::new(false),
 };
 
+/// the global logger, for example `fast_log::logger().set_level_filter(LevelFilter::Off)`
+pub fn logger() -> &'static Logger {
+    &LOGGER
+}
+
+/// change the level of the global logger at runtime, for example from a signal handler or an admin endpoint.
+/// the `log` max level follows and the appenders are told(LogAppender::on_config_change)
+pub fn set_level(level: log::Level) {
+    set_level_filter(level.to_level_filter());
+}
+
+/// set_level, LevelFilter::Off stops every record
+pub fn set_level_filter(level: LevelFilter) {
+    LOGGER.set_level_filter(level);
+    log::set_max_level(max_level(level));
+    config_changed();
+}
+
+/// the level of the global logger
+pub fn get_level() -> LevelFilter {
+    LOGGER.get_level()
+}
+
+/// stop all records at the producer, the pipeline and appenders stay alive
+pub fn mute() {
+    LOGGER.set_muted(true);
+}
+
+/// resume logging after mute()
+pub fn unmute() {
+    LOGGER.set_muted(false);
+}
+
+pub fn is_muted() -> bool {
+    LOGGER.is_muted()
+}
+
+/// a colored console logger for small binaries and examples, the level is RUST_LOG(for example "debug") or Info
+pub fn quick_init() -> Result<FastLogGuard, LogError> {
+    let level = std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|v| log::Level::from_str(v.trim()).ok())
+        .unwrap_or(log::Level::Info);
+    quick_init_level(level)
+}
+
+/// a colored console logger with the level
+pub fn quick_init_level(level: log::Level) -> Result<FastLogGuard, LogError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let appender: Box<dyn LogAppender> = Box::new(crate::plugin::console::ColorConsoleAppender::new());
+    #[cfg(target_arch = "wasm32")]
+    let appender: Box<dyn LogAppender> = Box::new(ConsoleAppender {});
+    init_custom_log(
+        vec![appender],
+        level,
+        Box::new(NoFilter {}),
+        Box::new(FastLogFormatRecord::new()),
+    )
+}
+
 /// initializes the log file path
 /// log_file_path:  example->  "test.log"
 /// channel_cup: example -> 1000
+/// format: None is FastLogFormatRecord, Some(Box::new(JsonFormatRecord::new())) writes json lines
+/// return FastLogGuard: keep it alive, on drop it flushes and shuts down the logger
 pub fn init_log(
     log_file_path: &str,
     level: log::Level,
     mut filter: Option<Box<dyn Filter>>,
+    format: Option<Box<dyn RecordFormat>>,
     debug_mode: bool,
-) -> Result<FastLogWaitGroup, LogError> {
+) -> Result<FastLogGuard, LogError> {
     let mut appenders: Vec<Box<dyn LogAppender>> = vec![Box::new(FileAppender::new(log_file_path))];
     if debug_mode {
         appenders.push(Box::new(ConsoleAppender {}));
@@ -122,25 +651,29 @@ pub fn init_log(
         appenders,
         level,
         log_filter,
-        Box::new(FastLogFormatRecord::new()),
+        format.unwrap_or_else(|| Box::new(FastLogFormatRecord::new())),
     );
 }
 
 /// initializes the log file path
 /// log_dir_path:  example->  "log/"
-/// max_temp_size: do zip if temp log full
+/// max_temp_size: do zip if temp log full, or a SplitType, for example SplitType::ByTime(Duration::from_secs(86400)) at midnight
 /// allow_zip_compress: zip compress log file
 /// filter: log filter
+/// format: None is FastLogFormatRecord, Some(Box::new(JsonFormatRecord::new())) writes json lines
 /// packer: you can use ZipPacker or LZ4Packer or custom your Packer
+/// return FastLogGuard: keep it alive, on drop it flushes and shuts down the logger
+#[allow(clippy::too_many_arguments)]
 pub fn init_split_log(
     log_dir_path: &str,
-    max_temp_size: LogSize,
+    max_temp_size: impl Into<SplitType>,
     rolling_type: RollingType,
     level: log::Level,
     mut filter: Option<Box<dyn Filter>>,
+    format: Option<Box<dyn RecordFormat>>,
     packer: Box<dyn Packer>,
     allow_console_log: bool,
-) -> Result<FastLogWaitGroup, LogError> {
+) -> Result<FastLogGuard, LogError> {
     let mut appenders: Vec<Box<dyn LogAppender>> = vec![Box::new(FileSplitAppender::new(
         log_dir_path,
         max_temp_size,
@@ -159,7 +692,7 @@ pub fn init_split_log(
         appenders,
         level,
         log_filter,
-        Box::new(FastLogFormatRecord::new()),
+        format.unwrap_or_else(|| Box::new(FastLogFormatRecord::new())),
     );
 }
 
@@ -168,60 +701,138 @@ pub fn init_custom_log(
     level: log::Level,
     filter: Box<dyn Filter>,
     format: Box<dyn RecordFormat>,
-) -> Result<FastLogWaitGroup, LogError> {
-    if appenders.is_empty() {
-        return Err(LogError::from("[fast_log] appenders can not be empty!"));
-    }
-    let wait_group = FastLogWaitGroup::new();
-    let main_recv = set_log(level, filter);
+) -> Result<FastLogGuard, LogError> {
+    let config = Config {
+        appenders,
+        level: level.to_level_filter(),
+        filter,
+        format,
+        channel: ChannelMode::Unbounded,
+        overflow: OverflowPolicy::Block,
+        #[cfg(not(target_arch = "wasm32"))]
+        clock: None,
+    };
+    FastLogger::build(config)?.install()
+}
+
+/// a record of the main worker dropped by OverflowPolicy::DropOldest
+#[cfg(not(target_arch = "wasm32"))]
+fn drop_queued(bound: &crate::channel::Bound, record: &FastLogRecord) {
+    bound.done(1);
+    crate::budget::release(crate::budget::record_size(record));
+    crate::backpressure::dequeued(1);
+    crate::channel::dropped(record);
+}
+
+/// stack size (in words) of the back worker coroutine which runs the appenders
+pub const BACK_WORKER_STACK_SIZE: usize = 0x20000;
+
+/// stack size (in words) of the main coroutine, the default stack overflows in debug builds
+/// while it drains a large batch of buffered records
+pub const MAIN_WORKER_STACK_SIZE: usize = 0x4000;
+
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(clippy::too_many_arguments)]
+fn spawn_pipeline(
+    main_recv: Receiver<FastLogRecord>,
+    appenders: Vec<Box<dyn LogAppender>>,
+    format: Box<dyn RecordFormat>,
+    start: Option<FastLogRecord>,
+    running: Arc<AtomicBool>,
+    bound: Option<Arc<crate::channel::Bound>>,
+    wait_group: &FastLogWaitGroup,
+    stopped: FastLogWaitGroup,
+) {
     let (back_sender, back_recv) = may::sync::mpsc::channel();
     //main recv data
     let wait_group_main = wait_group.clone();
-    go!(move ||{
+    let main_bound = bound.clone();
+    go_with!(MAIN_WORKER_STACK_SIZE, move || {
         let mut log_stack = VecDeque::<FastLogRecord>::with_capacity(16);
         loop {
             let data = main_recv.recv();
             if data.is_ok() {
                 let mut s: FastLogRecord = data.unwrap();
                 if s.command.eq(&Command::CommandExit) {
-                    back_sender.send(s);
+                    //the records before the exit are written first
+                    if !log_stack.is_empty() {
+                        back_sender.send(Vec::from(std::mem::take(&mut log_stack)));
+                    }
+                    back_sender.send(vec![s]);
                     drop(wait_group_main);
                     break;
-                } else if s.command.eq(&Command::CommandFlush) {
-                    while let Some(log_record) = log_stack.pop_front() {
-                        back_sender.send(log_record);
+                } else if s.command.ne(&Command::CommandRecord) {
+                    //the records before the command go as one batch
+                    if !log_stack.is_empty() {
+                        back_sender.send(Vec::from(std::mem::replace(&mut log_stack, VecDeque::with_capacity(16))));
                     }
+                    back_sender.send(vec![s]);
                 } else {
                     log_stack.push_back(s);
+                    if let Some(bound) = &main_bound {
+                        while bound.is_over() {
+                            match log_stack.pop_front() {
+                                Some(oldest) => drop_queued(bound, &oldest),
+                                None => break,
+                            }
+                        }
+                        //a full channel does not wait for a flush, the blocked senders go on once it is written
+                        if log_stack.len() >= bound.cap {
+                            back_sender.send(Vec::from(std::mem::replace(&mut log_stack, VecDeque::with_capacity(16))));
+                        }
+                    }
                 }
             }
         }
     });
     let wait_group_back = wait_group.clone();
-    //back recv data
-    go!(move || {
-        loop {
-            //recv
+    //back recv data, appenders(compress, encrypt, network...) need a bigger stack than a default coroutine
+    go_with!(BACK_WORKER_STACK_SIZE, move || {
+        crate::channel::set_back_worker();
+        for x in &appenders {
+            x.on_init();
+        }
+        if let Some(mut record) = start {
+            dispatch(format.as_ref(), &appenders, &mut record);
+        }
+        //records dispatched, for the session stop record
+        let mut written = 0u64;
+        let mut jumps = crate::clock::JumpDetector::default();
+        'recv: loop {
+            //recv a batch of records or one command
             let data = back_recv.recv();
-            if let Ok(mut data) = data {
-                if data.command.eq(&Command::CommandExit){
-                    drop(wait_group_back);
-                    break;
+            if let Ok(mut batch) = data {
+                if batch.first().map(|v| v.command.eq(&Command::CommandRecord)).unwrap_or(false) {
+                    for record in batch.iter_mut() {
+                        jumps.check(record);
+                    }
+                    dispatch_records(format.as_ref(), &appenders, &mut batch);
+                    written += batch.len() as u64;
+                    if let Some(bound) = &bound {
+                        bound.done(batch.len());
+                    }
+                    continue;
                 }
-                format.do_format(&mut data);
-                for x in &appenders {
-                    x.do_log(&mut data);
+                for data in batch.iter_mut() {
+                    if data.command.eq(&Command::CommandExit) {
+                        if let Some(mut record) = crate::session::stopping(written) {
+                            dispatch(format.as_ref(), &appenders, &mut record);
+                        }
+                        for x in &appenders {
+                            x.shutdown();
+                        }
+                        break 'recv;
+                    }
+                    dispatch(format.as_ref(), &appenders, data);
                 }
             }
         }
+        //appenders finish their files on drop, before the exit wait returns
+        drop(appenders);
+        running.store(false, Ordering::Relaxed);
+        drop(wait_group_back);
+        drop(stopped);
     });
-
-    let r = log::set_logger(&LOGGER).map(|()| log::set_max_level(level.to_level_filter()));
-    if r.is_err() {
-        return Err(LogError::from(r.err().unwrap()));
-    } else {
-        return Ok(wait_group);
-    }
 }
 
 pub fn exit() -> Result<(), LogError> {
@@ -232,12 +843,17 @@ pub fn exit() -> Result<(), LogError> {
             command: Command::CommandExit,
             level: log::Level::Info,
             target: String::new(),
-            args: "exit".to_string(),
+            args: "exit".into(),
             module_path: String::new(),
             file: String::new(),
             line: None,
-            now: SystemTime::now(),
-            formated: "exit".to_string(),
+            now: crate::appender::now(),
+            monotonic: Some(crate::appender::monotonic()),
+            formated: "exit".into(),
+            flush_ack: None,
+            trace: None,
+            kv: vec![],
+            tags: vec![],
         };
         let result = sender.send(fast_log_record);
         match result {
@@ -251,6 +867,34 @@ pub fn exit() -> Result<(), LogError> {
     return Err(LogError::E("[fast_log] exit fail!".to_string()));
 }
 
+/// flush, exit and return once the pipeline finished, at most `timeout`: the queued records are written,
+/// the appenders are dropped and so their threads are done(the FileSplitAppender packs, the buffers of the
+/// network appenders...). the global logger logs nothing after it
+pub fn shutdown(timeout: Duration) -> Result<(), LogError> {
+    let sender = match global_sender() {
+        Some(v) => v,
+        None => return Err(LogError::E("[fast_log] shutdown fail!".to_string())),
+    };
+    //fails if an exit was sent already, the pipeline may still write the records before it
+    let _ = sender.send(FastLogRecord::new_command(Command::CommandFlush));
+    let _ = sender.send(FastLogRecord::new_command(Command::CommandExit));
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let stopped = sender.stopped.lock().take();
+        let done = match stopped {
+            Some(stopped) => crate::guard::wait_timeout(stopped, timeout),
+            //an other shutdown waits for it
+            None => !sender.running.load(Ordering::Relaxed),
+        };
+        if !done {
+            return Err(LogError::E("[fast_log] shutdown timeout!".to_string()));
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = timeout;
+    Ok(())
+}
+
 
 pub fn flush() -> Result<(), LogError> {
     let sender = LOG_SENDER.read();
@@ -260,12 +904,17 @@ pub fn flush() -> Result<(), LogError> {
             command: Command::CommandFlush,
             level: log::Level::Info,
             target: String::new(),
-            args: "flush".to_string(),
+            args: "flush".into(),
             module_path: String::new(),
             file: String::new(),
             line: None,
-            now: SystemTime::now(),
-            formated: "flush".to_string(),
+            now: crate::appender::now(),
+            monotonic: Some(crate::appender::monotonic()),
+            formated: SmallString::new(),
+            flush_ack: None,
+            trace: None,
+            kv: vec![],
+            tags: vec![],
         };
         let result = sender.send(fast_log_record);
         match result {
@@ -277,3 +926,82 @@ pub fn flush() -> Result<(), LogError> {
     }
     return Err(LogError::E("[fast_log] flush fail!".to_string()));
 }
+
+/// flush and block until the queued records went through every appender and the background work of the
+/// flush finished(the FileSplitAppender packs), or timeout. for tests and panic hooks which need the logs on disk
+pub fn flush_and_wait(timeout: Duration) -> Result<(), LogError> {
+    //the lock is not held while waiting
+    let sender = global_sender().ok_or_else(|| LogError::E("[fast_log] flush fail!".to_string()))?;
+    flush_sender(&sender, timeout)
+}
+
+/// flush_and_wait on the pipeline of `sender`
+pub(crate) fn flush_sender(sender: &LoggerSender, timeout: Duration) -> Result<(), LogError> {
+    let (ack, wait_ack) = crossbeam::channel::bounded(1);
+    let mut record = FastLogRecord::new_command(Command::CommandFlush);
+    record.flush_ack = Some(ack);
+    if sender.send(record).is_err() {
+        return Err(LogError::E("[fast_log] flush fail!".to_string()));
+    }
+    wait_flushed(wait_ack, timeout)
+}
+
+/// the ack is sent once every appender handled the flush, the clones held by background work
+/// (a pack of the flush) are dropped when it is done: wait for both
+fn wait_flushed(wait_ack: crossbeam::channel::Receiver<()>, timeout: Duration) -> Result<(), LogError> {
+    let deadline = std::time::Instant::now() + timeout;
+    let mut handled = false;
+    loop {
+        match wait_ack.recv_deadline(deadline) {
+            Ok(()) => handled = true,
+            //dropped without the ack: the pipeline exited before the flush
+            Err(crossbeam::channel::RecvTimeoutError::Disconnected) if handled => return Ok(()),
+            Err(crossbeam::channel::RecvTimeoutError::Disconnected) => {
+                return Err(LogError::E("[fast_log] flush fail!".to_string()));
+            }
+            Err(crossbeam::channel::RecvTimeoutError::Timeout) => {
+                return Err(LogError::E("[fast_log] flush timeout!".to_string()));
+            }
+        }
+    }
+}
+
+/// ask file appenders to reopen their files (for example after logrotate moved them)
+pub fn reopen() -> Result<(), LogError> {
+    let sender = LOG_SENDER.read();
+    if let Some(sender) = sender.as_ref() {
+        if sender.send(FastLogRecord::new_command(Command::CommandReopen)).is_ok() {
+            return Ok(());
+        }
+    }
+    Err(LogError::E("[fast_log] reopen fail!".to_string()))
+}
+
+/// tell every appender the configuration changed(LogAppender::on_config_change),
+/// for example after rotating the credentials of a network appender
+pub fn config_changed() -> Result<(), LogError> {
+    send(Command::CommandConfig)
+}
+
+/// send a command to the pipeline, in order with the records logged before
+pub(crate) fn send(command: Command) -> Result<(), LogError> {
+    let sender = LOG_SENDER.read();
+    if let Some(sender) = sender.as_ref() {
+        if sender.send(FastLogRecord::new_command(command)).is_ok() {
+            return Ok(());
+        }
+    }
+    Err(LogError::E("[fast_log] send command fail!".to_string()))
+}
+
+/// send a Command::CommandCustom to every appender, in order with the records logged before
+pub fn send_command(id: u32, data: &str) -> Result<(), LogError> {
+    let sender = LOG_SENDER.read();
+    if let Some(sender) = sender.as_ref() {
+        let record = FastLogRecord::new_command(Command::CommandCustom(id, data.to_string()));
+        if sender.send(record).is_ok() {
+            return Ok(());
+        }
+    }
+    Err(LogError::E("[fast_log] send command fail!".to_string()))
+}
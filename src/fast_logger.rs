@@ -0,0 +1,418 @@
+use crate::appender::{Command, FastLogFormatRecord, FastLogRecord, LogAppender, RecordFormat};
+use crate::channel::{ChannelMode, OverflowPolicy};
+use crate::emit::LevelRecordBuilder;
+use crate::error::LogError;
+use crate::fast_log::{is_ignore_target, new_record, Logger, LoggerSender};
+use crate::filter::{Filter, NoFilter};
+use crate::guard::{FastLogGuard, DEFAULT_GUARD_TIMEOUT};
+use crate::plugin::console::ConsoleAppender;
+use crate::plugin::file::FileAppender;
+use crate::wait::FastLogWaitGroup;
+use log::{LevelFilter, Metadata, Record};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// live instances per level(Off..=Trace), the `log` max level lets the records of every instance through
+static LEVELS: [AtomicUsize; 6] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+
+/// the max level of the live instances, Off without instances
+pub(crate) fn max_level() -> LevelFilter {
+    LevelFilter::iter()
+        .filter(|v| LEVELS[*v as usize].load(Ordering::Relaxed) > 0)
+        .last()
+        .unwrap_or(LevelFilter::Off)
+}
+
+fn use_level(level: LevelFilter, count: bool) {
+    if count {
+        LEVELS[level as usize].fetch_add(1, Ordering::Relaxed);
+    } else {
+        LEVELS[level as usize].fetch_sub(1, Ordering::Relaxed);
+    }
+    let global = crate::fast_log::logger().get_level();
+    log::set_max_level(crate::fast_log::max_level(global));
+}
+
+/// the settings of a FastLogger
+pub struct Config {
+    pub appenders: Vec<Box<dyn LogAppender>>,
+    pub level: LevelFilter,
+    pub filter: Box<dyn Filter>,
+    pub format: Box<dyn RecordFormat>,
+    /// the capacity of the channel, default Unbounded
+    pub channel: ChannelMode,
+    /// what a record does when a bounded channel is full
+    pub overflow: OverflowPolicy,
+    /// installed process wide by build, see clock::set_clock
+    #[cfg(not(target_arch = "wasm32"))]
+    pub clock: Option<Arc<dyn crate::clock::Clock>>,
+}
+
+impl Config {
+    /// no appenders, level Info, no filter and the default format
+    pub fn new() -> Self {
+        Self {
+            appenders: vec![],
+            level: LevelFilter::Info,
+            filter: Box::new(NoFilter {}),
+            format: Box::new(FastLogFormatRecord::new()),
+            channel: ChannelMode::Unbounded,
+            overflow: OverflowPolicy::Block,
+            #[cfg(not(target_arch = "wasm32"))]
+            clock: None,
+        }
+    }
+
+    pub fn level(mut self, level: LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn filter(mut self, filter: Box<dyn Filter>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn format(mut self, format: Box<dyn RecordFormat>) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// bound the records waiting for the appenders, for example
+    /// `.channel(ChannelMode::Bounded(100_000), OverflowPolicy::DropOldest)`. the dropped records are counted
+    /// in Metrics::channel_dropped and summarized(see drop_summary)
+    pub fn channel(mut self, mode: ChannelMode, overflow: OverflowPolicy) -> Self {
+        self.channel = mode;
+        self.overflow = overflow;
+        self
+    }
+
+    /// take the times from `clock`, for example a clock::MockClock in tests
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn clock(mut self, clock: Arc<dyn crate::clock::Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    pub fn appender(mut self, appender: Box<dyn LogAppender>) -> Self {
+        self.appenders.push(appender);
+        self
+    }
+
+    /// append into the file
+    pub fn file(self, log_file_path: &str) -> Self {
+        self.appender(Box::new(FileAppender::new(log_file_path)))
+    }
+
+    pub fn console(self) -> Self {
+        self.appender(Box::new(ConsoleAppender {}))
+    }
+
+    /// windows services: log into the Event Log as `source`, registered if needed,
+    /// or append into `fallback_file` if the registration is denied, see plugin::event_log
+    #[cfg(windows)]
+    pub fn event_log(self, source: &str, fallback_file: &str) -> Self {
+        self.appender(crate::plugin::event_log::event_log_or_file(source, fallback_file))
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// a logger with its own channels, workers and appenders, independent of the global logger.
+/// use it with the `log` macros, `log::info!(logger: my_logger, "...")`, or install() it as the global logger.
+/// on drop it flushes and shuts its pipeline down
+pub struct FastLogger {
+    logger: Logger,
+    sender: Option<Arc<LoggerSender>>,
+    appender_names: Vec<String>,
+    wait_group: Option<FastLogWaitGroup>,
+    timeout: Duration,
+}
+
+impl FastLogger {
+    /// start the pipeline of the config
+    pub fn build(config: Config) -> Result<FastLogger, LogError> {
+        if config.appenders.is_empty() {
+            return Err(LogError::from("[fast_log] appenders can not be empty!"));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(clock) = config.clock {
+            crate::clock::set_clock(clock);
+        }
+        let appender_names = config.appenders.iter().map(|x| x.type_name().to_string()).collect();
+        let wait_group = FastLogWaitGroup::new();
+        let sender = crate::fast_log::start_pipeline(
+            config.appenders,
+            config.filter,
+            config.format,
+            config.level,
+            (config.channel, config.overflow),
+            &wait_group,
+        );
+        use_level(config.level, true);
+        Ok(Self {
+            logger: Logger::new(config.level),
+            sender: Some(Arc::new(sender)),
+            appender_names,
+            wait_group: Some(wait_group),
+            timeout: DEFAULT_GUARD_TIMEOUT,
+        })
+    }
+
+    /// make it the global logger, the same as init_custom_log
+    pub fn install(mut self) -> Result<FastLogGuard, LogError> {
+        let sender = self.sender.take().unwrap();
+        let wait_group = self.wait_group.take().unwrap();
+        let names = std::mem::take(&mut self.appender_names);
+        use_level(self.logger.get_level(), false);
+        crate::fast_log::install(sender, self.logger.get_level(), names)?;
+        Ok(FastLogGuard::new(wait_group).timeout(self.timeout))
+    }
+
+    /// set the max time drop waits for flush and shutdown
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn set_level(&self, level: LevelFilter) {
+        if self.wait_group.is_some() {
+            use_level(level, true);
+            use_level(self.logger.get_level(), false);
+        }
+        self.logger.set_level_filter(level);
+    }
+
+    pub fn get_level(&self) -> LevelFilter {
+        self.logger.get_level()
+    }
+
+    pub fn set_muted(&self, muted: bool) {
+        self.logger.set_muted(muted);
+    }
+
+    /// a logger named `name` writing into the appenders of this logger, see ChildLogger
+    pub fn child(&self, name: &str) -> Result<ChildLogger, LogError> {
+        match &self.sender {
+            Some(sender) => Ok(ChildLogger::new(name, self.logger.get_level(), sender.clone(), false)),
+            None => Err(LogError::from("[fast_log] the logger is installed, use fast_log::child")),
+        }
+    }
+
+    /// type names of the appenders
+    pub fn appender_names(&self) -> &[String] {
+        &self.appender_names
+    }
+
+    /// send a record built without the `log` facade, see fast_log::emit
+    pub fn emit(&self, builder: LevelRecordBuilder) -> Result<(), LogError> {
+        let record = builder.build();
+        if self.logger.is_muted() || record.level > self.logger.get_level() || is_ignore_target(&record.target) {
+            return Ok(());
+        }
+        self.send(record)
+    }
+
+    /// flush and wait until the queued records went through every appender and the packs of the flush are saved,
+    /// at most `timeout`(see fast_log::flush_and_wait)
+    pub fn flush_and_wait(&self, timeout: Duration) -> Result<(), LogError> {
+        match &self.sender {
+            Some(sender) => crate::fast_log::flush_sender(sender, timeout),
+            None => Err(LogError::E("[fast_log] send fail!".to_string())),
+        }
+    }
+
+    fn send(&self, record: FastLogRecord) -> Result<(), LogError> {
+        match &self.sender {
+            Some(sender) if sender.send(record).is_ok() => Ok(()),
+            _ => Err(LogError::E("[fast_log] send fail!".to_string())),
+        }
+    }
+}
+
+impl log::Log for FastLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        !self.logger.is_muted()
+            && metadata.level() <= log::STATIC_MAX_LEVEL
+            && metadata.level() <= self.logger.get_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) || is_ignore_target(record.target()) {
+            return;
+        }
+        if let Some(sender) = &self.sender {
+            if !sender.filter.filter(record) {
+                sender.send(new_record(record));
+            }
+        }
+    }
+
+    fn flush(&self) {
+        self.send(FastLogRecord::new_command(Command::CommandFlush));
+    }
+}
+
+impl Drop for FastLogger {
+    fn drop(&mut self) {
+        if let Some(wait_group) = self.wait_group.take() {
+            use_level(self.logger.get_level(), false);
+            self.send(FastLogRecord::new_command(Command::CommandFlush));
+            self.send(FastLogRecord::new_command(Command::CommandExit));
+            crate::guard::wait_timeout(wait_group, self.timeout);
+        }
+    }
+}
+
+/// a child of the global logger, see ChildLogger
+pub fn child(name: &str) -> Result<ChildLogger, LogError> {
+    match crate::fast_log::global_sender() {
+        Some(sender) => Ok(ChildLogger::new(name, crate::fast_log::logger().get_level(), sender, true)),
+        None => Err(LogError::from("[fast_log] the logger is not initialized")),
+    }
+}
+
+/// a named logger sharing the pipeline(appenders, format) of its parent, like a log4j category.
+/// the records have the target "{target of the call}::{parents}::{name}", for example "app::repo::db::query".
+/// children of the global logger follow fast_log::mute() and pause() too.
+/// the level, filters and static fields can be set per child:
+/// let db = logger.child("db")?.level(LevelFilter::Debug).field("pool", "main");
+/// log::debug!(logger: db, "query {}", sql);
+pub struct ChildLogger {
+    name: String,
+    logger: Logger,
+    sender: Arc<LoggerSender>,
+    filters: Vec<Arc<dyn Filter>>,
+    fields: Vec<(String, serde_json::Value)>,
+    //a child of the global logger, not of a FastLogger
+    global: bool,
+}
+
+impl ChildLogger {
+    fn new(name: &str, level: LevelFilter, sender: Arc<LoggerSender>, global: bool) -> Self {
+        use_level(level, true);
+        Self {
+            name: name.to_string(),
+            logger: Logger::new(level),
+            sender,
+            filters: vec![],
+            fields: vec![],
+            global,
+        }
+    }
+
+    /// the child of this child, with the same level, filters and fields
+    pub fn child(&self, name: &str) -> ChildLogger {
+        let mut child = ChildLogger::new(
+            &format!("{}::{}", self.name, name),
+            self.logger.get_level(),
+            self.sender.clone(),
+            self.global,
+        );
+        child.filters = self.filters.clone();
+        child.fields = self.fields.clone();
+        child
+    }
+
+    pub fn level(self, level: LevelFilter) -> Self {
+        self.set_level(level);
+        self
+    }
+
+    pub fn set_level(&self, level: LevelFilter) {
+        use_level(level, true);
+        use_level(self.logger.get_level(), false);
+        self.logger.set_level_filter(level);
+    }
+
+    pub fn get_level(&self) -> LevelFilter {
+        self.logger.get_level()
+    }
+
+    /// a filter of this child, the filters of the parents apply as well
+    pub fn filter(mut self, filter: Box<dyn Filter>) -> Self {
+        self.filters.push(Arc::from(filter));
+        self
+    }
+
+    /// a key-value added to every record of the child
+    pub fn field(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.fields.push((key.to_string(), value.into()));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// send a record built without the `log` facade, the target is "{target of the builder}::{name}"
+    pub fn emit(&self, builder: LevelRecordBuilder) -> Result<(), LogError> {
+        let record = builder.build();
+        if self.logger.is_muted() || record.level > self.logger.get_level() {
+            return Ok(());
+        }
+        self.send(record)
+    }
+
+    fn send(&self, mut record: FastLogRecord) -> Result<(), LogError> {
+        record.target = if record.target.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}::{}", record.target, self.name)
+        };
+        if is_ignore_target(&record.target) || (self.global && crate::fast_log::is_muted()) {
+            return Ok(());
+        }
+        record.kv.extend(self.fields.iter().cloned());
+        //pause() holds the records of the global pipeline only, resume() forwards them there
+        if self.global {
+            record = match crate::pause::hold(record) {
+                Some(v) => v,
+                None => return Ok(()),
+            };
+        }
+        self.sender
+            .send(record)
+            .map_err(|_| LogError::E("[fast_log] send fail!".to_string()))
+    }
+}
+
+impl log::Log for ChildLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        !self.logger.is_muted()
+            && metadata.level() <= log::STATIC_MAX_LEVEL
+            && metadata.level() <= self.logger.get_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) || self.sender.filter.filter(record) {
+            return;
+        }
+        if self.filters.iter().any(|x| x.filter(record)) {
+            return;
+        }
+        self.send(new_record(record));
+    }
+
+    fn flush(&self) {
+        self.sender.send(FastLogRecord::new_command(Command::CommandFlush));
+    }
+}
+
+impl Drop for ChildLogger {
+    fn drop(&mut self) {
+        use_level(self.logger.get_level(), false);
+    }
+}
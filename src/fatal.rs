@@ -0,0 +1,54 @@
+use parking_lot::RwLock;
+use std::time::Duration;
+
+/// an Error record with this target is fatal, for example `log::error!(target: "fatal", "disk lost")`
+pub const FATAL_TARGET: &str = "fatal";
+
+/// what happens after a fatal record was flushed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FatalAction {
+    /// std::process::abort(), the default
+    Abort,
+    /// std::process::exit(code)
+    Exit(i32),
+}
+
+struct FatalPolicy {
+    action: FatalAction,
+    flush_timeout: Duration,
+}
+
+lazy_static! {
+    static ref POLICY: RwLock<FatalPolicy> = RwLock::new(FatalPolicy {
+        action: FatalAction::Abort,
+        flush_timeout: Duration::from_secs(5),
+    });
+}
+
+/// set what a fatal record does and how long it waits for the appenders to flush
+pub fn set_fatal_policy(action: FatalAction, flush_timeout: Duration) {
+    *POLICY.write() = FatalPolicy { action, flush_timeout };
+}
+
+/// flush every appender, then abort or exit by the fatal policy
+pub fn die() -> ! {
+    let (action, flush_timeout) = {
+        let policy = POLICY.read();
+        (policy.action, policy.flush_timeout)
+    };
+    let _ = crate::fast_log::flush_and_wait(flush_timeout);
+    match action {
+        FatalAction::Abort => std::process::abort(),
+        FatalAction::Exit(code) => std::process::exit(code),
+    }
+}
+
+/// log an Error record, wait until it is written and abort(or exit, see set_fatal_policy):
+/// `fast_log::fatal!("config {} is broken", path);`
+#[macro_export]
+macro_rules! fatal {
+    ($($arg:tt)+) => {{
+        $crate::__log::error!($($arg)+);
+        $crate::fatal::die()
+    }};
+}
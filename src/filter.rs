@@ -1,3 +1,9 @@
+use crate::error::LogError;
+use log::LevelFilter;
+use parking_lot::RwLock;
+use std::str::FromStr;
+use std::sync::Arc;
+
 ///log filter
 pub trait Filter: Send + Sync {
     //return is filter
@@ -64,3 +70,95 @@ impl Filter for ModuleFilter {
         return false;
     }
 }
+
+/// the max level of every module path prefix, see ModuleLevelFilter
+#[derive(Clone, Debug)]
+pub struct ModuleLevels {
+    /// the level of the modules without an entry
+    pub default: LevelFilter,
+    //longest prefix first
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl ModuleLevels {
+    pub fn new(default: LevelFilter) -> Self {
+        Self {
+            default,
+            modules: vec![],
+        }
+    }
+
+    /// parse the RUST_LOG like "info,hyper=warn,my_app=trace", an entry without a module is the default(else Trace)
+    pub fn parse(spec: &str) -> Result<Self, LogError> {
+        let mut levels = ModuleLevels::new(LevelFilter::Trace);
+        for item in spec.split(',').map(|v| v.trim()).filter(|v| !v.is_empty()) {
+            let (module, level) = match item.split_once('=') {
+                Some((module, level)) => (Some(module.trim()), level.trim()),
+                None => (None, item),
+            };
+            let level = LevelFilter::from_str(level)
+                .map_err(|_| LogError::from(format!("[fast_log] unknown level '{}' in '{}'", level, spec)))?;
+            match module {
+                Some(module) => levels.set(module, level),
+                None => levels.default = level,
+            }
+        }
+        Ok(levels)
+    }
+
+    /// set the level of a module and its submodules, "hyper" is "hyper" and "hyper::client"
+    pub fn set(&mut self, module: &str, level: LevelFilter) {
+        match self.modules.iter_mut().find(|(m, _)| m == module) {
+            Some(item) => item.1 = level,
+            None => {
+                self.modules.push((module.to_string(), level));
+                self.modules.sort_by_key(|v| std::cmp::Reverse(v.0.len()));
+            }
+        }
+    }
+
+    /// remove the entry of a module, it takes the level of its parent or the default again
+    pub fn remove(&mut self, module: &str) {
+        self.modules.retain(|(m, _)| m != module);
+    }
+
+    /// the level of the longest matching prefix, else the default
+    pub fn level(&self, module: &str) -> LevelFilter {
+        for (prefix, level) in &self.modules {
+            if module.starts_with(prefix.as_str())
+                && (module.len() == prefix.len() || module[prefix.len()..].starts_with("::"))
+            {
+                return *level;
+            }
+        }
+        self.default
+    }
+}
+
+/// drop the records above the level of their module(the module path, else the target), for example
+/// `ModuleLevelFilter::new(ModuleLevels::parse("info,hyper=warn,my_app=trace")?)` silences hyper below warn.
+/// a filter only drops records: the logger level must be as high as the highest module level(Trace here).
+/// the levels can change at runtime through handle()
+pub struct ModuleLevelFilter {
+    levels: Arc<RwLock<ModuleLevels>>,
+}
+
+impl ModuleLevelFilter {
+    pub fn new(levels: ModuleLevels) -> Self {
+        Self {
+            levels: Arc::new(RwLock::new(levels)),
+        }
+    }
+
+    /// the shared levels, for example `filter.handle().write().set("hyper", LevelFilter::Debug)`
+    pub fn handle(&self) -> Arc<RwLock<ModuleLevels>> {
+        self.levels.clone()
+    }
+}
+
+impl Filter for ModuleLevelFilter {
+    fn filter(&self, record: &log::Record) -> bool {
+        let module = record.module_path().unwrap_or_else(|| record.target());
+        record.level() > self.levels.read().level(module)
+    }
+}
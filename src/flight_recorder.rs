@@ -0,0 +1,120 @@
+use crate::appender::{FastLogFormatRecord, FastLogRecord, RecordFormat};
+use crate::error::LogError;
+use log::LevelFilter;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// keep the last records in memory, also the ones below the active level
+#[derive(Clone, Debug)]
+pub struct FlightRecorder {
+    /// max records kept, the oldest is dropped
+    pub capacity: usize,
+    /// records up to this level are kept, for example Debug while the logger runs at Info
+    pub level: LevelFilter,
+    /// the panic hook dumps the records into this file
+    pub dump_path: Option<String>,
+}
+
+impl Default for FlightRecorder {
+    fn default() -> Self {
+        Self {
+            capacity: 1000,
+            level: LevelFilter::Debug,
+            dump_path: None,
+        }
+    }
+}
+
+struct RecorderState {
+    config: FlightRecorder,
+    records: VecDeque<FastLogRecord>,
+}
+
+/// LevelFilter as usize, 0(Off) = disabled
+static LEVEL: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    static ref STATE: Mutex<Option<RecorderState>> = Mutex::new(None);
+}
+
+/// start recording. the `log` max level is raised to `config.level` so
+/// records under the logger level still reach the recorder
+pub fn enable_flight_recorder(config: FlightRecorder) {
+    let level = config.level;
+    *STATE.lock() = Some(RecorderState {
+        records: VecDeque::with_capacity(config.capacity.min(4096)),
+        config,
+    });
+    LEVEL.store(level as usize, Ordering::Relaxed);
+    log::set_max_level(crate::fast_log::max_level(crate::fast_log::logger().get_level()));
+}
+
+pub fn disable_flight_recorder() {
+    LEVEL.store(0, Ordering::Relaxed);
+    *STATE.lock() = None;
+    log::set_max_level(crate::fast_log::max_level(crate::fast_log::logger().get_level()));
+}
+
+/// the recorder level, Off if disabled
+pub fn level() -> LevelFilter {
+    match LEVEL.load(Ordering::Relaxed) {
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        5 => LevelFilter::Trace,
+        _ => LevelFilter::Off,
+    }
+}
+
+/// called by the logger before the level check, for records up to level()
+pub(crate) fn record(record: FastLogRecord) {
+    if let Some(state) = STATE.lock().as_mut() {
+        if state.records.len() >= state.config.capacity {
+            state.records.pop_front();
+        }
+        if state.config.capacity != 0 {
+            state.records.push_back(record);
+        }
+    }
+}
+
+/// write the recorded records (oldest first) into `path`, return the count
+pub fn dump_flight_recorder(path: &str) -> Result<usize, LogError> {
+    let records: Vec<FastLogRecord> = match STATE.lock().as_ref() {
+        Some(state) => state.records.iter().cloned().collect(),
+        None => return Err(LogError::from("[fast_log] flight recorder is not enabled")),
+    };
+    let path = path.replace("\\", "/");
+    if let Some(right) = path.rfind('/') {
+        std::fs::create_dir_all(&path[0..right]);
+    }
+    let mut file = std::fs::File::create(&path)
+        .map_err(|e| LogError::from(format!("[fast_log] create({}) fail:{}", path, e)))?;
+    let format = FastLogFormatRecord::new();
+    let mut buf = String::new();
+    for mut record in records.iter().cloned() {
+        format.do_format(&mut record);
+        buf.push_str(&record.formated);
+    }
+    file.write_all(buf.as_bytes())
+        .and_then(|_| file.flush())
+        .map_err(|e| LogError::from(format!("[fast_log] write({}) fail:{}", path, e)))?;
+    Ok(records.len())
+}
+
+/// dump into `dump_path` if configured, called by the panic hook
+pub(crate) fn dump_on_panic() {
+    //the panic may come from inside the lock
+    let path = match STATE.try_lock() {
+        Some(state) => state.as_ref().and_then(|s| s.config.dump_path.clone()),
+        None => None,
+    };
+    if let Some(path) = path {
+        if let Err(e) = dump_flight_recorder(&path) {
+            crate::health::report_error("FlightRecorder", &e.to_string());
+        }
+    }
+}
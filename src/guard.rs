@@ -0,0 +1,76 @@
+use crate::wait::FastLogWaitGroup;
+use std::time::Duration;
+
+/// default time the guard waits for the log pipeline to flush on drop
+pub const DEFAULT_GUARD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// returned by init. keep it alive until the end of `main`,
+/// on drop it flushes all buffered records and shuts the pipeline down (bounded by `timeout`).
+/// for example:
+/// let _guard = fast_log::init_log("requests.log", log::Level::Info, None, None, true).unwrap();
+#[must_use = "dropping the guard flushes and shuts down the logger immediately"]
+#[derive(Debug)]
+pub struct FastLogGuard {
+    wait_group: Option<FastLogWaitGroup>,
+    timeout: Duration,
+}
+
+impl FastLogGuard {
+    pub fn new(wait_group: FastLogWaitGroup) -> Self {
+        Self {
+            wait_group: Some(wait_group),
+            timeout: DEFAULT_GUARD_TIMEOUT,
+        }
+    }
+
+    /// set the max time drop waits for flush and shutdown
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// wait call fast_log::exit();
+    pub fn do_wait(mut self) {
+        if let Some(wait_group) = self.wait_group.take() {
+            wait_group.do_wait();
+        }
+    }
+
+    /// send exit msg and wait, the records before it are written(the split files are not packed, see fast_log::shutdown)
+    pub fn wait(mut self) {
+        if let Some(wait_group) = self.wait_group.take() {
+            wait_group.wait();
+        }
+    }
+
+    ///send exit msg
+    pub fn exit(mut self) {
+        if let Some(wait_group) = self.wait_group.take() {
+            wait_group.exit();
+        }
+    }
+}
+
+impl Drop for FastLogGuard {
+    fn drop(&mut self) {
+        if let Some(wait_group) = self.wait_group.take() {
+            let _ = crate::fast_log::flush();
+            let _ = crate::fast_log::exit();
+            wait_timeout(wait_group, self.timeout);
+        }
+    }
+}
+
+/// wait until the pipeline of the wait group exited, at most `timeout`. false if it did not exit in time
+pub(crate) fn wait_timeout(wait_group: FastLogWaitGroup, timeout: Duration) -> bool {
+    //wasm dispatch records on the caller, it is done already
+    if cfg!(target_arch = "wasm32") {
+        return true;
+    }
+    let (done, wait_done) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        wait_group.do_wait();
+        let _ = done.send(());
+    });
+    wait_done.recv_timeout(timeout).is_ok()
+}
@@ -0,0 +1,126 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+/// a repeated error of an appender is printed at most once in this time
+const REPEAT_PRINT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// the errors of one appender
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AppenderHealth {
+    /// the type name of the appender, for example FileAppender. the http appenders use their service name
+    pub name: String,
+    /// errors since start
+    pub errors: u64,
+    pub last_error: Option<String>,
+    pub last_error_time: Option<SystemTime>,
+}
+
+/// the status of the global logger, see health()
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Health {
+    /// the logger is installed and its pipeline did not exit
+    pub running: bool,
+    /// records waiting in the pipeline
+    pub backlog: usize,
+    /// the last error of any appender
+    pub last_error: Option<String>,
+    /// the running appenders, then other appenders which reported errors
+    pub appenders: Vec<AppenderHealth>,
+}
+
+impl Health {
+    /// running and no appender failed in the last `window`
+    pub fn is_ok(&self, window: Duration) -> bool {
+        let now = crate::appender::now();
+        self.running
+            && self.appenders.iter().all(|v| match v.last_error_time {
+                Some(t) => now.duration_since(t).unwrap_or_default() > window,
+                None => true,
+            })
+    }
+}
+
+struct Errors {
+    //with the time the error was printed
+    appenders: Vec<(AppenderHealth, SystemTime)>,
+    last_error: Option<String>,
+}
+
+lazy_static! {
+    static ref ERRORS: Mutex<Errors> = Mutex::new(Errors {
+        appenders: vec![],
+        last_error: None,
+    });
+}
+
+/// "fast_log::plugin::file::FileAppender" -> "FileAppender"
+pub(crate) fn short_name(type_name: &str) -> &str {
+    type_name.split('<').next().unwrap_or("").rsplit("::").next().unwrap_or("")
+}
+
+/// an appender failed, it shows in health(). the error is printed to stderr and logged as an internal
+/// record(see diagnostics), the same error again at most once a minute
+pub fn report_error(appender: &str, error: &str) {
+    let now = crate::appender::now();
+    let mut errors = ERRORS.lock();
+    let index = match errors.appenders.iter().position(|(v, _)| v.name == appender) {
+        Some(index) => index,
+        None => {
+            let item = AppenderHealth {
+                name: appender.to_string(),
+                ..Default::default()
+            };
+            errors.appenders.push((item, SystemTime::UNIX_EPOCH));
+            errors.appenders.len() - 1
+        }
+    };
+    let (item, printed) = &mut errors.appenders[index];
+    let repeated = item.last_error.as_deref() == Some(error)
+        && now.duration_since(*printed).unwrap_or_default() < REPEAT_PRINT_INTERVAL;
+    if !repeated {
+        *printed = now;
+    }
+    item.errors += 1;
+    item.last_error = Some(error.to_string());
+    item.last_error_time = Some(now);
+    errors.last_error = Some(error.to_string());
+    //the internal record can fail again, it is sent without the lock
+    if !repeated {
+        drop(errors);
+        eprintln!("{}", error);
+        crate::diagnostics::emit(log::Level::Warn, "appender_error", format!("{}: {}", appender, error));
+    }
+}
+
+/// the status of the logger for readiness/health endpoints, for example
+/// `if !fast_log::health().is_ok(Duration::from_secs(60)) { ... }`
+pub fn health() -> Health {
+    let errors = ERRORS.lock();
+    let mut appenders: Vec<AppenderHealth> = crate::fast_log::appender_names()
+        .iter()
+        .map(|v| {
+            let name = short_name(v);
+            errors
+                .appenders
+                .iter()
+                .find(|(x, _)| x.name == name)
+                .map(|(x, _)| x.clone())
+                .unwrap_or_else(|| AppenderHealth {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+        })
+        .collect();
+    for (x, _) in &errors.appenders {
+        if !appenders.iter().any(|v| v.name == x.name) {
+            appenders.push(x.clone());
+        }
+    }
+    Health {
+        running: crate::fast_log::is_running(),
+        backlog: crate::backpressure::backlog(),
+        last_error: errors.last_error.clone(),
+        appenders,
+    }
+}
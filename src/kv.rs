@@ -0,0 +1,121 @@
+use log::kv::{Error, Key, Source, Value, VisitSource, VisitValue};
+use serde_json::json;
+
+/// log a record with typed key-values, for example
+/// `fast_log::log_kv!(log::Level::Info, "login {}", name; user_id = id, latency_ms = t)`.
+/// the key-values are in FastLogRecord::kv, the default format appends them as `key=value`
+#[macro_export]
+macro_rules! log_kv {
+    ($lvl:expr, $fmt:literal $(, $arg:expr)* ; $($key:ident = $value:expr),+ $(,)?) => {
+        $crate::__log::log!($lvl, $($key = $value),+ ; $fmt $(, $arg)*)
+    };
+    ($lvl:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::__log::log!($lvl, $fmt $(, $arg)*)
+    };
+}
+
+/// `fast_log::error_kv!("msg"; key = value, ..)`
+#[macro_export]
+macro_rules! error_kv {
+    ($($arg:tt)+) => { $crate::log_kv!($crate::__log::Level::Error, $($arg)+) };
+}
+
+/// `fast_log::warn_kv!("msg"; key = value, ..)`
+#[macro_export]
+macro_rules! warn_kv {
+    ($($arg:tt)+) => { $crate::log_kv!($crate::__log::Level::Warn, $($arg)+) };
+}
+
+/// `fast_log::info_kv!("msg"; key = value, ..)`
+#[macro_export]
+macro_rules! info_kv {
+    ($($arg:tt)+) => { $crate::log_kv!($crate::__log::Level::Info, $($arg)+) };
+}
+
+/// `fast_log::debug_kv!("msg"; key = value, ..)`
+#[macro_export]
+macro_rules! debug_kv {
+    ($($arg:tt)+) => { $crate::log_kv!($crate::__log::Level::Debug, $($arg)+) };
+}
+
+/// `fast_log::trace_kv!("msg"; key = value, ..)`
+#[macro_export]
+macro_rules! trace_kv {
+    ($($arg:tt)+) => { $crate::log_kv!($crate::__log::Level::Trace, $($arg)+) };
+}
+
+/// the key-values of a `log` record, numbers and bools keep their type, other values are strings
+pub(crate) fn collect(source: &dyn Source) -> Vec<(String, serde_json::Value)> {
+    let mut pairs = Pairs(Vec::new());
+    source.visit(&mut pairs);
+    pairs.0
+}
+
+struct Pairs(Vec<(String, serde_json::Value)>);
+
+impl<'kvs> VisitSource<'kvs> for Pairs {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+        let mut json = JsonValue(serde_json::Value::Null);
+        value.visit(&mut json)?;
+        self.0.push((key.as_str().to_string(), json.0));
+        Ok(())
+    }
+}
+
+struct JsonValue(serde_json::Value);
+
+impl<'v> VisitValue<'v> for JsonValue {
+    fn visit_any(&mut self, value: Value) -> Result<(), Error> {
+        self.0 = json!(value.to_string());
+        Ok(())
+    }
+
+    fn visit_null(&mut self) -> Result<(), Error> {
+        self.0 = serde_json::Value::Null;
+        Ok(())
+    }
+
+    fn visit_u64(&mut self, value: u64) -> Result<(), Error> {
+        self.0 = json!(value);
+        Ok(())
+    }
+
+    fn visit_i64(&mut self, value: i64) -> Result<(), Error> {
+        self.0 = json!(value);
+        Ok(())
+    }
+
+    fn visit_f64(&mut self, value: f64) -> Result<(), Error> {
+        self.0 = json!(value);
+        Ok(())
+    }
+
+    fn visit_bool(&mut self, value: bool) -> Result<(), Error> {
+        self.0 = json!(value);
+        Ok(())
+    }
+
+    fn visit_str(&mut self, value: &str) -> Result<(), Error> {
+        self.0 = json!(value);
+        Ok(())
+    }
+}
+
+/// " key=value key2=value2", strings with spaces, '=' or quotes are json quoted
+pub fn format_kv(kv: &[(String, serde_json::Value)]) -> String {
+    let mut out = String::new();
+    for (key, value) in kv {
+        out.push(' ');
+        out.push_str(key);
+        out.push('=');
+        match value {
+            serde_json::Value::String(v)
+                if !v.is_empty() && !v.contains(|c: char| c.is_whitespace() || c == '=' || c == '"') =>
+            {
+                out.push_str(v)
+            }
+            v => out.push_str(&v.to_string()),
+        }
+    }
+    out
+}
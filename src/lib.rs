@@ -6,13 +6,66 @@
 #[macro_use]
 extern crate lazy_static;
 
+pub mod adaptive;
+pub mod admin;
 pub mod appender;
+pub mod backpressure;
 pub mod bencher;
+pub mod budget;
+pub mod channel;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod clock;
 pub mod consts;
+pub mod crc;
+pub mod diagnostics;
+pub mod drop_summary;
+pub mod emit;
 pub mod error;
 pub mod fast_log;
+pub mod fast_logger;
+pub mod fatal;
 pub mod filter;
+pub mod flight_recorder;
+pub mod guard;
+pub mod health;
+pub mod kv;
+pub mod metrics;
+#[cfg(feature = "obj")]
+pub mod obj;
+pub mod panic_hook;
+pub mod pause;
 pub mod plugin;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod process;
+pub mod reader;
+pub mod relay;
+pub mod scoped_level;
+pub mod sanitize;
+pub mod session;
+#[cfg(all(unix, feature = "signal"))]
+pub mod signal;
+pub mod small_string;
+pub mod spill;
+pub mod subscriber;
+pub mod tags;
+pub mod testing;
+pub mod trace;
 pub mod wait;
 
 pub use fast_log::*;
+pub use fast_logger::{child, ChildLogger, Config, FastLogger};
+pub use emit::{emit, emit_raw, writer, LevelRecordBuilder, LogWriter};
+#[doc(hidden)]
+pub use log as __log;
+pub use health::{health, Health};
+pub use flight_recorder::{dump_flight_recorder, enable_flight_recorder};
+pub use panic_hook::install_panic_hook;
+pub use backpressure::{on_backpressure, Pressure};
+pub use pause::{pause, resume, PauseMode};
+pub use plugin::combinator::AppenderExt;
+pub use scoped_level::with_level;
+pub use spill::spill_large;
+pub use subscriber::subscribe;
+pub use testing::test_init;
+#[cfg(all(unix, feature = "signal"))]
+pub use signal::install_signal_handler;
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static LOGGED: AtomicU64 = AtomicU64::new(0);
+static SEND_FAIL: AtomicU64 = AtomicU64::new(0);
+static BUDGET_DROPPED: AtomicU64 = AtomicU64::new(0);
+static CHANNEL_DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// counters of the log pipeline
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Metrics {
+    /// records sent into the pipeline
+    pub logged: u64,
+    /// records lost because the pipeline was closed
+    pub send_fail: u64,
+    /// records dropped because the pipeline was over its memory budget(see budget::set_memory_budget),
+    /// they are in logged too
+    #[serde(default)]
+    pub budget_dropped: u64,
+    /// records dropped because a bounded channel was full(see channel::OverflowPolicy), they are in logged too
+    #[serde(default)]
+    pub channel_dropped: u64,
+    /// bytes of the queued records and pending pack jobs
+    #[serde(default)]
+    pub memory_used: u64,
+}
+
+pub(crate) fn inc_logged() {
+    LOGGED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn inc_send_fail() {
+    SEND_FAIL.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn inc_budget_dropped() {
+    BUDGET_DROPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn inc_channel_dropped() {
+    CHANNEL_DROPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn metrics() -> Metrics {
+    Metrics {
+        logged: LOGGED.load(Ordering::Relaxed),
+        send_fail: SEND_FAIL.load(Ordering::Relaxed),
+        budget_dropped: BUDGET_DROPPED.load(Ordering::Relaxed),
+        channel_dropped: CHANNEL_DROPPED.load(Ordering::Relaxed),
+        memory_used: crate::budget::memory_used() as u64,
+    }
+}
@@ -0,0 +1,90 @@
+use crate::emit::LevelRecordBuilder;
+use serde::Serialize;
+use serde_json::Value;
+
+/// log a message with the fields of a Serialize value as key-values(see FastLogRecord::kv), for example
+/// `fast_log::info_obj!("order created", &order)` logs "order created id=7 customer.name=alice".
+/// you need enable fast_log = { ... ,features=["obj"]}
+#[macro_export]
+macro_rules! log_obj {
+    ($lvl:expr, $msg:expr, $value:expr $(,)?) => {{
+        let lvl = $lvl;
+        if lvl <= $crate::__log::STATIC_MAX_LEVEL && lvl <= $crate::__log::max_level() {
+            $crate::obj::__log_obj(lvl, module_path!(), file!(), line!(), &$msg, $value);
+        }
+    }};
+}
+
+/// `fast_log::error_obj!("msg", &value)`
+#[macro_export]
+macro_rules! error_obj {
+    ($($arg:tt)+) => { $crate::log_obj!($crate::__log::Level::Error, $($arg)+) };
+}
+
+/// `fast_log::warn_obj!("msg", &value)`
+#[macro_export]
+macro_rules! warn_obj {
+    ($($arg:tt)+) => { $crate::log_obj!($crate::__log::Level::Warn, $($arg)+) };
+}
+
+/// `fast_log::info_obj!("msg", &value)`
+#[macro_export]
+macro_rules! info_obj {
+    ($($arg:tt)+) => { $crate::log_obj!($crate::__log::Level::Info, $($arg)+) };
+}
+
+/// `fast_log::debug_obj!("msg", &value)`
+#[macro_export]
+macro_rules! debug_obj {
+    ($($arg:tt)+) => { $crate::log_obj!($crate::__log::Level::Debug, $($arg)+) };
+}
+
+/// `fast_log::trace_obj!("msg", &value)`
+#[macro_export]
+macro_rules! trace_obj {
+    ($($arg:tt)+) => { $crate::log_obj!($crate::__log::Level::Trace, $($arg)+) };
+}
+
+/// the fields of the value as key-values, nested structs and maps are flattened as "a.b",
+/// arrays stay json and a value which is not a struct or map is the key "value"
+pub fn to_kv<T: Serialize + ?Sized>(value: &T) -> Vec<(String, Value)> {
+    let mut kv = vec![];
+    match serde_json::to_value(value) {
+        Ok(Value::Object(map)) => flatten("", map, &mut kv),
+        Ok(v) => kv.push(("value".to_string(), v)),
+        Err(e) => kv.push(("error".to_string(), Value::String(e.to_string()))),
+    }
+    kv
+}
+
+fn flatten(prefix: &str, map: serde_json::Map<String, Value>, kv: &mut Vec<(String, Value)>) {
+    for (key, value) in map {
+        let key = if prefix.is_empty() {
+            key
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            Value::Object(map) => flatten(&key, map, kv),
+            v => kv.push((key, v)),
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn __log_obj<M: std::fmt::Display + ?Sized, T: Serialize + ?Sized>(
+    level: log::Level,
+    module_path: &str,
+    file: &str,
+    line: u32,
+    msg: &M,
+    value: &T,
+) {
+    let mut builder = LevelRecordBuilder::new(level, msg.to_string())
+        .target(module_path)
+        .file(file, line);
+    for (key, value) in to_kv(value) {
+        builder = builder.kv(&key, value);
+    }
+    crate::emit::emit(builder);
+}
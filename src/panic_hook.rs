@@ -0,0 +1,36 @@
+use std::backtrace::Backtrace;
+use std::time::Duration;
+
+/// max time the panic hook waits for the log to reach the appenders
+pub const PANIC_FLUSH_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// log panics at Error level (payload, location, backtrace),
+/// then flush synchronously and dump the flight recorder before the previous(default) hook runs
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "Box<dyn Any>".to_string()
+        };
+        let location = match info.location() {
+            Some(l) => format!("{}:{}:{}", l.file(), l.line(), l.column()),
+            None => "unknown".to_string(),
+        };
+        let thread = std::thread::current();
+        log::error!(
+            target: "panic",
+            "thread '{}' panicked at '{}', {}\n{}",
+            thread.name().unwrap_or("<unnamed>"),
+            payload,
+            location,
+            Backtrace::force_capture()
+        );
+        let _ = crate::fast_log::flush_and_wait(PANIC_FLUSH_TIMEOUT);
+        crate::flight_recorder::dump_on_panic();
+        default_hook(info);
+    }));
+}
@@ -0,0 +1,80 @@
+use crate::appender::FastLogRecord;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// what pause() does with the held records
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PauseMode {
+    Drop,
+    /// keep at most n records, they are logged on resume(). records over n are dropped
+    Buffer(usize),
+}
+
+struct Paused {
+    level: log::Level,
+    mode: PauseMode,
+    buffer: Vec<FastLogRecord>,
+    dropped: u64,
+}
+
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref PAUSE: Mutex<Option<Paused>> = Mutex::new(None);
+}
+
+/// hold the records less severe than `level` until resume(), for example during a noisy bulk migration
+/// `fast_log::pause(Level::Warn, PauseMode::Drop)` only lets warnings and errors through
+pub fn pause(level: log::Level, mode: PauseMode) {
+    let mut pause = PAUSE.lock();
+    let (buffer, dropped) = match pause.take() {
+        Some(v) => (v.buffer, v.dropped),
+        None => (vec![], 0),
+    };
+    *pause = Some(Paused {
+        level,
+        mode,
+        buffer,
+        dropped,
+    });
+    PAUSED.store(true, Ordering::SeqCst);
+}
+
+/// log the buffered records and forward all records again, return the records dropped while paused
+pub fn resume() -> u64 {
+    let paused = {
+        let mut pause = PAUSE.lock();
+        PAUSED.store(false, Ordering::SeqCst);
+        pause.take()
+    };
+    match paused {
+        Some(paused) => {
+            for record in paused.buffer {
+                crate::fast_log::forward(record);
+            }
+            paused.dropped
+        }
+        None => 0,
+    }
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::Relaxed)
+}
+
+/// the record to send, None if it is held by pause()
+pub(crate) fn hold(record: FastLogRecord) -> Option<FastLogRecord> {
+    if !PAUSED.load(Ordering::Relaxed) {
+        return Some(record);
+    }
+    let mut pause = PAUSE.lock();
+    let paused = match pause.as_mut() {
+        Some(v) if record.level > v.level => v,
+        _ => return Some(record),
+    };
+    match paused.mode {
+        PauseMode::Buffer(cap) if paused.buffer.len() < cap => paused.buffer.push(record),
+        _ => paused.dropped += 1,
+    }
+    None
+}
@@ -0,0 +1,32 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use android_logger::PlatformLogWriter;
+use std::ffi::CString;
+use std::fmt::Write;
+
+/// logcat limits tag length on old android
+const MAX_TAG_LEN: usize = 23;
+
+/// you need enable fast_log = { ... ,features=["android"]} (target_os = "android")
+/// write records into logcat, tag is the record target and the priority is mapped from level
+/// (Error->ERROR, Warn->WARN, Info->INFO, Debug->DEBUG, Trace->VERBOSE)
+pub struct AndroidAppender {}
+
+impl LogAppender for AndroidAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.ne(&Command::CommandRecord) {
+            return;
+        }
+        let mut tag: String = record.target.chars().filter(|c| *c != '\0').collect();
+        if tag.len() > MAX_TAG_LEN {
+            let mut end = MAX_TAG_LEN;
+            while !tag.is_char_boundary(end) {
+                end -= 1;
+            }
+            tag.truncate(end);
+        }
+        let tag = CString::new(tag).unwrap_or_default();
+        let mut writer = PlatformLogWriter::new(None, record.level, &tag);
+        let _ = write!(writer, "{}", record.args);
+        writer.flush();
+    }
+}
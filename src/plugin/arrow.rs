@@ -0,0 +1,248 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::error::LogError;
+use crate::relay::RelayConn;
+use arrow_array::builder::{StringBuilder, TimestampMicrosecondBuilder, UInt32Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use crossbeam::channel::{Receiver, Sender};
+use std::cell::RefCell;
+use std::io::BufWriter;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+/// on exit the buffered batches are retried until this timeout
+const EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// the schema of the arrow batches and parquet files:
+/// timestamp(us, utc), level, target, line, message, fields(json of key-values/file/tags/trace_id/span_id or null)
+pub fn log_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("timestamp", DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into())), false),
+        Field::new("level", DataType::Utf8, false),
+        Field::new("target", DataType::Utf8, false),
+        Field::new("line", DataType::UInt32, true),
+        Field::new("message", DataType::Utf8, false),
+        Field::new("fields", DataType::Utf8, true),
+    ]))
+}
+
+/// build RecordBatch(see log_schema) from records
+pub struct LogBatchBuilder {
+    schema: SchemaRef,
+    timestamp: TimestampMicrosecondBuilder,
+    level: StringBuilder,
+    target: StringBuilder,
+    line: UInt32Builder,
+    message: StringBuilder,
+    fields: StringBuilder,
+    rows: usize,
+}
+
+impl Default for LogBatchBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogBatchBuilder {
+    pub fn new() -> Self {
+        Self {
+            schema: log_schema(),
+            timestamp: TimestampMicrosecondBuilder::new().with_timezone("+00:00"),
+            level: StringBuilder::new(),
+            target: StringBuilder::new(),
+            line: UInt32Builder::new(),
+            message: StringBuilder::new(),
+            fields: StringBuilder::new(),
+            rows: 0,
+        }
+    }
+
+    /// rows since the last finish
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn push(&mut self, record: &FastLogRecord) {
+        let micros = record.now.duration_since(UNIX_EPOCH).map(|d| d.as_micros() as i64).unwrap_or(0);
+        self.timestamp.append_value(micros);
+        self.level.append_value(record.level.as_str());
+        self.target.append_value(&record.target);
+        self.line.append_option(record.line);
+        self.message.append_value(&record.args);
+        self.fields.append_option(record_fields(record));
+        self.rows += 1;
+    }
+
+    /// take the rows pushed so far as a batch
+    pub fn finish(&mut self) -> Result<RecordBatch, LogError> {
+        self.rows = 0;
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.timestamp.finish()),
+            Arc::new(self.level.finish()),
+            Arc::new(self.target.finish()),
+            Arc::new(self.line.finish()),
+            Arc::new(self.message.finish()),
+            Arc::new(self.fields.finish()),
+        ];
+        RecordBatch::try_new(self.schema.clone(), columns)
+            .map_err(|e| LogError::from(format!("[fast_log] arrow batch fail:{}", e)))
+    }
+}
+
+/// the json of the key-values, file, tags, trace_id and span_id, None if they are empty
+fn record_fields(record: &FastLogRecord) -> Option<String> {
+    let mut fields: serde_json::Map<String, serde_json::Value> = record.kv.iter().cloned().collect();
+    if !record.file.is_empty() {
+        fields.insert("file".to_string(), record.file.clone().into());
+    }
+    if !record.tags.is_empty() {
+        fields.insert("tags".to_string(), record.tags.clone().into());
+    }
+    if let Some(trace) = &record.trace {
+        fields.insert("trace_id".to_string(), trace.trace_id.clone().into());
+        fields.insert("span_id".to_string(), trace.span_id.clone().into());
+    }
+    if fields.is_empty() {
+        None
+    } else {
+        Some(serde_json::Value::Object(fields).to_string())
+    }
+}
+
+/// you need enable fast_log = { ... ,features=["arrow"]}
+/// batch records into arrow RecordBatch(see log_schema) of `batch_size` rows, a flush sends the rows so far.
+/// the batches go to a channel(ArrowIpcAppender::channel) for in-process analytics,
+/// or to a socket(ArrowIpcAppender::socket) in the Arrow IPC streaming format
+pub struct ArrowIpcAppender {
+    builder: RefCell<LogBatchBuilder>,
+    batch_size: usize,
+    sender: Option<Sender<RecordBatch>>,
+    handle: Option<JoinHandle<()>>,
+    closing: Arc<AtomicBool>,
+}
+
+impl ArrowIpcAppender {
+    /// the batches can be read from the receiver, at most `buffer_cap` batches wait, then new batches are dropped
+    pub fn channel(batch_size: usize, buffer_cap: usize) -> (ArrowIpcAppender, Receiver<RecordBatch>) {
+        let (sender, receiver) = crossbeam::channel::bounded(buffer_cap.max(1));
+        (
+            Self {
+                builder: RefCell::new(LogBatchBuilder::new()),
+                batch_size: batch_size.max(1),
+                sender: Some(sender),
+                handle: None,
+                closing: Arc::new(AtomicBool::new(false)),
+            },
+            receiver,
+        )
+    }
+
+    /// addr: "127.0.0.1:9000" or "unix:/tmp/logs.sock".
+    /// every connection is an IPC stream(schema, then batches), for example pyarrow.ipc.open_stream(conn.makefile("rb")).
+    /// it reconnects every second while the consumer is down and keeps at most `buffer_cap` batches
+    pub fn socket(addr: &str, batch_size: usize, buffer_cap: usize) -> ArrowIpcAppender {
+        let (sender, receiver) = crossbeam::channel::bounded::<RecordBatch>(buffer_cap.max(1));
+        let addr = addr.to_string();
+        let closing = Arc::new(AtomicBool::new(false));
+        let thread_closing = closing.clone();
+        let handle = std::thread::spawn(move || {
+            let schema = log_schema();
+            let mut writer: Option<StreamWriter<BufWriter<RelayConn>>> = None;
+            let mut exit_deadline: Option<Instant> = None;
+            for batch in receiver.iter() {
+                loop {
+                    if writer.is_none() {
+                        match RelayConn::connect(&addr)
+                            .map_err(|e| e.to_string())
+                            .and_then(|conn| StreamWriter::try_new_buffered(conn, &schema).map_err(|e| e.to_string()))
+                        {
+                            Ok(w) => writer = Some(w),
+                            Err(_) => {
+                                if thread_closing.load(Ordering::Relaxed) {
+                                    let deadline = *exit_deadline.get_or_insert(Instant::now() + EXIT_TIMEOUT);
+                                    if Instant::now() >= deadline {
+                                        crate::health::report_error(
+                                            "ArrowIpcAppender",
+                                            &format!(
+                                                "[fast_log] arrow ipc connect({}) fail, {} batches lost",
+                                                addr,
+                                                receiver.len() + 1
+                                            ),
+                                        );
+                                        return;
+                                    }
+                                }
+                                std::thread::sleep(Duration::from_secs(1));
+                                continue;
+                            }
+                        }
+                    }
+                    let w = writer.as_mut().unwrap();
+                    if w.write(&batch).and_then(|_| w.flush()).is_ok() {
+                        break;
+                    }
+                    writer = None;
+                }
+            }
+            //end of stream
+            if let Some(mut w) = writer {
+                w.finish();
+            }
+        });
+        Self {
+            builder: RefCell::new(LogBatchBuilder::new()),
+            batch_size: batch_size.max(1),
+            sender: Some(sender),
+            handle: Some(handle),
+            closing,
+        }
+    }
+
+    fn send_batch(&self, builder: &mut LogBatchBuilder) {
+        if builder.rows() == 0 {
+            return;
+        }
+        match builder.finish() {
+            Ok(batch) => {
+                if let Some(sender) = &self.sender {
+                    //full: the consumer is too slow, drop
+                    sender.try_send(batch);
+                }
+            }
+            Err(e) => crate::health::report_error("ArrowIpcAppender", &e.to_string()),
+        }
+    }
+}
+
+impl LogAppender for ArrowIpcAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        let mut builder = self.builder.borrow_mut();
+        match record.command {
+            Command::CommandRecord => {
+                builder.push(record);
+                if builder.rows() >= self.batch_size {
+                    self.send_batch(&mut builder);
+                }
+            }
+            Command::CommandFlush => self.send_batch(&mut builder),
+            _ => {}
+        }
+    }
+}
+
+impl Drop for ArrowIpcAppender {
+    /// send the last rows and end the stream, the pipeline drops appenders on exit
+    fn drop(&mut self) {
+        let mut builder = std::mem::take(self.builder.get_mut());
+        self.send_batch(&mut builder);
+        self.closing.store(true, Ordering::Relaxed);
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            handle.join();
+        }
+    }
+}
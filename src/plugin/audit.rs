@@ -0,0 +1,178 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::error::LogError;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+
+/// the prev hash of the first line
+pub const AUDIT_GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+/// checkpoint line head
+pub const AUDIT_CHECKPOINT: &str = "#checkpoint";
+
+/// you need enable fast_log = { ... ,features=["audit"]}
+/// tamper-evident log file. every line is `{hash of prev line} {log line}`,
+/// and every `checkpoint_lines` lines a checkpoint `#checkpoint {seq} {hash of prev line} {hmac}`
+/// signed by `key` is written. use verify_audit_file() to check the file.
+pub struct AuditAppender {
+    cell: RefCell<AuditAppenderData>,
+}
+
+pub struct AuditAppenderData {
+    file: File,
+    key: Vec<u8>,
+    checkpoint_lines: u64,
+    prev_hash: String,
+    lines: u64,
+    checkpoint_seq: u64,
+}
+
+/// sha256 hex of a full line(without line end)
+pub fn audit_hash(line: &str) -> String {
+    hex(&Sha256::digest(line.as_bytes()))
+}
+
+/// hmac-sha256 hex of a checkpoint
+pub fn audit_sign(key: &[u8], seq: u64, prev_hash: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac can take key of any size");
+    mac.update(format!("{} {}", seq, prev_hash).as_bytes());
+    hex(&mac.finalize().into_bytes())
+}
+
+fn hex(data: &[u8]) -> String {
+    let mut s = String::with_capacity(data.len() * 2);
+    for x in data {
+        s.push_str(&format!("{:02x}", x));
+    }
+    s
+}
+
+impl AuditAppender {
+    /// log_file_path: audit log file, an exist file continue its chain
+    /// key: checkpoint sign key
+    /// checkpoint_lines: write a checkpoint every n lines
+    pub fn new(log_file_path: &str, key: &[u8], checkpoint_lines: u64) -> Result<AuditAppender, LogError> {
+        let log_file_path = log_file_path.replace("\\", "/");
+        if let Some(right) = log_file_path.rfind('/') {
+            std::fs::create_dir_all(&log_file_path[0..right]);
+        }
+        let mut prev_hash = AUDIT_GENESIS.to_string();
+        let mut checkpoint_seq = 0;
+        if let Ok(file) = File::open(&log_file_path) {
+            for line in BufReader::new(file).lines() {
+                let line = line.map_err(|e| LogError::from(format!("[fast_log] read audit file fail:{}", e)))?;
+                if line.starts_with(AUDIT_CHECKPOINT) {
+                    checkpoint_seq += 1;
+                }
+                prev_hash = audit_hash(&line);
+            }
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_file_path)
+            .map_err(|e| LogError::from(format!("[fast_log] open audit file fail:{}", e)))?;
+        Ok(Self {
+            cell: RefCell::new(AuditAppenderData {
+                file,
+                key: key.to_vec(),
+                checkpoint_lines: checkpoint_lines.max(1),
+                prev_hash,
+                lines: 0,
+                checkpoint_seq,
+            }),
+        })
+    }
+}
+
+impl AuditAppenderData {
+    fn write_line(&mut self, line: &str) {
+        let line = format!("{} {}", self.prev_hash, line);
+        self.file.write_all(line.as_bytes());
+        self.file.write_all(b"\n");
+        self.prev_hash = audit_hash(&line);
+    }
+
+    fn write_checkpoint(&mut self) {
+        let sign = audit_sign(&self.key, self.checkpoint_seq, &self.prev_hash);
+        let line = format!("{} {} {} {}", AUDIT_CHECKPOINT, self.checkpoint_seq, self.prev_hash, sign);
+        self.file.write_all(line.as_bytes());
+        self.file.write_all(b"\n");
+        self.prev_hash = audit_hash(&line);
+        self.checkpoint_seq += 1;
+        self.lines = 0;
+    }
+}
+
+impl LogAppender for AuditAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        let mut data = self.cell.borrow_mut();
+        if record.command.eq(&Command::CommandFlush) {
+            if data.lines != 0 {
+                data.write_checkpoint();
+            }
+        } else {
+            for line in record.formated.lines() {
+                data.write_line(line);
+                data.lines += 1;
+            }
+            if data.lines >= data.checkpoint_lines {
+                data.write_checkpoint();
+            }
+        }
+        data.file.flush();
+    }
+}
+
+/// result of verify_audit_file()
+#[derive(Clone, Debug, Default)]
+pub struct AuditReport {
+    /// log lines checked
+    pub lines: u64,
+    /// checkpoints with a valid sign
+    pub checkpoints: u64,
+    /// lines after the last checkpoint, not covered by a sign
+    pub unsigned_lines: u64,
+}
+
+/// verify the hash chain and the checkpoint signs of an audit file.
+/// return Err with the broken line number if the file was changed
+pub fn verify_audit_file(log_file_path: &str, key: &[u8]) -> Result<AuditReport, LogError> {
+    let file = File::open(log_file_path)
+        .map_err(|e| LogError::from(format!("[fast_log] open audit file fail:{}", e)))?;
+    let mut report = AuditReport::default();
+    let mut prev_hash = AUDIT_GENESIS.to_string();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line_no = index + 1;
+        let line = line.map_err(|e| LogError::from(format!("[fast_log] read audit file fail:{}", e)))?;
+        if let Some(checkpoint) = line.strip_prefix(AUDIT_CHECKPOINT) {
+            let items: Vec<&str> = checkpoint.split_whitespace().collect();
+            let seq = items.first().and_then(|v| v.parse::<u64>().ok());
+            match (seq, items.get(1), items.get(2)) {
+                (Some(seq), Some(hash), Some(sign))
+                    if *hash == prev_hash && *sign == audit_sign(key, seq, hash)
+                        && seq == report.checkpoints => {}
+                _ => {
+                    return Err(LogError::from(format!(
+                        "[fast_log] audit checkpoint broken at line {}",
+                        line_no
+                    )));
+                }
+            }
+            report.checkpoints += 1;
+            report.unsigned_lines = 0;
+        } else {
+            if !line.starts_with(&prev_hash) || line.as_bytes().get(prev_hash.len()) != Some(&b' ') {
+                return Err(LogError::from(format!(
+                    "[fast_log] audit hash chain broken at line {}",
+                    line_no
+                )));
+            }
+            report.lines += 1;
+            report.unsigned_lines += 1;
+        }
+        prev_hash = audit_hash(&line);
+    }
+    Ok(report)
+}
@@ -0,0 +1,106 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::error::LogError;
+use crate::plugin::http::{HttpBatchConfig, HttpBatcher, HttpRequest};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+/// you need enable fast_log = { ... ,features=["azure"]}
+/// post records to a Log Analytics workspace with the HTTP Data Collector API,
+/// each batch is signed with the workspace shared key. records land in the table "{log_type}_CL"
+pub struct AzureMonitorAppender {
+    batcher: HttpBatcher,
+}
+
+impl AzureMonitorAppender {
+    /// shared_key: the base64 primary or secondary key of the workspace
+    pub fn new(
+        workspace_id: &str,
+        shared_key: &str,
+        log_type: &str,
+        config: HttpBatchConfig,
+    ) -> Result<AzureMonitorAppender, LogError> {
+        let key = STANDARD
+            .decode(shared_key.trim())
+            .map_err(|e| LogError::from(format!("[fast_log] azure shared key is not base64:{}", e)))?;
+        let url = format!(
+            "https://{}.ods.opinsights.azure.com/api/logs?api-version=2016-04-01",
+            workspace_id
+        );
+        let workspace_id = workspace_id.to_string();
+        let log_type = log_type.to_string();
+        let batcher = HttpBatcher::new(
+            "azure monitor",
+            config,
+            Box::new(move |batch| {
+                let body = serde_json::to_vec(batch).unwrap_or_default();
+                let date = Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+                let signature = sign(&key, body.len(), &date);
+                HttpRequest {
+                    url: url.clone(),
+                    headers: vec![
+                        ("Content-Type".to_string(), "application/json".to_string()),
+                        ("Log-Type".to_string(), log_type.clone()),
+                        ("x-ms-date".to_string(), date),
+                        ("time-generated-field".to_string(), "TimeGenerated".to_string()),
+                        (
+                            "Authorization".to_string(),
+                            format!("SharedKey {}:{}", workspace_id, signature),
+                        ),
+                    ],
+                    body,
+                }
+            }),
+        );
+        Ok(Self { batcher })
+    }
+
+    /// events dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.batcher.dropped()
+    }
+
+    fn event(&self, record: &FastLogRecord) -> Value {
+        let time: DateTime<Utc> = DateTime::from(record.now);
+        let mut event = json!({
+            "TimeGenerated": time.to_rfc3339_opts(SecondsFormat::Micros, true),
+            "Level": record.level.as_str(),
+            "Target": record.target,
+            "Message": record.args,
+            "ModulePath": record.module_path,
+            "File": record.file,
+            "Line": record.line,
+        });
+        if let Some(trace) = &record.trace {
+            event["TraceId"] = json!(trace.trace_id);
+            event["SpanId"] = json!(trace.span_id);
+        }
+        event
+    }
+}
+
+/// base64(hmac_sha256(key, "POST\n{len}\napplication/json\nx-ms-date:{date}\n/api/logs"))
+fn sign(key: &[u8], content_length: usize, date: &str) -> String {
+    let text = format!(
+        "POST\n{}\napplication/json\nx-ms-date:{}\n/api/logs",
+        content_length, date
+    );
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac can take key of any size");
+    mac.update(text.as_bytes());
+    STANDARD.encode(mac.finalize().into_bytes())
+}
+
+impl LogAppender for AzureMonitorAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.eq(&Command::CommandRecord) {
+            self.batcher.push(self.event(record));
+        }
+    }
+
+    fn flush(&self) {
+        self.batcher.flush();
+    }
+}
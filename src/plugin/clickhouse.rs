@@ -0,0 +1,120 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::plugin::http::{url_encode, HttpBatchConfig, HttpBatcher, HttpRequest};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+/// you need enable fast_log = { ... ,features=["http"]}
+/// insert records into a ClickHouse table with the HTTP interface(port 8123), a batch is one
+/// `INSERT INTO {table} FORMAT JSONEachRow`. the columns are
+/// timestamp, level, target, message, module_path, file, line, trace_id, span_id and host,
+/// see ClickHouseAppender::create_table_sql
+pub struct ClickHouseAppender {
+    batcher: HttpBatcher,
+    host: String,
+}
+
+impl ClickHouseAppender {
+    /// url: for example "http://clickhouse:8123", table: "db.logs".
+    /// async_insert: let the server buffer small inserts(async_insert=1, wait_for_async_insert=1),
+    /// better for many small batches
+    pub fn new(
+        url: &str,
+        table: &str,
+        user: &str,
+        password: &str,
+        async_insert: bool,
+        config: HttpBatchConfig,
+    ) -> ClickHouseAppender {
+        let mut url = format!(
+            "{}/?query={}",
+            url.trim_end_matches('/'),
+            url_encode(&format!("INSERT INTO {} FORMAT JSONEachRow", table))
+        );
+        if async_insert {
+            url.push_str("&async_insert=1&wait_for_async_insert=1");
+        }
+        let headers = vec![
+            ("X-ClickHouse-User".to_string(), user.to_string()),
+            ("X-ClickHouse-Key".to_string(), password.to_string()),
+            ("Content-Type".to_string(), "application/x-ndjson".to_string()),
+        ];
+        let batcher = HttpBatcher::new(
+            "clickhouse",
+            config,
+            Box::new(move |batch| {
+                let mut body = Vec::new();
+                for row in batch {
+                    serde_json::to_writer(&mut body, row);
+                    body.push(b'\n');
+                }
+                HttpRequest {
+                    url: url.clone(),
+                    headers: headers.clone(),
+                    body,
+                }
+            }),
+        );
+        Self {
+            batcher,
+            host: crate::plugin::syslog::local_hostname(),
+        }
+    }
+
+    /// a MergeTree table for the rows of ClickHouseAppender
+    pub fn create_table_sql(table: &str) -> String {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+    timestamp DateTime64(6, 'UTC'),
+    level LowCardinality(String),
+    target LowCardinality(String),
+    message String,
+    module_path LowCardinality(String),
+    file LowCardinality(String),
+    line Nullable(UInt32),
+    trace_id String,
+    span_id String,
+    host LowCardinality(String)
+) ENGINE = MergeTree
+PARTITION BY toDate(timestamp)
+ORDER BY (level, target, timestamp)",
+            table
+        )
+    }
+
+    /// rows dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.batcher.dropped()
+    }
+
+    fn row(&self, record: &FastLogRecord) -> Value {
+        let time: DateTime<Utc> = DateTime::from(record.now);
+        let (trace_id, span_id) = match &record.trace {
+            Some(trace) => (trace.trace_id.as_str(), trace.span_id.as_str()),
+            None => ("", ""),
+        };
+        json!({
+            "timestamp": time.format("%Y-%m-%d %H:%M:%S%.6f").to_string(),
+            "level": record.level.as_str(),
+            "target": record.target,
+            "message": record.args,
+            "module_path": record.module_path,
+            "file": record.file,
+            "line": record.line,
+            "trace_id": trace_id,
+            "span_id": span_id,
+            "host": self.host,
+        })
+    }
+}
+
+impl LogAppender for ClickHouseAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.eq(&Command::CommandRecord) {
+            self.batcher.push(self.row(record));
+        }
+    }
+
+    fn flush(&self) {
+        self.batcher.flush();
+    }
+}
@@ -0,0 +1,188 @@
+use crate::appender::{Command, FastLogRecord, LogAppender, RecordFormat};
+use crate::small_string::SmallString;
+use std::cell::Cell;
+use std::time::{Duration, SystemTime};
+
+/// combinators for every appender, for example
+/// ConsoleAppender {}.filtered(|r| r.level <= Level::Warn).rate_limited(100).boxed()
+/// writes at most 100 warnings and errors every second
+pub trait AppenderExt: LogAppender + Sized + 'static {
+    /// only the records `f` returns true for, commands always pass
+    fn filtered<F>(self, f: F) -> Filtered<Self, F>
+    where
+        F: Fn(&FastLogRecord) -> bool + Send,
+    {
+        Filtered { inner: self, f }
+    }
+
+    /// the appender writes the records formatted by `format` instead of the logger format
+    fn formatted<R: RecordFormat + 'static>(self, format: R) -> Formatted<Self, R> {
+        Formatted { inner: self, format }
+    }
+
+    /// at most `per_second` records every second, the others are dropped
+    fn rate_limited(self, per_second: u64) -> RateLimited<Self> {
+        RateLimited {
+            inner: self,
+            per_second,
+            window: Cell::new(crate::appender::now()),
+            count: Cell::new(0),
+            dropped: Cell::new(0),
+        }
+    }
+
+    fn boxed(self) -> Box<dyn LogAppender> {
+        Box::new(self)
+    }
+}
+
+impl<T: LogAppender + Sized + 'static> AppenderExt for T {}
+
+/// the hooks of a wrapper go to the inner appender
+macro_rules! forward_hooks {
+    () => {
+        fn attach(&self, pipeline: &crate::appender::PipelineSender) {
+            self.inner.attach(pipeline);
+        }
+
+        fn on_init(&self) {
+            self.inner.on_init();
+        }
+
+        fn on_rotate(&self) {
+            self.inner.on_rotate();
+        }
+
+        fn on_config_change(&self) {
+            self.inner.on_config_change();
+        }
+
+        fn flush(&self) {
+            self.inner.flush();
+        }
+
+        fn shutdown(&self) {
+            self.inner.shutdown();
+        }
+
+        fn type_name(&self) -> &'static str {
+            self.inner.type_name()
+        }
+    };
+}
+
+/// see AppenderExt::filtered
+pub struct Filtered<A, F> {
+    inner: A,
+    f: F,
+}
+
+impl<A, F> LogAppender for Filtered<A, F>
+where
+    A: LogAppender,
+    F: Fn(&FastLogRecord) -> bool + Send,
+{
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.ne(&Command::CommandRecord) || (self.f)(record) {
+            self.inner.do_log(record);
+        }
+    }
+
+    fn accepts(&self, record: &FastLogRecord) -> bool {
+        (self.f)(record) && self.inner.accepts(record)
+    }
+
+    forward_hooks!();
+}
+
+/// see AppenderExt::formatted
+pub struct Formatted<A, R> {
+    inner: A,
+    format: R,
+}
+
+impl<A, R: RecordFormat> Formatted<A, R> {
+    /// other appenders and subscribers still see the logger format
+    fn swap(&self, record: &mut FastLogRecord) -> SmallString {
+        let mut formated = SmallString::new();
+        self.format.format(record, &mut formated);
+        std::mem::replace(&mut record.formated, formated)
+    }
+}
+
+impl<A: LogAppender, R: RecordFormat> LogAppender for Formatted<A, R> {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.ne(&Command::CommandRecord) {
+            self.inner.do_log(record);
+            return;
+        }
+        let formated = self.swap(record);
+        self.inner.do_log(record);
+        record.formated = formated;
+    }
+
+    fn accepts(&self, record: &FastLogRecord) -> bool {
+        self.inner.accepts(record)
+    }
+
+    fn do_logs(&self, records: &mut [FastLogRecord]) {
+        let formated: Vec<SmallString> = records.iter_mut().map(|v| self.swap(v)).collect();
+        self.inner.do_logs(records);
+        for (record, formated) in records.iter_mut().zip(formated) {
+            record.formated = formated;
+        }
+    }
+
+    forward_hooks!();
+}
+
+/// see AppenderExt::rate_limited
+pub struct RateLimited<A> {
+    inner: A,
+    per_second: u64,
+    window: Cell<SystemTime>,
+    count: Cell<u64>,
+    dropped: Cell<u64>,
+}
+
+impl<A> RateLimited<A> {
+    /// records dropped by the limit
+    pub fn dropped(&self) -> u64 {
+        self.dropped.get()
+    }
+
+    fn allow(&self) -> bool {
+        let now = crate::appender::now();
+        //a window in the future: the clock went back(or a clock of clock::set_clock was installed)
+        let elapsed = now.duration_since(self.window.get()).unwrap_or(Duration::MAX);
+        if elapsed >= Duration::from_secs(1) {
+            self.window.set(now);
+            self.count.set(0);
+        }
+        if self.count.get() >= self.per_second {
+            self.dropped.set(self.dropped.get() + 1);
+            return false;
+        }
+        self.count.set(self.count.get() + 1);
+        true
+    }
+}
+
+impl<A: LogAppender> LogAppender for RateLimited<A> {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.ne(&Command::CommandRecord) {
+            self.inner.do_log(record);
+            return;
+        }
+        //records the inner appender ignores do not count
+        if self.inner.accepts(record) && self.allow() {
+            self.inner.do_log(record);
+        }
+    }
+
+    fn accepts(&self, record: &FastLogRecord) -> bool {
+        self.inner.accepts(record)
+    }
+
+    forward_hooks!();
+}
@@ -4,7 +4,65 @@ use crate::appender::{FastLogRecord, LogAppender};
 pub struct ConsoleAppender {}
 
 impl LogAppender for ConsoleAppender {
+    #[cfg(not(target_arch = "wasm32"))]
     fn do_log(&self, record: &mut FastLogRecord) {
         print!("{}", record.formated);
     }
+
+    /// wasm writes into the browser console
+    #[cfg(target_arch = "wasm32")]
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.formated.is_empty() {
+            return;
+        }
+        let data = wasm_bindgen::JsValue::from_str(record.formated.trim_end());
+        match record.level {
+            log::Level::Error => web_sys::console::error_1(&data),
+            log::Level::Warn => web_sys::console::warn_1(&data),
+            log::Level::Info => web_sys::console::info_1(&data),
+            log::Level::Debug => web_sys::console::debug_1(&data),
+            log::Level::Trace => web_sys::console::log_1(&data),
+        }
+    }
+}
+
+/// write into console, the lines are colored by level when stdout is a terminal and NO_COLOR is not set
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ColorConsoleAppender {
+    color: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ColorConsoleAppender {
+    pub fn new() -> Self {
+        use std::io::IsTerminal;
+        Self {
+            color: std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for ColorConsoleAppender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LogAppender for ColorConsoleAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if !self.color || record.formated.is_empty() {
+            print!("{}", record.formated);
+            return;
+        }
+        let color = match record.level {
+            log::Level::Error => "\x1b[31m",
+            log::Level::Warn => "\x1b[33m",
+            log::Level::Info => "\x1b[32m",
+            log::Level::Debug => "\x1b[36m",
+            log::Level::Trace => "\x1b[90m",
+        };
+        println!("{}{}\x1b[0m", color, record.formated.trim_end_matches('\n'));
+    }
 }
@@ -0,0 +1,115 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::plugin::http::{HttpBatchConfig, HttpBatcher, HttpRequest};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use std::time::UNIX_EPOCH;
+
+/// you need enable fast_log = { ... ,features=["http"]}
+/// post records as json logs to the Datadog logs intake (api v2), gzip compressed if `compress`
+pub struct DatadogAppender {
+    batcher: HttpBatcher,
+    service: String,
+    ddsource: String,
+    ddtags: String,
+    hostname: String,
+}
+
+impl DatadogAppender {
+    /// site: "datadoghq.com", "datadoghq.eu"... or a full intake url(for example a proxy)
+    /// ddtags: for example "env:prod,version:1.2"
+    pub fn new(
+        site: &str,
+        api_key: &str,
+        service: &str,
+        ddsource: &str,
+        ddtags: &str,
+        compress: bool,
+        config: HttpBatchConfig,
+    ) -> DatadogAppender {
+        let url = if site.starts_with("http://") || site.starts_with("https://") {
+            site.to_string()
+        } else {
+            format!("https://http-intake.logs.{}/api/v2/logs", site)
+        };
+        let api_key = api_key.to_string();
+        let batcher = HttpBatcher::new(
+            "datadog",
+            config,
+            Box::new(move |batch| {
+                let mut headers = vec![
+                    ("DD-API-KEY".to_string(), api_key.clone()),
+                    ("Content-Type".to_string(), "application/json".to_string()),
+                ];
+                let mut body = serde_json::to_vec(batch).unwrap_or_default();
+                if compress {
+                    let mut encoder = GzEncoder::new(Vec::with_capacity(body.len() / 4), Compression::default());
+                    if encoder.write_all(&body).is_ok() {
+                        if let Ok(v) = encoder.finish() {
+                            body = v;
+                            headers.push(("Content-Encoding".to_string(), "gzip".to_string()));
+                        }
+                    }
+                }
+                HttpRequest {
+                    url: url.clone(),
+                    headers,
+                    body,
+                }
+            }),
+        );
+        Self {
+            batcher,
+            service: service.to_string(),
+            ddsource: ddsource.to_string(),
+            ddtags: ddtags.to_string(),
+            hostname: crate::plugin::syslog::local_hostname(),
+        }
+    }
+
+    /// events dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.batcher.dropped()
+    }
+
+    fn event(&self, record: &FastLogRecord) -> Value {
+        let mut event = Map::new();
+        event.insert("ddsource".to_string(), json!(self.ddsource));
+        event.insert("ddtags".to_string(), json!(self.ddtags));
+        event.insert("service".to_string(), json!(self.service));
+        if !self.hostname.is_empty() {
+            event.insert("hostname".to_string(), json!(self.hostname));
+        }
+        event.insert("message".to_string(), json!(record.args));
+        event.insert("status".to_string(), json!(record.level.as_str().to_lowercase()));
+        let millis = record.now.duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+        event.insert("timestamp".to_string(), json!(millis as u64));
+        event.insert(
+            "logger".to_string(),
+            json!({
+                "name": record.target,
+                "module_path": record.module_path,
+                "file": record.file,
+                "line": record.line,
+            }),
+        );
+        if let Some(trace) = &record.trace {
+            event.insert("dd.trace_id".to_string(), json!(trace.trace_id));
+            event.insert("dd.span_id".to_string(), json!(trace.span_id));
+        }
+        Value::Object(event)
+    }
+}
+
+impl LogAppender for DatadogAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.eq(&Command::CommandRecord) {
+            self.batcher.push(self.event(record));
+        }
+    }
+
+    fn flush(&self) {
+        self.batcher.flush();
+    }
+}
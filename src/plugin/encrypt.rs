@@ -0,0 +1,139 @@
+use crate::appender::{Command, FastLogRecord, LogAppender, PipelineSender};
+use crate::error::LogError;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{AeadCore, XChaCha20Poly1305, XNonce};
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// nonce = 16 bytes random prefix + 8 bytes counter
+const NONCE_LEN: usize = 24;
+const PREFIX_LEN: usize = 16;
+
+/// you need enable fast_log = { ... ,features=["encrypt"]}
+/// encrypt every record (XChaCha20-Poly1305) before the inner appender writes it,
+/// so the file(even temp.log) never holds plaintext. each record becomes one hex line `{nonce}{ciphertext}`.
+/// the nonce prefix is rotated every `rotate_records` records. read with decrypt_line()/decrypt_file().
+pub struct EncryptAppender {
+    inner: Box<dyn LogAppender>,
+    cipher: XChaCha20Poly1305,
+    prefix: Cell<[u8; PREFIX_LEN]>,
+    counter: Cell<u64>,
+    rotate_records: u64,
+}
+
+impl EncryptAppender {
+    /// key: 32 bytes key
+    pub fn new(inner: Box<dyn LogAppender>, key: &[u8; 32], rotate_records: u64) -> Self {
+        Self {
+            inner,
+            cipher: XChaCha20Poly1305::new(key.into()),
+            prefix: Cell::new(new_prefix()),
+            counter: Cell::new(0),
+            rotate_records: rotate_records.max(1),
+        }
+    }
+
+    fn next_nonce(&self) -> XNonce {
+        let mut counter = self.counter.get();
+        if counter >= self.rotate_records {
+            self.prefix.set(new_prefix());
+            counter = 0;
+        }
+        self.counter.set(counter + 1);
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce[0..PREFIX_LEN].copy_from_slice(&self.prefix.get());
+        nonce[PREFIX_LEN..].copy_from_slice(&counter.to_be_bytes());
+        XNonce::clone_from_slice(&nonce)
+    }
+}
+
+fn new_prefix() -> [u8; PREFIX_LEN] {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let mut prefix = [0u8; PREFIX_LEN];
+    prefix.copy_from_slice(&nonce[0..PREFIX_LEN]);
+    prefix
+}
+
+impl LogAppender for EncryptAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.ne(&Command::CommandRecord) {
+            self.inner.do_log(record);
+            return;
+        }
+        let nonce = self.next_nonce();
+        let data = match self.cipher.encrypt(&nonce, record.formated.as_bytes()) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        let mut line = String::with_capacity((NONCE_LEN + data.len()) * 2 + 1);
+        for x in nonce.iter().chain(data.iter()) {
+            line.push_str(&format!("{:02x}", x));
+        }
+        line.push('\n');
+        //other appenders and subscribers still see the plaintext
+        let formated = std::mem::replace(&mut record.formated, line.into());
+        self.inner.do_log(record);
+        record.formated = formated;
+    }
+
+    fn accepts(&self, record: &FastLogRecord) -> bool {
+        self.inner.accepts(record)
+    }
+
+    fn attach(&self, pipeline: &PipelineSender) {
+        self.inner.attach(pipeline);
+    }
+
+    fn on_init(&self) {
+        self.inner.on_init();
+    }
+
+    fn on_rotate(&self) {
+        self.inner.on_rotate();
+    }
+
+    fn on_config_change(&self) {
+        self.inner.on_config_change();
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+
+    fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+}
+
+/// decrypt one line written by EncryptAppender
+pub fn decrypt_line(key: &[u8; 32], line: &str) -> Result<String, LogError> {
+    let line = line.trim();
+    if line.len() % 2 != 0 || line.len() < NONCE_LEN * 2 {
+        return Err(LogError::from("[fast_log] bad encrypted line"));
+    }
+    let mut data = Vec::with_capacity(line.len() / 2);
+    for index in (0..line.len()).step_by(2) {
+        let v = u8::from_str_radix(&line[index..index + 2], 16)
+            .map_err(|_| LogError::from("[fast_log] bad encrypted line"))?;
+        data.push(v);
+    }
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let plain = cipher
+        .decrypt(XNonce::from_slice(&data[0..NONCE_LEN]), &data[NONCE_LEN..])
+        .map_err(|_| LogError::from("[fast_log] decrypt fail, wrong key or changed data"))?;
+    String::from_utf8(plain).map_err(|e| LogError::from(e.to_string()))
+}
+
+/// decrypt a file written by EncryptAppender, one record per item
+pub fn decrypt_file(
+    key: &[u8; 32],
+    path: &str,
+) -> Result<impl Iterator<Item = Result<String, LogError>>, LogError> {
+    let file = File::open(path).map_err(|e| LogError::from(format!("[fast_log] open({}) fail:{}", path, e)))?;
+    let key = *key;
+    Ok(BufReader::new(file).lines().map(move |line| match line {
+        Ok(line) => decrypt_line(&key, &line),
+        Err(e) => Err(LogError::from(e.to_string())),
+    }))
+}
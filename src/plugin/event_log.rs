@@ -0,0 +1,152 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::error::LogError;
+use crate::plugin::file::FileAppender;
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, Write};
+use std::os::windows::process::CommandExt;
+use std::process::{Child, ChildStdin, Stdio};
+use std::thread::JoinHandle;
+
+/// the longest message of an event log entry(in chars)
+const MAX_MESSAGE: usize = 31839;
+
+const SOURCE_ENV: &str = "FAST_LOG_EVENT_SOURCE";
+
+/// register the source if it does not exist, SourceExists throws too without the rights to search every log
+const REGISTER_SCRIPT: &str = "$s=$env:FAST_LOG_EVENT_SOURCE
+try { if (-not [Diagnostics.EventLog]::SourceExists($s)) { [Diagnostics.EventLog]::CreateEventSource($s, 'Application') } }
+catch { [Console]::Error.WriteLine($_.Exception.Message); exit 1 }";
+
+/// write one entry per stdin line {"t":type,"m":message}, the lines are ascii json
+const WRITE_SCRIPT: &str = "$s=$env:FAST_LOG_EVENT_SOURCE
+while (($l=[Console]::In.ReadLine()) -ne $null) {
+  try { $e=ConvertFrom-Json $l; [Diagnostics.EventLog]::WriteEntry($s, $e.m, $e.t) }
+  catch { [Console]::Error.WriteLine($_.Exception.Message) }
+}";
+
+fn powershell(script: &str, source: &str) -> std::process::Command {
+    let mut command = std::process::Command::new("powershell.exe");
+    command
+        .args(["-NoLogo", "-NoProfile", "-NonInteractive", "-Command", script])
+        .env(SOURCE_ENV, source);
+    //CREATE_NO_WINDOW, no console window for a service or a gui app
+    command.creation_flags(0x0800_0000);
+    command
+}
+
+/// register `source` in the Application log, a no-op if it exists. registering needs administrator rights,
+/// usually done once by the installer or the first start of the service
+pub fn register_event_source(source: &str) -> Result<(), LogError> {
+    let output = powershell(REGISTER_SCRIPT, source)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| LogError::from(format!("[fast_log] run powershell fail: {}", e)))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(LogError::from(format!(
+        "[fast_log] register event source {} fail: {}",
+        source,
+        String::from_utf8_lossy(&output.stderr).trim()
+    )))
+}
+
+/// json with the non-ascii chars escaped, powershell reads stdin in the console code page
+fn ascii_json(value: &serde_json::Value) -> String {
+    let json = value.to_string();
+    let mut out = String::with_capacity(json.len());
+    for c in json.chars() {
+        if c.is_ascii() {
+            out.push(c);
+        } else {
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                out.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    out
+}
+
+/// write records into the Windows Event Log(Application) with the registered `source`, see register_event_source.
+/// Error->Error, Warn->Warning, others->Information. the entries are written by one powershell process,
+/// its errors(for example an unregistered source) show in fast_log::health()
+pub struct EventLogAppender {
+    child: Child,
+    stdin: RefCell<Option<ChildStdin>>,
+    errors: Option<JoinHandle<()>>,
+}
+
+impl EventLogAppender {
+    pub fn new(source: &str) -> Result<Self, LogError> {
+        let mut child = powershell(WRITE_SCRIPT, source)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| LogError::from(format!("[fast_log] run powershell fail: {}", e)))?;
+        let stdin = child.stdin.take();
+        let errors = child.stderr.take().map(|stderr| {
+            std::thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    if !line.trim().is_empty() {
+                        crate::health::report_error("EventLogAppender", &format!("[fast_log] event log: {}", line));
+                    }
+                }
+            })
+        });
+        Ok(Self {
+            child,
+            stdin: RefCell::new(stdin),
+            errors,
+        })
+    }
+}
+
+impl LogAppender for EventLogAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.ne(&Command::CommandRecord) {
+            return;
+        }
+        let kind = match record.level {
+            log::Level::Error => "Error",
+            log::Level::Warn => "Warning",
+            _ => "Information",
+        };
+        let message: String = record.args.chars().take(MAX_MESSAGE).collect();
+        let line = ascii_json(&serde_json::json!({"t": kind, "m": message}));
+        let mut stdin = self.stdin.borrow_mut();
+        let result = match stdin.as_mut() {
+            Some(pipe) => writeln!(pipe, "{}", line).and_then(|_| pipe.flush()),
+            None => return,
+        };
+        if let Err(e) = result {
+            crate::health::report_error("EventLogAppender", &format!("[fast_log] event log writer exited: {}", e));
+            stdin.take();
+        }
+    }
+}
+
+impl Drop for EventLogAppender {
+    /// close stdin and wait for the written entries
+    fn drop(&mut self) {
+        self.stdin.borrow_mut().take();
+        self.child.wait();
+        if let Some(handle) = self.errors.take() {
+            handle.join();
+        }
+    }
+}
+
+/// the deployment path of a windows service: register `source` and log into the Event Log,
+/// if the registration is denied(not an administrator) or powershell is missing append into `fallback_file`.
+/// the reason of a fallback shows in fast_log::health()
+pub fn event_log_or_file(source: &str, fallback_file: &str) -> Box<dyn LogAppender> {
+    match register_event_source(source).and_then(|_| EventLogAppender::new(source)) {
+        Ok(appender) => Box::new(appender),
+        Err(LogError::E(e)) => {
+            crate::health::report_error("EventLogAppender", &format!("{}, logging into {}", e, fallback_file));
+            Box::new(FileAppender::new(fallback_file))
+        }
+    }
+}
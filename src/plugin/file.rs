@@ -1,40 +1,464 @@
-use crate::appender::{FastLogRecord, LogAppender};
-use std::cell::RefCell;
-use std::fs::{File, OpenOptions, DirBuilder};
-use std::io::Write;
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use std::cell::{Cell, RefCell};
+use std::fs::{File, OpenOptions};
+use parking_lot::Mutex;
+use std::io::{ErrorKind, IoSlice, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// open flags of the log files
+#[derive(Clone, Debug, Default)]
+pub struct OpenFlags {
+    /// O_APPEND: every write goes to the end of the file, safe when several processes write one file.
+    /// FileAppender always appends
+    pub append: bool,
+    /// O_DSYNC: a write returns once the data is on disk(FILE_FLAG_WRITE_THROUGH on windows)
+    pub dsync: bool,
+    /// O_SYNC: as dsync, and the metadata(size, mtime) is on disk too
+    pub sync: bool,
+    /// more flags of open(2), for example libc::O_DIRECT. O_DIRECT needs block aligned writes,
+    /// the records are not aligned, so only use it with a file system which allows that
+    pub custom: i32,
+}
+
+impl OpenFlags {
+    pub fn apply(&self, options: &mut OpenOptions) {
+        if self.append {
+            options.append(true);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            let mut flags = self.custom;
+            if self.dsync {
+                flags |= libc::O_DSYNC;
+            }
+            if self.sync {
+                flags |= libc::O_SYNC;
+            }
+            options.custom_flags(flags);
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::OpenOptionsExt;
+            //FILE_FLAG_WRITE_THROUGH
+            let mut flags = self.custom as u32;
+            if self.dsync || self.sync {
+                flags |= 0x8000_0000;
+            }
+            options.custom_flags(flags);
+        }
+    }
+}
+
+/// when the written records are fsynced to disk
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SyncPolicy {
+    /// the os writes the page cache back
+    #[default]
+    Never,
+    /// fsync on every flush(fast_log::flush and exit)
+    OnFlush,
+    /// group commit: at most one fsync every interval for all the writes and flushes in it,
+    /// a background thread syncs, so the writes never wait
+    Interval(Duration),
+}
+
+struct SyncShared {
+    //a handle of the current file for the sync thread
+    file: Mutex<Option<File>>,
+    dirty: AtomicBool,
+    closing: AtomicBool,
+}
+
+/// fsync of a file appender by its SyncPolicy
+pub(crate) struct FileSyncer {
+    policy: SyncPolicy,
+    shared: Arc<SyncShared>,
+}
+
+impl FileSyncer {
+    pub(crate) fn new(policy: SyncPolicy, file: &File) -> Self {
+        let shared = Arc::new(SyncShared {
+            file: Mutex::new(None),
+            dirty: AtomicBool::new(false),
+            closing: AtomicBool::new(false),
+        });
+        let syncer = Self { policy, shared };
+        if let SyncPolicy::Interval(interval) = policy {
+            syncer.set_file(file);
+            let shared = syncer.shared.clone();
+            std::thread::spawn(move || {
+                while !shared.closing.load(Ordering::Relaxed) {
+                    std::thread::sleep(interval);
+                    if shared.dirty.swap(false, Ordering::AcqRel) {
+                        if let Some(file) = shared.file.lock().as_ref() {
+                            file.sync_data();
+                        }
+                    }
+                }
+            });
+        }
+        syncer
+    }
+
+    /// the appender opened a new file
+    pub(crate) fn set_file(&self, file: &File) {
+        if let SyncPolicy::Interval(_) = self.policy {
+            *self.shared.file.lock() = file.try_clone().ok();
+        }
+    }
+
+    pub(crate) fn written(&self) {
+        self.shared.dirty.store(true, Ordering::Release);
+    }
+
+    /// a flush request, with SyncPolicy::Interval it waits for the next group commit
+    pub(crate) fn flush(&self, file: &File) {
+        match self.policy {
+            SyncPolicy::Never => {}
+            SyncPolicy::OnFlush => {
+                if self.shared.dirty.swap(false, Ordering::AcqRel) {
+                    file.sync_data();
+                }
+            }
+            SyncPolicy::Interval(_) => self.written(),
+        }
+    }
+
+    /// sync what is left, on shutdown
+    pub(crate) fn close(&self, file: &File) {
+        self.shared.closing.store(true, Ordering::Relaxed);
+        if self.policy != SyncPolicy::Never && self.shared.dirty.swap(false, Ordering::AcqRel) {
+            file.sync_data();
+        }
+    }
+}
+
+impl Drop for FileSyncer {
+    fn drop(&mut self) {
+        self.shared.closing.store(true, Ordering::Relaxed);
+    }
+}
+
+/// start of the header and trailer lines of the log files, the rest of the line is json
+pub const META_PREFIX: &str = "#fast_log ";
+/// version of the header and trailer json
+pub const META_VERSION: u32 = 1;
+
+/// the header written at the start of every new log file and the trailer written when a file is rotated,
+/// so archived files tell where they come from. for example FileMeta::new(env!("CARGO_PKG_VERSION"))
+#[derive(Clone, Debug)]
+pub struct FileMeta {
+    hostname: String,
+    pid: u32,
+    version: String,
+    start: String,
+}
+
+impl FileMeta {
+    /// version: the version of the binary
+    pub fn new(version: &str) -> Self {
+        Self {
+            hostname: crate::plugin::syslog::local_hostname(),
+            pid: std::process::id(),
+            version: version.to_string(),
+            start: chrono::Local::now().to_rfc3339(),
+        }
+    }
+
+    /// the header line: hostname, pid, binary version, process start time, file create time and meta version
+    pub fn header(&self) -> String {
+        let json = serde_json::json!({
+            "type": "header",
+            "meta_version": META_VERSION,
+            "hostname": self.hostname,
+            "pid": self.pid,
+            "version": self.version,
+            "start": self.start,
+            "created": chrono::Local::now().to_rfc3339(),
+        });
+        format!("{}{}\n", META_PREFIX, json)
+    }
+
+    /// the trailer line: rotate time and the bytes of the file before it
+    pub fn trailer(&self, bytes: u64) -> String {
+        let json = serde_json::json!({
+            "type": "trailer",
+            "meta_version": META_VERSION,
+            "pid": self.pid,
+            "closed": chrono::Local::now().to_rfc3339(),
+            "bytes": bytes,
+        });
+        format!("{}{}\n", META_PREFIX, json)
+    }
+}
+
+/// parse a header or trailer line
+pub fn parse_meta(line: &str) -> Option<serde_json::Value> {
+    serde_json::from_str(line.strip_prefix(META_PREFIX)?).ok()
+}
 
 /// only write append into file
 pub struct FileAppender {
     file: RefCell<File>,
+    path: String,
+    flags: OpenFlags,
+    syncer: FileSyncer,
+    meta: Option<FileMeta>,
+    //internal error already reported
+    notified: Cell<bool>,
+    path_check: PathCheck,
 }
 
 impl FileAppender {
     pub fn new(log_file_path: &str) -> FileAppender {
+        Self::new_with_flags(log_file_path, OpenFlags::default())
+    }
+
+    /// for example OpenFlags { dsync: true, ..Default::default() } when every record must be on disk
+    pub fn new_with_flags(log_file_path: &str, flags: OpenFlags) -> FileAppender {
         let log_file_path = log_file_path.replace("\\", "/");
-        if let Some(right) = log_file_path.rfind("/") {
-            let path = &log_file_path[0..right];
-            std::fs::create_dir_all(path);
-        }
+        let file = open_file(&log_file_path, &flags).unwrap();
         Self {
-            file: RefCell::new(
-                OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(&log_file_path)
-                    .unwrap(),
-            ),
+            syncer: FileSyncer::new(SyncPolicy::Never, &file),
+            file: RefCell::new(file),
+            path: log_file_path,
+            flags,
+            meta: None,
+            notified: Cell::new(false),
+            path_check: PathCheck::new(),
+        }
+    }
+
+    /// write a header(see FileMeta) to every new file and a trailer to a file which was moved away(logrotate)
+    pub fn meta(mut self, meta: FileMeta) -> Self {
+        write_header(self.file.get_mut(), &meta);
+        self.meta = Some(meta);
+        self
+    }
+
+    /// fsync the file by the policy, for example SyncPolicy::Interval(Duration::from_millis(100))
+    pub fn sync_policy(mut self, policy: SyncPolicy) -> Self {
+        self.syncer = FileSyncer::new(policy, self.file.get_mut());
+        self
+    }
+
+    /// the log dir was removed at runtime: create dir and file again
+    fn recreate(&self) -> bool {
+        if !self.notified.replace(true) {
+            crate::health::report_error(
+                "FileAppender",
+                &format!("[fast_log] log file {} was removed, recreate it", self.path),
+            );
+        }
+        self.reopen()
+    }
+
+    /// open the file path again, for example after logrotate moved the file
+    pub fn reopen(&self) -> bool {
+        match open_file(&self.path, &self.flags) {
+            Ok(mut file) => {
+                if let Some(meta) = &self.meta {
+                    let mut old = self.file.borrow_mut();
+                    if !same_file(&old, &file) {
+                        let bytes = old.metadata().map(|m| m.len()).unwrap_or(0);
+                        old.write_all(meta.trailer(bytes).as_bytes());
+                    }
+                    write_header(&mut file, meta);
+                }
+                self.syncer.set_file(&file);
+                *self.file.borrow_mut() = file;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// write the header if the file is empty
+pub(crate) fn write_header(file: &mut File, meta: &FileMeta) -> usize {
+    if file.metadata().map(|m| m.len()).unwrap_or(1) != 0 {
+        return 0;
+    }
+    let header = meta.header();
+    match file.write_all(header.as_bytes()) {
+        Ok(_) => header.len(),
+        Err(_) => 0,
+    }
+}
+
+/// the two handles are one file, true if that is unknown so no trailer goes into the middle of a file
+fn same_file(a: &File, b: &File) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if let (Ok(a), Ok(b)) = (a.metadata(), b.metadata()) {
+            return a.dev() == b.dev() && a.ino() == b.ino();
         }
     }
+    true
+}
+
+/// where the path of an open file leads now
+pub(crate) enum PathState {
+    Same,
+    /// the file or its dir was removed, writes go to an unlinked file
+    Removed,
+    /// an other file is there, for example logrotate moved the file and created a new one
+    Replaced,
+}
+
+/// a write checks the path(path_state, two stats) at most once per interval, a flush always does
+const PATH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// rate limit of path_state on the write path
+pub(crate) struct PathCheck(Cell<Option<Instant>>);
+
+impl PathCheck {
+    pub(crate) fn new() -> Self {
+        Self(Cell::new(None))
+    }
+
+    /// true if the path was not checked for PATH_CHECK_INTERVAL, it counts as checked then
+    pub(crate) fn due(&self) -> bool {
+        let now = Instant::now();
+        match self.0.get() {
+            Some(last) if now.duration_since(last) < PATH_CHECK_INTERVAL => false,
+            _ => {
+                self.0.set(Some(now));
+                true
+            }
+        }
+    }
+}
+
+/// stat the path and compare it with the open file(dev/ino, unix only). writes into an unlinked file
+/// succeed on unix, so a removed file is not noticed by a write error
+pub(crate) fn path_state(path: &str, file: &File) -> PathState {
+    match std::fs::metadata(path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => PathState::Removed,
+        Err(_) => PathState::Same,
+        #[cfg(unix)]
+        Ok(path_meta) => {
+            use std::os::unix::fs::MetadataExt;
+            match file.metadata() {
+                Ok(m) if m.dev() != path_meta.dev() || m.ino() != path_meta.ino() => PathState::Replaced,
+                _ => PathState::Same,
+            }
+        }
+        #[cfg(not(unix))]
+        Ok(_) => {
+            let _ = file;
+            PathState::Same
+        }
+    }
+}
+
+/// create parent dir and open file as append mode
+fn open_file(log_file_path: &str, flags: &OpenFlags) -> std::io::Result<File> {
+    if let Some(right) = log_file_path.rfind('/') {
+        let path = &log_file_path[0..right];
+        std::fs::create_dir_all(path);
+    }
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    flags.apply(&mut options);
+    options.open(log_file_path)
 }
 
 impl LogAppender for FileAppender {
     fn do_log(&self, record: &mut FastLogRecord) {
-        let mut log_file = self.file.borrow_mut();
-        let mut buf = vec![];
-        for x in record.formated.bytes() {
-            buf.push(x);
+        if record.command.eq(&Command::CommandFlush) {
+            self.check_path();
+            self.syncer.flush(&self.file.borrow());
+            return;
+        }
+        if record.command.eq(&Command::CommandReopen) {
+            self.reopen();
+            return;
+        }
+        self.write(&[record.formated.as_bytes()]);
+    }
+
+    /// the whole batch in one writev, the records are not copied into one buffer
+    fn do_logs(&self, records: &mut [FastLogRecord]) {
+        let bufs: Vec<&[u8]> = records.iter().map(|v| v.formated.as_bytes()).collect();
+        self.write(&bufs);
+    }
+
+    fn shutdown(&self) {
+        self.syncer.close(&self.file.borrow());
+    }
+}
+
+impl FileAppender {
+    /// the file was removed or replaced since it was opened: open the path again
+    fn check_path(&self) {
+        let state = path_state(&self.path, &self.file.borrow());
+        match state {
+            PathState::Same => {}
+            PathState::Removed => {
+                self.recreate();
+            }
+            PathState::Replaced => {
+                self.reopen();
+            }
+        }
+    }
+
+    fn write(&self, bufs: &[&[u8]]) {
+        if self.path_check.due() {
+            self.check_path();
+        }
+        let r = write_all_vectored(&mut *self.file.borrow_mut(), bufs);
+        if let Err(e) = r {
+            if e.kind() == ErrorKind::NotFound && self.recreate() {
+                write_all_vectored(&mut *self.file.borrow_mut(), bufs);
+            } else {
+                crate::health::report_error("FileAppender", &format!("[fast_log] write {} fail:{}", self.path, e));
+            }
+        }
+        self.file.borrow_mut().flush();
+        self.syncer.written();
+    }
+}
+
+/// reserve `len` bytes of disk for the file without changing its size(fallocate FALLOC_FL_KEEP_SIZE),
+/// so appends do not allocate blocks. only on linux, elsewhere it does nothing
+pub fn preallocate(file: &File, len: u64) -> std::io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use nix::fcntl::{fallocate, FallocateFlags};
+        fallocate(file, FallocateFlags::FALLOC_FL_KEEP_SIZE, 0, len as libc::off_t)?;
+    }
+    Ok(())
+}
+
+/// write all bufs with write_vectored(writev), a short write continues with the rest. return the bytes written
+pub(crate) fn write_all_vectored<W: Write>(w: &mut W, bufs: &[&[u8]]) -> std::io::Result<usize> {
+    let mut bufs: Vec<&[u8]> = bufs.iter().filter(|v| !v.is_empty()).copied().collect();
+    let total = bufs.iter().map(|v| v.len()).sum();
+    //the first buf not written completely
+    let mut first = 0;
+    while first < bufs.len() {
+        let slices: Vec<IoSlice> = bufs[first..].iter().map(|v| IoSlice::new(v)).collect();
+        match w.write_vectored(&slices) {
+            Ok(0) => return Err(ErrorKind::WriteZero.into()),
+            Ok(mut n) => {
+                //IoSlice::advance_slices needs rust 1.81
+                while n > 0 && n >= bufs[first].len() {
+                    n -= bufs[first].len();
+                    first += 1;
+                }
+                if n > 0 {
+                    bufs[first] = &bufs[first][n..];
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
         }
-        log_file.write_all(buf.as_slice());
-        log_file.flush();
     }
+    Ok(total)
 }
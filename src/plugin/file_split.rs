@@ -1,18 +1,23 @@
 use std::cell::RefCell;
 use std::fs::{DirBuilder, DirEntry, File, OpenOptions};
-use std::io::{BufRead, BufReader, Seek, SeekFrom, Write, Error};
+use std::io::{BufRead, BufReader, ErrorKind, Seek, SeekFrom, Write, Error};
 
-use chrono::{Local, NaiveDateTime};
-use zip::write::FileOptions;
+use chrono::{DateTime, Local, NaiveDateTime};
 
-use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::appender::{Command, FastLogRecord, LogAppender, PipelineSender};
 use crate::consts::LogSize;
 use std::ops::Sub;
-use std::time::Duration;
-use may::go;
-use may::sync::mpsc::{Receiver, Sender};
-use zip::result::ZipResult;
+use std::sync::atomic::{AtomicU64, Ordering};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crossbeam::channel::{Receiver, RecvTimeoutError, Sender};
 use crate::error::LogError;
+use crate::plugin::file::{
+    path_state, preallocate, write_all_vectored, write_header, FileMeta, FileSyncer, OpenFlags, PathCheck, PathState,
+    SyncPolicy,
+};
 
 /// .zip or .lz4 or any one packer
 pub trait Packer: Send {
@@ -26,6 +31,81 @@ pub trait Packer: Send {
 /// split log file allow compress log
 pub struct FileSplitAppender {
     cell: RefCell<FileSplitAppenderData>,
+    //the saver thread, joined on drop
+    saver: Option<JoinHandle<()>>,
+    //the pipeline running the appender(see LogAppender::attach), shared with the split timer
+    pipeline: Arc<Mutex<Option<PipelineSender>>>,
+}
+
+/// the longest sleep of the time split timer, it sees a changed clock(clock::set_clock) after it
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// when temp.log is split. a LogSize is SplitType::BySize
+#[derive(Copy, Clone, Debug)]
+pub enum SplitType {
+    /// the temp file reaches the size
+    BySize(LogSize),
+    /// every period of the local time regardless of the size, Duration::from_secs(86400) splits at midnight,
+    /// Duration::from_secs(3600) every hour. a temp file without records is not split
+    ByTime(Duration),
+    /// the size or the period, whichever comes first
+    BySizeOrTime(LogSize, Duration),
+}
+
+impl SplitType {
+    fn max_bytes(&self) -> usize {
+        match self {
+            SplitType::BySize(size) | SplitType::BySizeOrTime(size, _) => size.get_len(),
+            SplitType::ByTime(_) => usize::MAX,
+        }
+    }
+
+    fn period(&self) -> Option<Duration> {
+        match self {
+            SplitType::BySize(_) => None,
+            SplitType::ByTime(period) | SplitType::BySizeOrTime(_, period) => Some(*period),
+        }
+    }
+}
+
+impl From<LogSize> for SplitType {
+    fn from(size: LogSize) -> Self {
+        SplitType::BySize(size)
+    }
+}
+
+/// the next multiple of `period` after `now` in the local time, a day period is the next midnight
+fn next_split(period: Duration, now: SystemTime) -> SystemTime {
+    let offset = DateTime::<Local>::from(now).offset().local_minus_utc() as i128 * 1_000_000_000;
+    let period = period.as_nanos().max(1) as i128;
+    let local = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as i128 + offset;
+    let next = (local.div_euclid(period) + 1) * period - offset;
+    UNIX_EPOCH + Duration::from_nanos(next.max(0) as u64)
+}
+
+/// send a Command::CommandTick into the pipeline when a period ends so an idle temp file is split too,
+/// until `stop` is dropped
+fn spawn_split_timer(period: Duration, stop: Receiver<()>, pipeline: Arc<Mutex<Option<PipelineSender>>>) {
+    std::thread::spawn(move || {
+        let mut next = next_split(period, crate::appender::now());
+        loop {
+            let wait = next.duration_since(crate::appender::now()).unwrap_or_default();
+            match stop.recv_timeout(wait.min(TICK_INTERVAL)) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => break,
+            }
+            let now = crate::appender::now();
+            if now >= next {
+                if let Some(pipeline) = pipeline.lock().as_ref() {
+                    pipeline.send(Command::CommandTick);
+                }
+            }
+            //due, or the clock was set back(for example clock::set_clock after the appender was made)
+            if now >= next || next.duration_since(now).unwrap_or_default() > period {
+                next = next_split(period, now);
+            }
+        }
+    });
 }
 
 ///log data pack
@@ -33,6 +113,8 @@ pub struct LogPack {
     pub dir: String,
     pub rolling: RollingType,
     pub new_log_name: String,
+    /// the ack of the flush which sent this pack, dropped once it is saved(see fast_log::flush_and_wait)
+    pub flush_ack: Option<Sender<()>>,
 }
 
 ///rolling keep type
@@ -86,7 +168,7 @@ impl RollingType {
                     return;
                 }
                 let duration = duration.unwrap();
-                let now = Local::now().naive_local();
+                let now = DateTime::<Local>::from(crate::appender::now()).naive_local();
                 for index in 0..paths_vec.len() {
                     let item = &paths_vec[index];
                     let file_name = item.file_name();
@@ -108,7 +190,8 @@ impl RollingType {
             if let Some(v) = time_str.find(".") {
                 time_str = time_str[0..v].to_string();
             }
-            let time = chrono::NaiveDateTime::parse_from_str(&time_str, "%Y_%m_%dT%H_%M_%S");
+            //a time without fraction is padded with '_'
+            let time = chrono::NaiveDateTime::parse_from_str(time_str.trim_end_matches('_'), "%Y_%m_%dT%H_%M_%S");
             if let Ok(time) = time {
                 return Some(time);
             }
@@ -121,30 +204,250 @@ impl RollingType {
 /// Memory space swop running time , reduces the number of repeated queries for IO
 pub struct FileSplitAppenderData {
     max_split_bytes: usize,
+    //the time split period and when the current period ends, set by the first record
+    split_period: Option<Duration>,
+    next_split: Option<SystemTime>,
+    //temp_bytes of the temp file without records(the header)
+    empty_bytes: usize,
+    //stops the split timer on drop
+    _timer: Option<Sender<()>>,
     dir_path: String,
     file: File,
-    sender: Sender<LogPack>,
+    //taken on drop, the saver thread exits once the packs before are saved
+    sender: Option<Sender<LogPack>>,
     rolling_type: RollingType,
     //cache data
     temp_bytes: usize,
+    flags: OpenFlags,
+    syncer: FileSyncer,
+    //split when temp_bytes * ratio reaches it
+    packed_target: Option<usize>,
+    //packed size / log size of the last packs, f64 bits
+    ratio: Arc<AtomicU64>,
+    //reserve max_split_bytes for every temp file
+    preallocate: bool,
+    meta: Option<FileMeta>,
+    //internal error already reported
+    notified: bool,
+    path_check: PathCheck,
 }
 
 impl FileSplitAppenderData {
-    pub fn send_pack(&mut self) {
+    /// flush_ack: dropped by the saver thread once the pack is saved
+    pub fn send_pack(&mut self, flush_ack: Option<Sender<()>>) {
         let first_file_path = format!("{}{}.log", self.dir_path, "temp");
         let new_log_name = format!(
             "{}{}{}.log",
             self.dir_path,
             "temp",
-            format!("{:29}", Local::now().format("%Y_%m_%dT%H_%M_%S%.f")).replace(" ", "_")
+            format!("{:29}", DateTime::<Local>::from(crate::appender::now()).format("%Y_%m_%dT%H_%M_%S%.f")).replace(" ", "_")
+        );
+        if let Some(meta) = &self.meta {
+            let trailer = meta.trailer(self.temp_bytes as u64);
+            self.file.write_all(trailer.as_bytes());
+        }
+        //the full file goes to the pack thread as is and the writes switch to a new temp.log at once.
+        //a file which can not be renamed(open on windows) is copied and truncated
+        let switched = self.switch(&first_file_path, &new_log_name);
+        if !switched {
+            if let Err(e) = std::fs::copy(&first_file_path, &new_log_name) {
+                if e.kind() == ErrorKind::NotFound && self.recreate() {
+                    //the removed temp file is still open, save what it holds
+                    self.file.rewind();
+                    if let Ok(mut new_log) = File::create(&new_log_name) {
+                        std::io::copy(&mut self.file, &mut new_log);
+                    }
+                    if let Ok(file) = open_temp_file(&first_file_path, &self.flags) {
+                        self.syncer.set_file(&file);
+                        self.file = file;
+                    }
+                }
+            }
+        }
+        let args = format!("rotated {}temp.log to {}", self.dir_path, new_log_name);
+        crate::budget::charge(std::mem::size_of::<LogPack>() + self.dir_path.len() + new_log_name.len());
+        if let Some(sender) = &self.sender {
+            sender.send(LogPack {
+                dir: self.dir_path.clone(),
+                rolling: self.rolling_type.clone(),
+                new_log_name: new_log_name,
+                flush_ack,
+            });
+        }
+        if !switched {
+            self.truncate();
+        }
+        crate::diagnostics::emit(log::Level::Info, "rotate", args);
+    }
+
+    /// rename temp.log to the pack name and write a new temp.log, false if the file was not renamed
+    fn switch(&mut self, first_file_path: &str, new_log_name: &str) -> bool {
+        if std::fs::rename(first_file_path, new_log_name).is_err() {
+            return false;
+        }
+        match open_temp_file(first_file_path, &self.flags) {
+            Ok(file) => {
+                self.syncer.set_file(&file);
+                self.file = file;
+                self.temp_bytes = 0;
+                self.start_file();
+                true
+            }
+            Err(_) => {
+                //keep writing the old file
+                if std::fs::rename(new_log_name, first_file_path).is_ok() {
+                    return false;
+                }
+                //it can not be renamed back either: it is packed as is, the writes must not go into it.
+                //a read only handle fails them(reported), the next record opens temp.log again(check_temp)
+                if let Ok(file) = OpenOptions::new().read(true).open(new_log_name) {
+                    self.syncer.set_file(&file);
+                    self.file = file;
+                }
+                self.temp_bytes = 0;
+                true
+            }
+        }
+    }
+
+    /// the log dir was removed at runtime: create dir again, return is created
+    pub fn recreate(&mut self) -> bool {
+        if !self.notified {
+            self.notified = true;
+            crate::health::report_error(
+                "FileSplitAppender",
+                &format!("[fast_log] log dir {} was removed, recreate it", self.dir_path),
+            );
+        }
+        self.dir_path.is_empty() || std::fs::create_dir_all(&self.dir_path).is_ok()
+    }
+
+    /// recreate the removed dir and open a new temp.log, false if that fails
+    fn recreate_temp(&mut self) -> bool {
+        if !self.recreate() {
+            return false;
+        }
+        let first_file_path = format!("{}{}.log", self.dir_path, "temp");
+        match open_temp_file(&first_file_path, &self.flags) {
+            Ok(file) => {
+                self.syncer.set_file(&file);
+                self.file = file;
+                self.temp_bytes = 0;
+                self.start_file();
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// temp.log was removed or replaced since it was opened(see file::path_state): open it again,
+    /// checked on flush and at most once a second before a record or batch
+    fn check_temp(&mut self) {
+        let first_file_path = format!("{}{}.log", self.dir_path, "temp");
+        match path_state(&first_file_path, &self.file) {
+            PathState::Same => {}
+            PathState::Removed => {
+                self.recreate_temp();
+            }
+            PathState::Replaced => self.reopen(),
+        }
+    }
+
+    /// write to temp file, recreate dir and file if they were removed
+    pub fn write(&mut self, buf: &[u8]) -> usize {
+        match self.file.write(buf) {
+            Ok(w) => w,
+            Err(e) => {
+                if e.kind() == ErrorKind::NotFound && self.recreate_temp() {
+                    return self.file.write(buf).unwrap_or(0);
+                }
+                self.write_fail(e);
+                0
+            }
+        }
+    }
+
+    /// write the bufs with one writev(see FileAppender::do_logs), recreate dir and file if they were removed
+    pub fn write_vectored(&mut self, bufs: &[&[u8]]) -> usize {
+        match write_all_vectored(&mut self.file, bufs) {
+            Ok(w) => w,
+            Err(e) => {
+                if e.kind() == ErrorKind::NotFound && self.recreate_temp() {
+                    return write_all_vectored(&mut self.file, bufs).unwrap_or(0);
+                }
+                self.write_fail(e);
+                0
+            }
+        }
+    }
+
+    fn write_fail(&self, e: Error) {
+        crate::health::report_error(
+            "FileSplitAppender",
+            &format!("[fast_log] write {}temp.log fail:{}", self.dir_path, e),
         );
-        std::fs::copy(&first_file_path, &new_log_name);
-        self.sender.send(LogPack {
-            dir: self.dir_path.clone(),
-            rolling: self.rolling_type.clone(),
-            new_log_name: new_log_name,
-        });
-        self.truncate();
+    }
+
+    /// open temp.log again, for example after it was moved
+    pub fn reopen(&mut self) {
+        let first_file_path = format!("{}{}.log", self.dir_path, "temp");
+        if let Ok(mut file) = open_temp_file(&first_file_path, &self.flags) {
+            self.temp_bytes = file.metadata().map(|m| m.len() as usize).unwrap_or(0);
+            file.seek(SeekFrom::End(0));
+            self.syncer.set_file(&file);
+            self.file = file;
+            self.start_file();
+        }
+    }
+
+    /// the period of SplitType::ByTime ended at `now`(the time of a record), the next period starts.
+    /// false if no record was written in it, `pending` bytes are not written yet
+    fn is_time_due(&mut self, now: SystemTime, pending: usize) -> bool {
+        match (self.split_period, self.next_split) {
+            (Some(period), Some(next)) => {
+                if now < next {
+                    return false;
+                }
+                self.next_split = Some(next_split(period, now));
+                self.temp_bytes + pending > self.empty_bytes
+            }
+            //the first record starts the period, the clock may be set(clock::set_clock) after the appender was made
+            (Some(period), None) => {
+                self.next_split = Some(next_split(period, now));
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// the temp file is full: the log size or with FileSplitAppender::packed_size the estimated packed size
+    fn is_full(&self, pending: usize) -> bool {
+        let bytes = self.temp_bytes + pending;
+        match self.packed_target {
+            Some(target) => bytes as f64 * f64::from_bits(self.ratio.load(Ordering::Relaxed)) >= target as f64,
+            None => bytes >= self.max_split_bytes,
+        }
+    }
+
+    /// a temp file was opened or truncated: preallocate it and write the header into it if it is empty
+    fn start_file(&mut self) {
+        self.reserve();
+        if let Some(meta) = &self.meta {
+            self.temp_bytes += write_header(&mut self.file, meta);
+        }
+        self.empty_bytes = self.temp_bytes;
+    }
+
+    /// preallocate the split size of the temp file(see FileSplitAppender::preallocate)
+    fn reserve(&self) {
+        if self.preallocate && self.max_split_bytes != usize::MAX {
+            if let Err(e) = preallocate(&self.file, self.max_split_bytes as u64) {
+                crate::health::report_error(
+                    "FileSplitAppender",
+                    &format!("[fast_log] preallocate {}temp.log fail:{}", self.dir_path, e),
+                );
+            }
+        }
     }
 
     pub fn truncate(&mut self) {
@@ -152,17 +455,18 @@ impl FileSplitAppenderData {
         self.file.set_len(0);
         self.file.seek(SeekFrom::Start(0));
         self.temp_bytes = 0;
+        self.start_file();
     }
 }
 
 impl FileSplitAppender {
-    ///split_log_bytes:  log file data bytes(MB) splite
+    ///split_log_bytes:  log file data bytes(MB) splite, a LogSize or a SplitType(for example by the hour)
     ///dir_path:         the log dir
     ///log_pack_cap:     pack(zip,lz4 or more...) or log Waiting cap
     /// packer: default is zip packer
     pub fn new(
         dir_path: &str,
-        max_temp_size: LogSize,
+        max_temp_size: impl Into<SplitType>,
         rolling_type: RollingType,
         log_pack_cap: usize,
         packer: Box<dyn Packer>,
@@ -177,11 +481,7 @@ impl FileSplitAppender {
             std::fs::create_dir_all(dir_path);
         }
         let first_file_path = format!("{}{}.log", dir_path, "temp");
-        let file = OpenOptions::new()
-            .create(true)
-            .read(true)
-            .write(true)
-            .open(first_file_path.as_str());
+        let file = open_temp_file(&first_file_path, &OpenFlags::default());
         if file.is_err() {
             panic!(
                 "[fast_log] open and create file fail:{}",
@@ -194,56 +494,224 @@ impl FileSplitAppender {
             temp_bytes = m.len() as usize;
         }
         file.seek(SeekFrom::Start(temp_bytes as u64));
-        let (sender, receiver) = may::sync::mpsc::channel();
-        spawn_saver(receiver, packer);
+        let syncer = FileSyncer::new(SyncPolicy::Never, &file);
+        let (sender, receiver) = crossbeam::channel::unbounded();
+        let ratio = Arc::new(AtomicU64::new(1f64.to_bits()));
+        let saver = spawn_saver(receiver, packer, ratio.clone());
+        let split: SplitType = max_temp_size.into();
+        let split_period = split.period();
+        let pipeline = Arc::new(Mutex::new(None));
+        let timer = split_period.map(|period| {
+            let (stop, stop_receiver) = crossbeam::channel::bounded(0);
+            spawn_split_timer(period, stop_receiver, pipeline.clone());
+            stop
+        });
         Self {
             cell: RefCell::new(FileSplitAppenderData {
-                max_split_bytes: max_temp_size.get_len(),
+                max_split_bytes: split.max_bytes(),
+                split_period,
+                next_split: None,
+                empty_bytes: 0,
+                _timer: timer,
                 temp_bytes: temp_bytes,
                 dir_path: dir_path.to_string(),
                 file: file,
-                sender: sender,
+                sender: Some(sender),
                 rolling_type: rolling_type,
+                flags: OpenFlags::default(),
+                syncer,
+                packed_target: None,
+                ratio,
+                preallocate: false,
+                meta: None,
+                notified: false,
+                path_check: PathCheck::new(),
             }),
+            saver: Some(saver),
+            pipeline,
+        }
+    }
+}
+
+impl FileSplitAppender {
+    /// open temp.log again with the flags(see OpenFlags), for example append: true when
+    /// several processes share the log dir
+    pub fn flags(self, flags: OpenFlags) -> Self {
+        {
+            let mut data = self.cell.borrow_mut();
+            data.flags = flags;
+            data.reopen();
+        }
+        self
+    }
+
+    /// fsync temp.log by the policy(see SyncPolicy), a flush syncs before the temp file is packed
+    pub fn sync_policy(self, policy: SyncPolicy) -> Self {
+        {
+            let mut data = self.cell.borrow_mut();
+            data.syncer = FileSyncer::new(policy, &data.file);
+        }
+        self
+    }
+
+    /// split when the packed(zip, lz4...) size would reach `target`, so the archives come out about the same size
+    /// whatever the compression ratio of the logs is. the ratio is measured on the finished packs,
+    /// the first file is split at `target` log bytes. max_temp_size is only the preallocate size then
+    pub fn packed_size(self, target: LogSize) -> Self {
+        self.cell.borrow_mut().packed_target = Some(target.get_len());
+        self
+    }
+
+    /// reserve the split size on disk for every temp file(fallocate, linux only),
+    /// steady writes then do not fragment the file or update the block map
+    pub fn preallocate(self, enable: bool) -> Self {
+        {
+            let mut data = self.cell.borrow_mut();
+            data.preallocate = enable;
+            data.reserve();
+        }
+        self
+    }
+
+    /// write a header(see FileMeta) into every new temp file and a trailer before a temp file is packed,
+    /// so the archives tell where they come from
+    pub fn meta(self, meta: FileMeta) -> Self {
+        {
+            let mut data = self.cell.borrow_mut();
+            data.meta = Some(meta);
+            data.start_file();
+        }
+        self
+    }
+}
+
+impl FileSplitAppender {
+    /// a command into the pipeline running the appender, nothing if it runs outside of one
+    fn send(&self, command: Command) {
+        if let Some(pipeline) = self.pipeline.lock().as_ref() {
+            pipeline.send(command);
         }
     }
 }
 
 impl LogAppender for FileSplitAppender {
+    fn attach(&self, pipeline: &PipelineSender) {
+        *self.pipeline.lock() = Some(pipeline.clone());
+    }
+
     fn do_log(&self, record: &mut FastLogRecord) {
         let mut data = self.cell.borrow_mut();
-        if record.command.eq(&Command::CommandFlush) || (data.temp_bytes >= data.max_split_bytes) {
-            data.send_pack();
+        if record.command.eq(&Command::CommandReopen) {
+            if !data.dir_path.is_empty() {
+                std::fs::create_dir_all(&data.dir_path);
+            }
+            data.reopen();
+            return;
+        }
+        if record.command.eq(&Command::CommandFlush)
+            || (record.command.eq(&Command::CommandRecord) && data.path_check.due())
+        {
+            data.check_temp();
+        }
+        if record.command.eq(&Command::CommandFlush) || data.is_full(0) || data.is_time_due(record.now, 0) {
+            data.syncer.flush(&data.file);
+            data.send_pack(record.flush_ack.clone());
+            //the pipeline calls LogAppender::on_rotate of every appender
+            self.send(Command::CommandRotate);
+        }
+        if record.command.ne(&Command::CommandRecord) {
             return;
         }
-        let mut write_bytes = 0;
-        let w = data.file.write(record.formated.as_bytes());
-        if let Ok(w) = w {
-            write_bytes = write_bytes + w;
+        let write_bytes = data.write(record.formated.as_bytes());
+        data.file.flush();
+        data.temp_bytes += write_bytes;
+        data.syncer.written();
+    }
+
+    /// one writev for the records up to the split size, then the pack is sent and the rest goes to the new file
+    fn do_logs(&self, records: &mut [FastLogRecord]) {
+        let mut data = self.cell.borrow_mut();
+        if data.path_check.due() {
+            data.check_temp();
+        }
+        let mut bufs: Vec<&[u8]> = Vec::with_capacity(records.len());
+        let mut pending = 0;
+        for record in records.iter() {
+            if data.is_full(pending) || data.is_time_due(record.now, pending) {
+                let write_bytes = data.write_vectored(&bufs);
+                data.temp_bytes += write_bytes;
+                bufs.clear();
+                pending = 0;
+                data.send_pack(None);
+                self.send(Command::CommandRotate);
+            }
+            bufs.push(record.formated.as_bytes());
+            pending += record.formated.len();
         }
+        let write_bytes = data.write_vectored(&bufs);
         data.file.flush();
         data.temp_bytes += write_bytes;
+        data.syncer.written();
+    }
+
+    fn shutdown(&self) {
+        let data = self.cell.borrow();
+        data.syncer.close(&data.file);
+    }
+}
+
+fn open_temp_file(path: &str, flags: &OpenFlags) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.create(true).read(true).write(true);
+    flags.apply(&mut options);
+    options.open(path)
+}
+
+impl Drop for FileSplitAppender {
+    /// wait until the sent packs are saved, the pipeline drops appenders on exit(see fast_log::shutdown)
+    fn drop(&mut self) {
+        self.cell.borrow_mut().sender.take();
+        if let Some(handle) = self.saver.take() {
+            handle.join();
+        }
     }
 }
 
-///spawn an saver thread to save log file or zip file
-fn spawn_saver(r: Receiver<LogPack>, packer: Box<dyn Packer>) {
+///spawn an saver thread to save log file or zip file, it exits once the appender is dropped
+fn spawn_saver(r: Receiver<LogPack>, packer: Box<dyn Packer>, ratio: Arc<AtomicU64>) -> JoinHandle<()> {
     std::thread::spawn(move || {
-        loop {
-            if let Ok(pack) = r.recv() {
-                //do rolling
-                pack.rolling.do_rolling(&pack.dir);
-                let log_file_path = pack.new_log_name.clone();
-                //do save pack
-                let remove = do_pack(&packer, pack);
-                if let Ok(remove) = remove {
-                    if remove {
-                        std::fs::remove_file(log_file_path);
-                    }
+        //ends once the appender dropped the sender and the packs before are saved
+        for mut pack in r.iter() {
+            let flush_ack = pack.flush_ack.take();
+            //do rolling
+            pack.rolling.do_rolling(&pack.dir);
+            let log_file_path = pack.new_log_name.clone();
+            crate::budget::release(std::mem::size_of::<LogPack>() + pack.dir.len() + log_file_path.len());
+            let log_len = std::fs::metadata(&log_file_path).map(|m| m.len()).unwrap_or(0);
+            //do save pack
+            let remove = do_pack(&packer, pack);
+            if let Ok(remove) = remove {
+                update_ratio(&ratio, packer.as_ref(), &log_file_path, log_len);
+                if remove {
+                    std::fs::remove_file(log_file_path);
                 }
             }
+            drop(flush_ack);
         }
-    });
+    })
+}
+
+/// the packers write "x.log" into "x.{pack_name}", average its size ratio into `ratio`
+fn update_ratio(ratio: &AtomicU64, packer: &dyn Packer, log_file_path: &str, log_len: u64) {
+    if log_len == 0 {
+        return;
+    }
+    let packed_path = log_file_path.replace(".log", &format!(".{}", packer.pack_name()));
+    if let Ok(m) = std::fs::metadata(&packed_path) {
+        let last = m.len() as f64 / log_len as f64;
+        let old = f64::from_bits(ratio.load(Ordering::Relaxed));
+        ratio.store(((old + last) / 2.0).to_bits(), Ordering::Relaxed);
+    }
 }
 
 /// write an Pack to zip file
@@ -257,8 +725,19 @@ pub fn do_pack(packer: &Box<dyn Packer>, mut pack: LogPack) -> Result<bool, LogP
         return Err(pack);
     }
     let log_file = log_file.unwrap();
+    let start = std::time::Instant::now();
     //make
     let r = packer.do_pack(log_file, log_file_path);
+    match &r {
+        Ok(_) => {
+            let args = format!("packed {} in {}ms", log_file_path, start.elapsed().as_millis());
+            crate::diagnostics::emit(log::Level::Info, "pack", args);
+        }
+        Err(e) => crate::health::report_error(
+            "FileSplitAppender",
+            &format!("[fast_log] pack {} fail:{}", log_file_path, e),
+        ),
+    }
     if r.is_err() && packer.retry() > 0 {
         let mut retry = 1;
         while let Err(packs) = do_pack(packer, pack) {
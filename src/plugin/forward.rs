@@ -0,0 +1,101 @@
+use crate::error::LogError;
+use crate::plugin::file_split::Packer;
+use crate::relay::RelayConn;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+
+/// ship every rotated log file to a socket, for example a collector running forward::receive_files.
+/// addr: "127.0.0.1:9003" or "unix:/tmp/logs.sock". on linux the file goes to the socket with
+/// sendfile/splice(std::io::copy of a File to a socket), so a large file is not copied through user space.
+/// a connection carries one file: a header line "{file name} {len}\n", then the bytes
+pub struct ForwardPacker {
+    addr: String,
+    keep_local: bool,
+}
+
+impl ForwardPacker {
+    /// keep_local: false removes the rotated file once it was sent
+    pub fn new(addr: &str, keep_local: bool) -> ForwardPacker {
+        Self {
+            addr: addr.to_string(),
+            keep_local,
+        }
+    }
+}
+
+impl Packer for ForwardPacker {
+    fn pack_name(&self) -> &'static str {
+        "log"
+    }
+
+    fn do_pack(&self, mut log_file: File, log_file_path: &str) -> Result<bool, LogError> {
+        let name = log_file_path.replace('\\', "/");
+        let name = name.rsplit('/').next().unwrap_or_default().to_string();
+        let fail = |e: std::io::Error| LogError::from(format!("[fast_log] forward {} to {} fail:{}", name, self.addr, e));
+        let len = log_file.metadata().map(|m| m.len()).map_err(fail)?;
+        let mut conn = RelayConn::connect(&self.addr).map_err(fail)?;
+        conn.write_all(format!("{} {}\n", name, len).as_bytes()).map_err(fail)?;
+        let sent = conn.send_file(&mut log_file).map_err(fail)?;
+        if sent != len {
+            return Err(LogError::from(format!(
+                "[fast_log] forward {} fail: sent {} of {} bytes",
+                name, sent, len
+            )));
+        }
+        conn.flush().map_err(fail)?;
+        Ok(!self.keep_local)
+    }
+
+    /// a collector restart should not lose the file
+    fn retry(&self) -> i32 {
+        3
+    }
+}
+
+/// store the files of ForwardPacker into `dir`, on its own thread
+pub fn receive_files(addr: &str, dir: &str) -> Result<(), LogError> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| LogError::from(format!("[fast_log] create dir({}) fail:{}", dir, e)))?;
+    let listener = TcpListener::bind(addr)
+        .map_err(|e| LogError::from(format!("[fast_log] forward bind({}) fail:{}", addr, e)))?;
+    let dir = dir.trim_end_matches('/').to_string();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let dir = dir.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = receive_file(stream, &dir) {
+                    crate::health::report_error("receive_files", &e.to_string());
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn receive_file<R: Read>(stream: R, dir: &str) -> Result<(), LogError> {
+    let mut reader = BufReader::new(stream);
+    let mut header = String::new();
+    let fail = |e: std::io::Error| LogError::from(format!("[fast_log] forward receive fail:{}", e));
+    reader.read_line(&mut header).map_err(fail)?;
+    let (name, len) = header
+        .trim_end()
+        .rsplit_once(' ')
+        .and_then(|(name, len)| len.parse::<u64>().ok().map(|len| (name.to_string(), len)))
+        .ok_or_else(|| LogError::from(format!("[fast_log] forward bad header:{}", header.trim_end())))?;
+    //only a file name, never a path out of dir
+    if name.is_empty() || name.contains('/') || name.contains('\\') || name == ".." {
+        return Err(LogError::from(format!("[fast_log] forward bad file name:{}", name)));
+    }
+    let path = format!("{}/{}", dir, name);
+    let mut file = File::create(&path).map_err(fail)?;
+    let received = std::io::copy(&mut reader.take(len), &mut file).map_err(fail)?;
+    if received != len {
+        std::fs::remove_file(&path);
+        return Err(LogError::from(format!(
+            "[fast_log] forward {} fail: received {} of {} bytes",
+            name, received, len
+        )));
+    }
+    Ok(())
+}
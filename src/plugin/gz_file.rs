@@ -0,0 +1,91 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::error::LogError;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// you need enable fast_log = { ... ,features=["gzip"]}
+/// write records directly into a gzip stream. every `flush_records` records or `flush_interval`
+/// a sync flush point is written, so a partial file(for example after a crash) can be read up to it.
+/// each start appends a new gzip member, read the file with reader::open() or `zcat`.
+pub struct GzFileAppender {
+    cell: RefCell<GzFileAppenderData>,
+}
+
+pub struct GzFileAppenderData {
+    encoder: Option<GzEncoder<File>>,
+    flush_records: u64,
+    flush_interval: Duration,
+    records: u64,
+    last_flush: Instant,
+}
+
+impl GzFileAppender {
+    /// log_file_path: for example "target/logs/app.log.gz"
+    pub fn new(
+        log_file_path: &str,
+        flush_records: u64,
+        flush_interval: Duration,
+    ) -> Result<GzFileAppender, LogError> {
+        let log_file_path = log_file_path.replace("\\", "/");
+        if let Some(right) = log_file_path.rfind('/') {
+            std::fs::create_dir_all(&log_file_path[0..right]);
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_file_path)
+            .map_err(|e| LogError::from(format!("[fast_log] open({}) fail:{}", log_file_path, e)))?;
+        Ok(Self {
+            cell: RefCell::new(GzFileAppenderData {
+                encoder: Some(GzEncoder::new(file, Compression::default())),
+                flush_records: flush_records.max(1),
+                flush_interval,
+                records: 0,
+                last_flush: Instant::now(),
+            }),
+        })
+    }
+}
+
+impl GzFileAppenderData {
+    fn flush(&mut self) {
+        if let Some(encoder) = self.encoder.as_mut() {
+            encoder.flush();
+        }
+        self.records = 0;
+        self.last_flush = Instant::now();
+    }
+}
+
+impl LogAppender for GzFileAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.ne(&Command::CommandRecord) {
+            return;
+        }
+        let mut data = self.cell.borrow_mut();
+        if let Some(encoder) = data.encoder.as_mut() {
+            encoder.write_all(record.formated.as_bytes());
+        }
+        data.records += 1;
+        if data.records >= data.flush_records || data.last_flush.elapsed() >= data.flush_interval {
+            data.flush();
+        }
+    }
+
+    fn flush(&self) {
+        self.cell.borrow_mut().flush();
+    }
+}
+
+impl Drop for GzFileAppender {
+    /// write the gzip trailer, the pipeline drops appenders on exit
+    fn drop(&mut self) {
+        if let Some(encoder) = self.cell.get_mut().encoder.take() {
+            encoder.finish();
+        }
+    }
+}
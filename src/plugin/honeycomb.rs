@@ -0,0 +1,90 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::plugin::http::{HttpBatchConfig, HttpBatcher, HttpRequest};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+use std::cell::Cell;
+
+pub const HONEYCOMB_API_HOST: &str = "https://api.honeycomb.io";
+
+/// you need enable fast_log = { ... ,features=["http"]}
+/// send records as Honeycomb events to the batch api, one of `sample_rate` records is kept
+/// and carries the sample rate so Honeycomb can weight it
+pub struct HoneycombAppender {
+    batcher: HttpBatcher,
+    sample_rate: u32,
+    count: Cell<u64>,
+}
+
+impl HoneycombAppender {
+    /// api_host: HONEYCOMB_API_HOST or your proxy, sample_rate: 1 = keep all
+    pub fn new(
+        api_host: &str,
+        api_key: &str,
+        dataset: &str,
+        sample_rate: u32,
+        config: HttpBatchConfig,
+    ) -> HoneycombAppender {
+        let url = format!("{}/1/batch/{}", api_host.trim_end_matches('/'), dataset);
+        let api_key = api_key.to_string();
+        let batcher = HttpBatcher::new(
+            "honeycomb",
+            config,
+            Box::new(move |batch| HttpRequest {
+                url: url.clone(),
+                headers: vec![
+                    ("X-Honeycomb-Team".to_string(), api_key.clone()),
+                    ("Content-Type".to_string(), "application/json".to_string()),
+                ],
+                body: serde_json::to_vec(batch).unwrap_or_default(),
+            }),
+        );
+        Self {
+            batcher,
+            sample_rate: sample_rate.max(1),
+            count: Cell::new(0),
+        }
+    }
+
+    /// events dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.batcher.dropped()
+    }
+
+    fn event(&self, record: &FastLogRecord) -> Value {
+        let mut data = Map::new();
+        data.insert("level".to_string(), json!(record.level.as_str()));
+        data.insert("target".to_string(), json!(record.target));
+        data.insert("message".to_string(), json!(record.args));
+        data.insert("module_path".to_string(), json!(record.module_path));
+        data.insert("file".to_string(), json!(record.file));
+        if let Some(line) = record.line {
+            data.insert("line".to_string(), json!(line));
+        }
+        if let Some(trace) = &record.trace {
+            data.insert("trace.trace_id".to_string(), json!(trace.trace_id));
+            data.insert("trace.parent_id".to_string(), json!(trace.span_id));
+        }
+        let time: DateTime<Utc> = DateTime::from(record.now);
+        json!({
+            "time": time.to_rfc3339_opts(SecondsFormat::Nanos, true),
+            "samplerate": self.sample_rate,
+            "data": data,
+        })
+    }
+}
+
+impl LogAppender for HoneycombAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.eq(&Command::CommandRecord) {
+            let count = self.count.get();
+            self.count.set(count.wrapping_add(1));
+            if count % self.sample_rate as u64 == 0 {
+                self.batcher.push(self.event(record));
+            }
+        }
+    }
+
+    fn flush(&self) {
+        self.batcher.flush();
+    }
+}
@@ -0,0 +1,177 @@
+use crossbeam::channel::{RecvTimeoutError, Sender};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// you need enable fast_log = { ... ,features=["http"]}
+/// batching of the http appenders(Honeycomb, Datadog, Splunk...)
+#[derive(Clone, Debug)]
+pub struct HttpBatchConfig {
+    /// events per request
+    pub max_batch: usize,
+    /// max time an event waits for its batch
+    pub interval: Duration,
+    /// events queued while the endpoint is slow, more are dropped
+    pub queue_cap: usize,
+    /// retries of a batch on 429, 5xx or transport errors
+    pub max_retries: u32,
+    /// timeout of one request
+    pub timeout: Duration,
+}
+
+impl Default for HttpBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_batch: 500,
+            interval: Duration::from_secs(1),
+            queue_cap: 100000,
+            max_retries: 3,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// one request of a batch
+pub struct HttpRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// build the request of a batch
+pub type HttpRequestBuilder = Box<dyn Fn(&[serde_json::Value]) -> HttpRequest + Send>;
+
+enum BatchMsg {
+    Event(serde_json::Value),
+    Flush,
+}
+
+/// queue events and post them in batches on its own thread, the back worker never waits for the network.
+/// on drop the queued events are sent before it returns
+pub struct HttpBatcher {
+    sender: Option<Sender<BatchMsg>>,
+    handle: Option<JoinHandle<()>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl HttpBatcher {
+    pub fn new(name: &str, config: HttpBatchConfig, builder: HttpRequestBuilder) -> HttpBatcher {
+        let (sender, receiver) = crossbeam::channel::bounded::<BatchMsg>(config.queue_cap.max(1));
+        let name = name.to_string();
+        let handle = std::thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout(config.timeout).build();
+            let mut batch = Vec::with_capacity(config.max_batch);
+            let mut deadline = Instant::now() + config.interval;
+            loop {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                let mut send = false;
+                let mut exit = false;
+                match receiver.recv_timeout(timeout) {
+                    Ok(BatchMsg::Event(v)) => {
+                        batch.push(v);
+                        send = batch.len() >= config.max_batch;
+                    }
+                    Ok(BatchMsg::Flush) => send = true,
+                    Err(RecvTimeoutError::Timeout) => send = true,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        send = true;
+                        exit = true;
+                    }
+                }
+                if send {
+                    if !batch.is_empty() {
+                        post(&agent, &name, &config, &builder(&batch));
+                        batch.clear();
+                    }
+                    deadline = Instant::now() + config.interval;
+                }
+                if exit {
+                    break;
+                }
+            }
+        });
+        Self {
+            sender: Some(sender),
+            handle: Some(handle),
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn push(&self, event: serde_json::Value) {
+        if let Some(sender) = &self.sender {
+            if sender.try_send(BatchMsg::Event(event)).is_err() {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// send the queued events now
+    pub fn flush(&self) {
+        if let Some(sender) = &self.sender {
+            sender.try_send(BatchMsg::Flush);
+        }
+    }
+
+    /// events dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for HttpBatcher {
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            handle.join();
+        }
+    }
+}
+
+fn post(agent: &ureq::Agent, name: &str, config: &HttpBatchConfig, request: &HttpRequest) {
+    let mut attempt = 0;
+    loop {
+        let mut req = agent.post(&request.url);
+        for (k, v) in &request.headers {
+            req = req.set(k, v);
+        }
+        let mut retry_after = None;
+        let error = match req.send_bytes(&request.body) {
+            Ok(_) => return,
+            Err(ureq::Error::Status(code, resp)) => {
+                if code != 429 && code < 500 {
+                    crate::health::report_error(
+                        name,
+                        &format!("[fast_log] {} post fail: status {} {}", name, code, resp.into_string().unwrap_or_default()),
+                    );
+                    return;
+                }
+                retry_after = resp
+                    .header("Retry-After")
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                format!("status {}", code)
+            }
+            Err(e) => e.to_string(),
+        };
+        if attempt >= config.max_retries {
+            crate::health::report_error(name, &format!("[fast_log] {} post fail after {} retries: {}", name, attempt, error));
+            return;
+        }
+        let backoff = Duration::from_millis(200 << attempt.min(6));
+        std::thread::sleep(retry_after.unwrap_or(backoff).min(Duration::from_secs(30)));
+        attempt += 1;
+    }
+}
+
+/// percent-encode a query parameter value
+pub(crate) fn url_encode(v: &str) -> String {
+    let mut out = String::with_capacity(v.len());
+    for b in v.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
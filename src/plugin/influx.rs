@@ -0,0 +1,159 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+#[cfg(feature = "http")]
+use crate::plugin::http::{url_encode, HttpBatchConfig, HttpBatcher, HttpRequest};
+use std::net::UdpSocket;
+use std::time::UNIX_EPOCH;
+
+/// where the lines go
+pub enum InfluxTransport {
+    /// "host:port" of the InfluxDB udp listener or a Telegraf socket_listener("udp://:8094"), one line per datagram
+    Udp(String),
+    /// InfluxDB 2 `/api/v2/write`, a batch is one request
+    #[cfg(feature = "http")]
+    Http {
+        /// for example "http://influxdb:8086"
+        url: String,
+        org: String,
+        bucket: String,
+        token: String,
+        config: HttpBatchConfig,
+    },
+}
+
+enum InfluxSink {
+    Udp(UdpSocket),
+    #[cfg(feature = "http")]
+    Http(HttpBatcher),
+}
+
+/// write records as InfluxDB line protocol:
+/// `{measurement},level=INFO,target=..,host=.. message="..",line=10i,trace_id="..",span_id=".." {unix nanos}`
+pub struct InfluxAppender {
+    sink: InfluxSink,
+    /// the escaped measurement and host tag
+    measurement: String,
+    host: String,
+}
+
+impl InfluxAppender {
+    /// measurement: usually "logs"
+    pub fn new(transport: InfluxTransport, measurement: &str) -> std::io::Result<InfluxAppender> {
+        let sink = match transport {
+            InfluxTransport::Udp(addr) => {
+                let socket = UdpSocket::bind("0.0.0.0:0")?;
+                socket.connect(addr)?;
+                InfluxSink::Udp(socket)
+            }
+            #[cfg(feature = "http")]
+            InfluxTransport::Http {
+                url,
+                org,
+                bucket,
+                token,
+                config,
+            } => {
+                let url = format!(
+                    "{}/api/v2/write?org={}&bucket={}&precision=ns",
+                    url.trim_end_matches('/'),
+                    url_encode(&org),
+                    url_encode(&bucket)
+                );
+                let authorization = format!("Token {}", token);
+                InfluxSink::Http(HttpBatcher::new(
+                    "influxdb",
+                    config,
+                    Box::new(move |batch| {
+                        let mut body = Vec::new();
+                        for line in batch {
+                            body.extend_from_slice(line.as_str().unwrap_or_default().as_bytes());
+                            body.push(b'\n');
+                        }
+                        HttpRequest {
+                            url: url.clone(),
+                            headers: vec![
+                                ("Authorization".to_string(), authorization.clone()),
+                                ("Content-Type".to_string(), "text/plain; charset=utf-8".to_string()),
+                            ],
+                            body,
+                        }
+                    }),
+                ))
+            }
+        };
+        Ok(Self {
+            sink,
+            measurement: escape(measurement, &[',', ' ']),
+            host: escape(&crate::plugin::syslog::local_hostname(), &[',', '=', ' ']),
+        })
+    }
+
+    /// one line, without '\n'
+    pub fn line(&self, record: &FastLogRecord) -> String {
+        let mut line = format!("{},level={}", self.measurement, record.level.as_str());
+        if !record.target.is_empty() {
+            line.push_str(",target=");
+            line.push_str(&escape(&record.target, &[',', '=', ' ']));
+        }
+        if !self.host.is_empty() {
+            line.push_str(",host=");
+            line.push_str(&self.host);
+        }
+        line.push_str(" message=");
+        line.push_str(&field_string(&record.args));
+        if let Some(v) = record.line {
+            line.push_str(&format!(",line={}i", v));
+        }
+        if let Some(trace) = &record.trace {
+            line.push_str(",trace_id=");
+            line.push_str(&field_string(&trace.trace_id));
+            line.push_str(",span_id=");
+            line.push_str(&field_string(&trace.span_id));
+        }
+        let nanos = record.now.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        line.push_str(&format!(" {}", nanos));
+        line
+    }
+}
+
+/// backslash before the special chars, line protocol has no newlines
+fn escape(v: &str, special: &[char]) -> String {
+    let mut out = String::with_capacity(v.len());
+    for c in v.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c if special.contains(&c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn field_string(v: &str) -> String {
+    format!("\"{}\"", escape(v, &['"', '\\']))
+}
+
+impl LogAppender for InfluxAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        match (&record.command, &self.sink) {
+            (Command::CommandRecord, InfluxSink::Udp(socket)) => {
+                socket.send(self.line(record).as_bytes());
+            }
+            #[cfg(feature = "http")]
+            (Command::CommandRecord, InfluxSink::Http(batcher)) => {
+                batcher.push(serde_json::Value::String(self.line(record)))
+            }
+            _ => {}
+        }
+    }
+
+    fn flush(&self) {
+        #[cfg(feature = "http")]
+        if let InfluxSink::Http(batcher) = &self.sink {
+            batcher.flush();
+        }
+    }
+}
@@ -0,0 +1,368 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::error::LogError;
+use log::LevelFilter;
+use std::cell::RefCell;
+use std::convert::TryInto;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::{Bound, RangeBounds};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// file header: magic + version(u32 le)
+pub const JOURNAL_MAGIC: &[u8; 8] = b"FASTLOGJ";
+/// version of new journals. 1: payloads without schema byte, 2: every payload starts with its schema version
+pub const JOURNAL_VERSION: u32 = 2;
+/// schema version of the payloads written into a version 2 journal
+pub const RECORD_SCHEMA: u8 = 2;
+const HEADER_LEN: u64 = 12;
+/// max record payload, a bigger length means a corrupt journal
+const MAX_RECORD_LEN: usize = 64 * 1024 * 1024;
+/// records are written in the order they reach the appender, their time may be a little out of order
+const MAX_TIME_SKEW: Duration = Duration::from_secs(1);
+
+/// an indexed binary journal:
+/// - `{path}`: header, then records [payload len u32][crc32 u32][payload],
+///   payload = schema(u8) time(unix nanos u64) level(u8) line(u32, u32::MAX = none)
+///   target module_path file args kv(json) tags(json)(u32 len + utf8 each).
+///   a reader decodes the fields of its schema and skips the fields a newer schema appends.
+///   a version 1 journal has no schema byte, kv and tags, records appended to it keep that layout
+/// - `{path}.idx`: every `index_interval` records an entry [time u64][offset u64] for range queries.
+///
+/// all integers are little endian. read it with JournalReader
+pub struct JournalAppender {
+    cell: RefCell<JournalData>,
+}
+
+struct JournalData {
+    file: BufWriter<File>,
+    index: BufWriter<File>,
+    offset: u64,
+    index_interval: u64,
+    count: u64,
+    //journal version of the file
+    version: u32,
+}
+
+impl JournalAppender {
+    /// append to the journal at `path` if it exists
+    pub fn new(path: &str, index_interval: u64) -> Result<JournalAppender, LogError> {
+        let path = path.replace("\\", "/");
+        if let Some(right) = path.rfind('/') {
+            std::fs::create_dir_all(&path[0..right]);
+        }
+        let open = |path: &str| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| LogError::from(format!("[fast_log] open({}) fail:{}", path, e)))
+        };
+        let mut file = open(&path)?;
+        let index = open(&format!("{}.idx", path))?;
+        let mut offset = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let mut version = JOURNAL_VERSION;
+        if offset == 0 {
+            file.write_all(JOURNAL_MAGIC)
+                .and_then(|_| file.write_all(&JOURNAL_VERSION.to_le_bytes()))
+                .map_err(|e| LogError::from(format!("[fast_log] write({}) fail:{}", path, e)))?;
+            offset = HEADER_LEN;
+        } else {
+            (offset, version) = repair(&path, &file, &index, offset)?;
+        }
+        Ok(Self {
+            cell: RefCell::new(JournalData {
+                file: BufWriter::new(file),
+                index: BufWriter::new(index),
+                offset,
+                index_interval: index_interval.max(1),
+                count: 0,
+                version,
+            }),
+        })
+    }
+}
+
+/// return the journal version
+fn check_header(file: &mut File) -> Result<u32, LogError> {
+    let mut header = [0u8; HEADER_LEN as usize];
+    file.read_exact(&mut header)
+        .map_err(|e| LogError::from(format!("[fast_log] read journal header fail:{}", e)))?;
+    if &header[0..8] != JOURNAL_MAGIC {
+        return Err(LogError::from("[fast_log] not a fast_log journal"));
+    }
+    let version = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+    if version == 0 || version > JOURNAL_VERSION {
+        return Err(LogError::from(format!("[fast_log] unsupported journal version {}", version)));
+    }
+    Ok(version)
+}
+
+/// cut a torn tail(for example after a crash) so new records follow the last valid one,
+/// and the index entries past it. return the end offset and the journal version
+fn repair(path: &str, file: &File, index: &File, len: u64) -> Result<(u64, u32), LogError> {
+    let mut reader = JournalReader::open(path)?;
+    //the index is written before the record, its last entries may point past the data
+    let start = reader
+        .index
+        .iter()
+        .rev()
+        .map(|(_, offset)| *offset)
+        .find(|offset| *offset < len)
+        .unwrap_or(HEADER_LEN);
+    let mut end = start;
+    if reader.file.seek(SeekFrom::Start(start)).is_ok() {
+        while let Some(payload) = read_record(&mut reader.file) {
+            end += 8 + payload.len() as u64;
+        }
+    }
+    let end = end.min(len);
+    if end < len {
+        crate::health::report_error(
+            "JournalAppender",
+            &format!("[fast_log] journal {} has a torn tail, cut {} bytes", path, len - end),
+        );
+        file.set_len(end).map_err(|e| LogError::from(e.to_string()))?;
+    }
+    //also a torn entry(not 16 bytes) is cut
+    let entries = reader.index.iter().take_while(|(_, offset)| *offset < end).count() as u64;
+    if index.metadata().map(|m| m.len()).unwrap_or(0) != entries * 16 {
+        index.set_len(entries * 16).map_err(|e| LogError::from(e.to_string()))?;
+    }
+    Ok((end, reader.version))
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0)
+}
+
+fn put_str(buf: &mut Vec<u8>, v: &str) {
+    buf.extend_from_slice(&(v.len() as u32).to_le_bytes());
+    buf.extend_from_slice(v.as_bytes());
+}
+
+fn encode(record: &FastLogRecord, version: u32) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(64 + record.args.len());
+    if version >= 2 {
+        payload.push(RECORD_SCHEMA);
+    }
+    payload.extend_from_slice(&unix_nanos(record.now).to_le_bytes());
+    payload.push(record.level as u8);
+    payload.extend_from_slice(&record.line.unwrap_or(u32::MAX).to_le_bytes());
+    put_str(&mut payload, &record.target);
+    put_str(&mut payload, &record.module_path);
+    put_str(&mut payload, &record.file);
+    put_str(&mut payload, &record.args);
+    if version >= 2 {
+        let kv: serde_json::Map<String, serde_json::Value> = record.kv.iter().cloned().collect();
+        put_str(&mut payload, &serde_json::Value::Object(kv).to_string());
+        put_str(&mut payload, &serde_json::Value::from(record.tags.clone()).to_string());
+    }
+    payload
+}
+
+impl JournalData {
+    fn write(&mut self, record: &FastLogRecord) -> std::io::Result<()> {
+        if self.count % self.index_interval == 0 {
+            self.index.write_all(&unix_nanos(record.now).to_le_bytes())?;
+            self.index.write_all(&self.offset.to_le_bytes())?;
+        }
+        let payload = encode(record, self.version);
+        self.file.write_all(&(payload.len() as u32).to_le_bytes())?;
+        self.file.write_all(&crc32fast::hash(&payload).to_le_bytes())?;
+        self.file.write_all(&payload)?;
+        self.offset += 8 + payload.len() as u64;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        self.file.flush();
+        self.index.flush();
+    }
+}
+
+impl LogAppender for JournalAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.ne(&Command::CommandRecord) {
+            return;
+        }
+        if let Err(e) = self.cell.borrow_mut().write(record) {
+            crate::health::report_error("JournalAppender", &format!("[fast_log] journal write fail:{}", e));
+        }
+    }
+
+    fn flush(&self) {
+        self.cell.borrow_mut().flush();
+    }
+}
+
+impl Drop for JournalAppender {
+    fn drop(&mut self) {
+        self.cell.get_mut().flush();
+    }
+}
+
+/// read a journal written by JournalAppender
+pub struct JournalReader {
+    file: BufReader<File>,
+    /// (time, offset)
+    index: Vec<(u64, u64)>,
+    version: u32,
+}
+
+impl JournalReader {
+    pub fn open(path: &str) -> Result<JournalReader, LogError> {
+        let mut file = File::open(path).map_err(|e| LogError::from(format!("[fast_log] open({}) fail:{}", path, e)))?;
+        let version = check_header(&mut file)?;
+        let mut index = vec![];
+        if let Ok(mut f) = File::open(format!("{}.idx", path)) {
+            let mut buf = vec![];
+            f.read_to_end(&mut buf);
+            for entry in buf.chunks_exact(16) {
+                let time = u64::from_le_bytes(entry[0..8].try_into().unwrap_or_default());
+                let offset = u64::from_le_bytes(entry[8..16].try_into().unwrap_or_default());
+                index.push((time, offset));
+            }
+        }
+        Ok(Self {
+            file: BufReader::new(file),
+            index,
+            version,
+        })
+    }
+
+    /// all records of the journal
+    pub fn iter(&mut self) -> JournalIter<'_> {
+        self.query(.., LevelFilter::Trace)
+    }
+
+    /// the records in the time range with a level up to `level`, the index skips to the range start
+    pub fn query(&mut self, range: impl RangeBounds<SystemTime>, level: LevelFilter) -> JournalIter<'_> {
+        let start = match range.start_bound() {
+            Bound::Included(v) | Bound::Excluded(v) => Some(unix_nanos(*v)),
+            Bound::Unbounded => None,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(v) | Bound::Excluded(v) => Some(unix_nanos(*v)),
+            Bound::Unbounded => None,
+        };
+        let skew = MAX_TIME_SKEW.as_nanos() as u64;
+        let mut offset = HEADER_LEN;
+        if let Some(start) = start {
+            //the last index entry clearly before the start
+            let position = self.index.partition_point(|(time, _)| time.saturating_add(skew) < start);
+            if position > 0 {
+                offset = self.index[position - 1].1;
+            }
+        }
+        let ok = self.file.seek(SeekFrom::Start(offset)).is_ok();
+        JournalIter {
+            reader: self,
+            start_bound: range.start_bound().cloned(),
+            end_bound: range.end_bound().cloned(),
+            end,
+            level,
+            done: !ok,
+        }
+    }
+}
+
+pub struct JournalIter<'a> {
+    reader: &'a mut JournalReader,
+    start_bound: Bound<SystemTime>,
+    end_bound: Bound<SystemTime>,
+    end: Option<u64>,
+    level: LevelFilter,
+    done: bool,
+}
+
+impl JournalIter<'_> {
+    fn next_record(&mut self) -> Option<(u64, FastLogRecord)> {
+        decode(&read_record(&mut self.reader.file)?, self.reader.version)
+    }
+}
+
+/// the payload of the next record, None at the end or at a torn/corrupt record
+fn read_record(file: &mut BufReader<File>) -> Option<Vec<u8>> {
+    let mut head = [0u8; 8];
+    file.read_exact(&mut head).ok()?;
+    let len = u32::from_le_bytes([head[0], head[1], head[2], head[3]]) as usize;
+    let crc = u32::from_le_bytes([head[4], head[5], head[6], head[7]]);
+    if len > MAX_RECORD_LEN {
+        return None;
+    }
+    let mut payload = vec![0u8; len];
+    file.read_exact(&mut payload).ok()?;
+    if crc32fast::hash(&payload) != crc {
+        return None;
+    }
+    Some(payload)
+}
+
+impl Iterator for JournalIter<'_> {
+    type Item = FastLogRecord;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let (time, record) = match self.next_record() {
+                Some(v) => v,
+                None => {
+                    self.done = true;
+                    return None;
+                }
+            };
+            if let Some(end) = self.end {
+                if time > end.saturating_add(MAX_TIME_SKEW.as_nanos() as u64) {
+                    self.done = true;
+                    return None;
+                }
+            }
+            if record.level <= self.level && (self.start_bound, self.end_bound).contains(&record.now) {
+                return Some(record);
+            }
+        }
+        None
+    }
+}
+
+fn decode(payload: &[u8], version: u32) -> Option<(u64, FastLogRecord)> {
+    let mut pos = 0;
+    let mut take = |n: usize| -> Option<&[u8]> {
+        let v = payload.get(pos..pos + n)?;
+        pos += n;
+        Some(v)
+    };
+    //schema 1: the layout of version 1 journals
+    let schema = if version >= 2 { take(1)?[0] } else { 1 };
+    let time = u64::from_le_bytes(take(8)?.try_into().ok()?);
+    let level = match take(1)?[0] {
+        1 => log::Level::Error,
+        2 => log::Level::Warn,
+        3 => log::Level::Info,
+        4 => log::Level::Debug,
+        _ => log::Level::Trace,
+    };
+    let line = u32::from_le_bytes(take(4)?.try_into().ok()?);
+    let fields = if schema >= 2 { 6 } else { 4 };
+    let mut strings = Vec::with_capacity(fields);
+    for _ in 0..fields {
+        let len = u32::from_le_bytes(take(4)?.try_into().ok()?) as usize;
+        strings.push(String::from_utf8_lossy(take(len)?).to_string());
+    }
+    let mut record = FastLogRecord::new_command(Command::CommandRecord);
+    if schema >= 2 {
+        record.tags = serde_json::from_str(&strings.pop()?).unwrap_or_default();
+        if let Ok(serde_json::Value::Object(kv)) = serde_json::from_str(&strings.pop()?) {
+            record.kv = kv.into_iter().collect();
+        }
+    }
+    record.now = UNIX_EPOCH + Duration::from_nanos(time);
+    record.monotonic = None;
+    record.level = level;
+    record.line = if line == u32::MAX { None } else { Some(line) };
+    record.args = strings.pop()?.into();
+    record.file = strings.pop()?;
+    record.module_path = strings.pop()?;
+    record.target = strings.pop()?;
+    Some((time, record))
+}
@@ -0,0 +1,94 @@
+use crate::appender::{Command, FastLogRecord, LogAppender, PipelineSender};
+use log::Level;
+use std::ops::RangeInclusive;
+
+/// route records to appenders by level, the first route contains the level wins.
+/// commands(for example flush) are sent to every route.
+/// for example:
+/// LevelRouterAppender::new()
+///     .route(Level::Error..=Level::Error, Box::new(ErrorAppender {}))
+///     .route(Level::Warn..=Level::Info, Box::new(FileAppender::new("requests.log")))
+pub struct LevelRouterAppender {
+    routes: Vec<(RangeInclusive<Level>, Box<dyn LogAppender>)>,
+}
+
+impl LevelRouterAppender {
+    pub fn new() -> Self {
+        Self { routes: vec![] }
+    }
+
+    /// levels: Error is the min level, Trace is the max level
+    pub fn route(mut self, levels: RangeInclusive<Level>, appender: Box<dyn LogAppender>) -> Self {
+        self.routes.push((levels, appender));
+        self
+    }
+}
+
+impl Default for LevelRouterAppender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogAppender for LevelRouterAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.eq(&Command::CommandRecord) {
+            for (levels, appender) in &self.routes {
+                if levels.contains(&record.level) {
+                    appender.do_log(record);
+                    return;
+                }
+            }
+        } else {
+            for (_, appender) in &self.routes {
+                appender.do_log(record);
+            }
+        }
+    }
+
+    /// the first route containing the level decides
+    fn accepts(&self, record: &FastLogRecord) -> bool {
+        for (levels, appender) in &self.routes {
+            if levels.contains(&record.level) {
+                return appender.accepts(record);
+            }
+        }
+        false
+    }
+
+    fn attach(&self, pipeline: &PipelineSender) {
+        for (_, appender) in &self.routes {
+            appender.attach(pipeline);
+        }
+    }
+
+    fn on_init(&self) {
+        for (_, appender) in &self.routes {
+            appender.on_init();
+        }
+    }
+
+    fn on_rotate(&self) {
+        for (_, appender) in &self.routes {
+            appender.on_rotate();
+        }
+    }
+
+    fn on_config_change(&self) {
+        for (_, appender) in &self.routes {
+            appender.on_config_change();
+        }
+    }
+
+    fn flush(&self) {
+        for (_, appender) in &self.routes {
+            appender.flush();
+        }
+    }
+
+    fn shutdown(&self) {
+        for (_, appender) in &self.routes {
+            appender.shutdown();
+        }
+    }
+}
@@ -1,4 +1,54 @@
+pub mod combinator;
 pub mod console;
 pub mod file;
 pub mod file_split;
+pub mod forward;
+#[cfg(feature = "gzip")]
+pub mod gz_file;
+pub mod influx;
+pub mod journal;
+pub mod level_router;
+pub mod net;
 pub mod packer;
+pub mod relp;
+pub mod statsd;
+pub mod summary;
+pub mod syslog;
+pub mod vector;
+pub mod zmq;
+#[cfg(feature = "audit")]
+pub mod audit;
+#[cfg(feature = "encrypt")]
+pub mod encrypt;
+#[cfg(feature = "serial")]
+pub mod serial;
+#[cfg(all(target_os = "linux", feature = "uring"))]
+pub mod uring;
+#[cfg(all(target_os = "android", feature = "android"))]
+pub mod android;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "oslog"))]
+pub mod os_log;
+#[cfg(windows)]
+pub mod event_log;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "http")]
+pub mod honeycomb;
+#[cfg(feature = "http")]
+pub mod datadog;
+#[cfg(feature = "http")]
+pub mod splunk;
+#[cfg(feature = "http")]
+pub mod clickhouse;
+#[cfg(feature = "amqp")]
+pub mod amqp;
+#[cfg(feature = "azure")]
+pub mod azure;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "mongo")]
+pub mod mongo;
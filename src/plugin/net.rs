@@ -0,0 +1,159 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crossbeam::channel::Sender;
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// a collector which accepts connections but does not read fails the write after it
+pub(crate) const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// wait between two connects while the collector is down
+pub(crate) const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// on exit the buffered records are retried until this timeout
+pub(crate) const EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// the largest payload of an udp datagram, a longer record is cut
+pub(crate) const MAX_DATAGRAM: usize = 65507;
+
+/// the first address of "host:port"
+pub(crate) fn resolve(addr: &str) -> std::io::Result<SocketAddr> {
+    addr.to_socket_addrs()?
+        .next()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, addr.to_string()))
+}
+
+/// an udp socket bound to any local port and connected to `addr`
+pub(crate) fn connect_udp(addr: SocketAddr) -> std::io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" })?;
+    socket.connect(addr)?;
+    Ok(socket)
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SocketProtocol {
+    /// a stream of formatted records(one per line)
+    Tcp,
+    /// one datagram per record
+    Udp,
+}
+
+enum SocketConn {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+impl SocketConn {
+    fn connect(protocol: SocketProtocol, addr: &str) -> std::io::Result<SocketConn> {
+        let addr = resolve(addr)?;
+        match protocol {
+            SocketProtocol::Tcp => {
+                let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(SocketConn::Tcp(stream))
+            }
+            SocketProtocol::Udp => connect_udp(addr).map(SocketConn::Udp),
+        }
+    }
+
+    fn send(&mut self, data: &[u8]) -> std::io::Result<()> {
+        match self {
+            SocketConn::Tcp(s) => s.write_all(data),
+            SocketConn::Udp(s) => s.send(&data[..data.len().min(MAX_DATAGRAM)]).map(|_| ()),
+        }
+    }
+}
+
+/// send the formatted records(see Config::format) to a collector over tcp or udp, no file on the disk.
+/// records are sent on its own thread, it reconnects every second while the collector is down
+/// and keeps at most `buffer_cap` records meanwhile, the newer ones are dropped
+pub struct SocketAppender {
+    sender: Option<Sender<Vec<u8>>>,
+    handle: Option<JoinHandle<()>>,
+    closing: Arc<AtomicBool>,
+}
+
+impl SocketAppender {
+    /// addr: for example "127.0.0.1:5170" or "collector.local:5170"
+    pub fn new(protocol: SocketProtocol, addr: &str, buffer_cap: usize) -> SocketAppender {
+        let (sender, receiver) = crossbeam::channel::bounded::<Vec<u8>>(buffer_cap.max(1));
+        let addr = addr.to_string();
+        let closing = Arc::new(AtomicBool::new(false));
+        let thread_closing = closing.clone();
+        let handle = std::thread::spawn(move || {
+            let mut conn: Option<SocketConn> = None;
+            let mut exit_deadline: Option<Instant> = None;
+            let mut reported = false;
+            for data in receiver.iter() {
+                loop {
+                    //on exit the buffer is sent until EXIT_TIMEOUT, also to a collector which is only slow
+                    if thread_closing.load(Ordering::Relaxed) {
+                        let deadline = *exit_deadline.get_or_insert(Instant::now() + EXIT_TIMEOUT);
+                        if Instant::now() >= deadline {
+                            crate::health::report_error(
+                                "SocketAppender",
+                                &format!("[fast_log] socket send({}) fail, {} records lost", addr, receiver.len() + 1),
+                            );
+                            return;
+                        }
+                    }
+                    if conn.is_none() {
+                        match SocketConn::connect(protocol, &addr) {
+                            Ok(c) => {
+                                conn = Some(c);
+                                reported = false;
+                            }
+                            Err(e) => {
+                                if !reported {
+                                    reported = true;
+                                    crate::health::report_error(
+                                        "SocketAppender",
+                                        &format!("[fast_log] socket connect({}) fail: {}", addr, e),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    if let Some(c) = conn.as_mut() {
+                        if c.send(&data).is_ok() {
+                            break;
+                        }
+                        //for example the write timed out, connect again
+                        conn = None;
+                    }
+                    std::thread::sleep(RECONNECT_INTERVAL);
+                }
+            }
+        });
+        Self {
+            sender: Some(sender),
+            handle: Some(handle),
+            closing,
+        }
+    }
+}
+
+impl LogAppender for SocketAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if let (Command::CommandRecord, Some(sender)) = (&record.command, &self.sender) {
+            //full: the collector is down too long, drop
+            sender.try_send(record.formated.as_bytes().to_vec());
+        }
+    }
+}
+
+impl Drop for SocketAppender {
+    /// send the buffered records, the pipeline drops appenders on exit
+    fn drop(&mut self) {
+        self.closing.store(true, Ordering::Relaxed);
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            handle.join();
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use oslog::{Level, OsLog};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// you need enable fast_log = { ... ,features=["oslog"]} (macos/ios)
+/// write records into Apple unified logging (Console.app, `log stream`).
+/// category is the record target, subsystem is the one given or the crate name of the target.
+/// level: Error->error, Warn->default, Info->info, Debug/Trace->debug
+pub struct OsLogAppender {
+    subsystem: Option<String>,
+    //(subsystem, category) -> log
+    logs: RefCell<HashMap<String, OsLog>>,
+}
+
+impl OsLogAppender {
+    /// subsystem: for example "com.example.app", None use the crate name of the target
+    pub fn new(subsystem: Option<&str>) -> Self {
+        Self {
+            subsystem: subsystem.map(|v| v.to_string()),
+            logs: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+impl LogAppender for OsLogAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.ne(&Command::CommandRecord) {
+            return;
+        }
+        let level = match record.level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Default,
+            log::Level::Info => Level::Info,
+            log::Level::Debug | log::Level::Trace => Level::Debug,
+        };
+        let mut logs = self.logs.borrow_mut();
+        let log = logs.entry(record.target.clone()).or_insert_with(|| {
+            let subsystem = match &self.subsystem {
+                Some(v) => v.as_str(),
+                None => record.target.split("::").next().unwrap_or(""),
+            };
+            OsLog::new(subsystem, &record.target)
+        });
+        log.with_level(level, &record.args);
+    }
+}
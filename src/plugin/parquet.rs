@@ -0,0 +1,102 @@
+use crate::appender::FastLogRecord;
+use crate::error::LogError;
+use crate::plugin::arrow::{log_schema, LogBatchBuilder};
+use crate::plugin::file_split::Packer;
+use crate::reader::parse_record;
+use crate::trace::TraceContext;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// rows of a row group
+const ROW_GROUP_SIZE: usize = 64 * 1024;
+
+/// you need enable fast_log = { ... ,features=["parquet"]}
+/// convert a rotated log into `{name}.parquet`(snappy) with the columns of plugin::arrow::log_schema.
+/// lines without a time (for example a backtrace) are part of the message before
+pub struct ParquetPacker {}
+
+/// take "  (file:line)" and " trace_id=.. span_id=.." added by FastLogFormatRecord off the message
+fn split_fields(record: &mut FastLogRecord) {
+    if let Some(index) = record.args.rfind(" trace_id=") {
+        let mut tail = record.args[index + 1..].split(' ');
+        let trace_id = tail.next().and_then(|v| v.strip_prefix("trace_id="));
+        let span_id = tail.next().and_then(|v| v.strip_prefix("span_id="));
+        if let (Some(trace_id), Some(span_id), None) = (trace_id, span_id, tail.next()) {
+            record.trace = Some(TraceContext {
+                trace_id: trace_id.to_string(),
+                span_id: span_id.to_string(),
+            });
+            record.args.truncate(index);
+        }
+    }
+    if record.args.ends_with(')') {
+        if let Some(index) = record.args.rfind("  (") {
+            let file = &record.args[index + 3..record.args.len() - 1];
+            record.file = match file.rfind(':') {
+                Some(v) => file[..v].to_string(),
+                None => file.to_string(),
+            };
+            record.args.truncate(index);
+        }
+    }
+}
+
+impl Packer for ParquetPacker {
+    fn pack_name(&self) -> &'static str {
+        "parquet"
+    }
+
+    fn do_pack(&self, log_file: File, log_file_path: &str) -> Result<bool, LogError> {
+        let parquet_path = log_file_path.replace(".log", ".parquet");
+        let file = File::create(&parquet_path)
+            .map_err(|e| LogError::from(format!("[fast_log] create({}) fail:{}", parquet_path, e)))?;
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_max_row_group_size(ROW_GROUP_SIZE)
+            .build();
+        let mut writer = ArrowWriter::try_new(file, log_schema(), Some(props))
+            .map_err(|e| LogError::from(format!("[fast_log] parquet writer fail:{}", e)))?;
+        let mut columns = LogBatchBuilder::new();
+        let mut last: Option<FastLogRecord> = None;
+        let push = |columns: &mut LogBatchBuilder, mut record: FastLogRecord| {
+            split_fields(&mut record);
+            columns.push(&record);
+        };
+        for line in BufReader::new(log_file).lines() {
+            let line = line.map_err(|e| LogError::from(format!("[fast_log] read({}) fail:{}", log_file_path, e)))?;
+            match parse_record(&line) {
+                Some(record) => {
+                    if let Some(last) = last.replace(record) {
+                        push(&mut columns, last);
+                    }
+                }
+                None => {
+                    if let Some(last) = last.as_mut() {
+                        last.args.push('\n');
+                        last.args.push_str(&line);
+                    }
+                }
+            }
+            if columns.rows() >= ROW_GROUP_SIZE {
+                writer
+                    .write(&columns.finish()?)
+                    .map_err(|e| LogError::from(format!("[fast_log] parquet write fail:{}", e)))?;
+            }
+        }
+        if let Some(last) = last.take() {
+            push(&mut columns, last);
+        }
+        if columns.rows() > 0 {
+            writer
+                .write(&columns.finish()?)
+                .map_err(|e| LogError::from(format!("[fast_log] parquet write fail:{}", e)))?;
+        }
+        writer
+            .close()
+            .map_err(|e| LogError::from(format!("[fast_log] parquet close fail:{}", e)))?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,221 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::plugin::syslog::{Facility, SyslogConn, SyslogFormat, SyslogTransport};
+use crossbeam::channel::{Receiver, RecvTimeoutError, Sender};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Write};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const RSP_TIMEOUT: Duration = Duration::from_secs(10);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+const EXIT_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_TXNR: u64 = 999_999_999;
+
+/// send records as RFC 5424 messages to rsyslog(imrelp) with RELP.
+/// up to `window` messages wait for their "rsp 200", a message leaves the buffer only once acknowledged,
+/// after a broken connection the unacknowledged messages are sent again(at-least-once).
+/// at most `buffer_cap` messages are kept while the server is down, the oldest is dropped
+pub struct RelpAppender {
+    sender: Option<Sender<String>>,
+    handle: Option<JoinHandle<()>>,
+    format: SyslogFormat,
+}
+
+impl RelpAppender {
+    /// transport: usually SyslogTransport::Tcp("host:2514"), RELP does not work over Udp or Unix
+    pub fn new(
+        transport: SyslogTransport,
+        facility: Facility,
+        app_name: &str,
+        hostname: Option<&str>,
+        window: usize,
+        buffer_cap: usize,
+    ) -> RelpAppender {
+        let (sender, receiver) = crossbeam::channel::unbounded();
+        let handle = std::thread::spawn(move || send_loop(transport, window.max(1), buffer_cap.max(1), receiver));
+        Self {
+            sender: Some(sender),
+            handle: Some(handle),
+            format: SyslogFormat::new(facility, app_name, hostname),
+        }
+    }
+}
+
+impl LogAppender for RelpAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if let (Command::CommandRecord, Some(sender)) = (&record.command, &self.sender) {
+            sender.send(self.format.format(record));
+        }
+    }
+}
+
+impl Drop for RelpAppender {
+    /// wait for the acknowledgments of the buffered messages, the pipeline drops appenders on exit
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            handle.join();
+        }
+    }
+}
+
+struct RelpSession {
+    conn: BufReader<SyslogConn>,
+    txnr: u64,
+}
+
+impl RelpSession {
+    fn open(transport: &SyslogTransport) -> std::io::Result<RelpSession> {
+        let conn = transport.connect()?;
+        if conn.is_datagram() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "relp needs a stream transport",
+            ));
+        }
+        conn.set_read_timeout(Some(RSP_TIMEOUT))?;
+        let mut session = RelpSession {
+            conn: BufReader::new(conn),
+            txnr: 0,
+        };
+        let txnr = session.write("open", b"relp_version=0\nrelp_software=fast_log\ncommands=syslog")?;
+        session.conn.get_mut().flush()?;
+        session.read_rsp(txnr)?;
+        Ok(session)
+    }
+
+    /// "TXNR SP COMMAND SP DATALEN [SP DATA] \n"
+    fn write(&mut self, command: &str, data: &[u8]) -> std::io::Result<u64> {
+        self.txnr = if self.txnr >= MAX_TXNR { 1 } else { self.txnr + 1 };
+        let mut frame = format!("{} {} {}", self.txnr, command, data.len()).into_bytes();
+        if !data.is_empty() {
+            frame.push(b' ');
+            frame.extend_from_slice(data);
+        }
+        frame.push(b'\n');
+        self.conn.get_mut().write_all(&frame)?;
+        Ok(self.txnr)
+    }
+
+    fn read_field(&mut self) -> std::io::Result<(String, u8)> {
+        let mut field = Vec::new();
+        loop {
+            let mut b = [0u8; 1];
+            self.conn.read_exact(&mut b)?;
+            if b[0] == b' ' || b[0] == b'\n' {
+                return Ok((String::from_utf8_lossy(&field).to_string(), b[0]));
+            }
+            if field.len() > 32 {
+                return Err(Error::new(ErrorKind::InvalidData, "relp header too long"));
+            }
+            field.push(b[0]);
+        }
+    }
+
+    /// (txnr, command, data)
+    fn read_frame(&mut self) -> std::io::Result<(u64, String, Vec<u8>)> {
+        let (txnr, _) = self.read_field()?;
+        let (command, _) = self.read_field()?;
+        let (len, end) = self.read_field()?;
+        let txnr = txnr.parse::<u64>().map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let len = len.parse::<usize>().map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let mut data = vec![0u8; len];
+        if end == b' ' {
+            self.conn.read_exact(&mut data)?;
+            let mut trailer = Vec::new();
+            self.conn.read_until(b'\n', &mut trailer)?;
+        }
+        Ok((txnr, command, data))
+    }
+
+    fn read_rsp(&mut self, txnr: u64) -> std::io::Result<()> {
+        let (rsp_txnr, command, data) = self.read_frame()?;
+        if command != "rsp" {
+            return Err(Error::new(ErrorKind::ConnectionAborted, format!("relp {}", command)));
+        }
+        if rsp_txnr != txnr || !data.starts_with(b"200") {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("relp rsp {}: {}", rsp_txnr, String::from_utf8_lossy(&data)),
+            ));
+        }
+        Ok(())
+    }
+
+    /// send the front of `pending` and remove the acknowledged messages
+    fn send(&mut self, pending: &mut VecDeque<String>, window: usize) -> std::io::Result<()> {
+        while !pending.is_empty() {
+            let count = pending.len().min(window);
+            let mut txnrs = Vec::with_capacity(count);
+            for msg in pending.iter().take(count) {
+                txnrs.push(self.write("syslog", msg.as_bytes())?);
+            }
+            self.conn.get_mut().flush()?;
+            for txnr in txnrs {
+                self.read_rsp(txnr)?;
+                pending.pop_front();
+            }
+        }
+        Ok(())
+    }
+
+    fn close(mut self) {
+        if let Ok(txnr) = self.write("close", b"") {
+            self.conn.get_mut().flush();
+            self.read_rsp(txnr);
+        }
+    }
+}
+
+fn send_loop(transport: SyslogTransport, window: usize, buffer_cap: usize, receiver: Receiver<String>) {
+    let mut pending = VecDeque::new();
+    let mut session: Option<RelpSession> = None;
+    let mut last_connect: Option<Instant> = None;
+    //on exit the unacknowledged messages are retried until this deadline
+    let mut exit_deadline: Option<Instant> = None;
+    loop {
+        if exit_deadline.is_none() {
+            match receiver.recv_timeout(RECONNECT_INTERVAL) {
+                Ok(v) => pending.push_back(v),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => exit_deadline = Some(Instant::now() + EXIT_TIMEOUT),
+            }
+            pending.extend(receiver.try_iter());
+            while pending.len() > buffer_cap {
+                pending.pop_front();
+            }
+        }
+        if pending.is_empty() {
+            if exit_deadline.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some(deadline) = exit_deadline {
+            if Instant::now() >= deadline {
+                crate::health::report_error(
+                    "RelpAppender",
+                    &format!("[fast_log] relp send fail, {} records lost", pending.len()),
+                );
+                break;
+            }
+        }
+        if session.is_none() {
+            if let Some(last) = last_connect {
+                if last.elapsed() < RECONNECT_INTERVAL {
+                    std::thread::sleep(RECONNECT_INTERVAL - last.elapsed());
+                }
+            }
+            last_connect = Some(Instant::now());
+            session = RelpSession::open(&transport).ok();
+        }
+        if let Some(s) = session.as_mut() {
+            if s.send(&mut pending, window).is_err() {
+                session = None;
+            }
+        }
+    }
+    if let Some(s) = session.take() {
+        s.close();
+    }
+}
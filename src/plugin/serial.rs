@@ -0,0 +1,130 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::error::LogError;
+use crossbeam::channel::{Receiver, Sender, TrySendError};
+pub use serialport::Parity;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// how a record is framed on the wire
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SerialFraming {
+    /// the formatted record, ends with '\n'
+    Line,
+    /// 0x02 + record + 0x03
+    StxEtx,
+}
+
+/// what to drop when the buffer is full (the uart is slower than the log)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DropPolicy {
+    DropNewest,
+    DropOldest,
+}
+
+/// you need enable fast_log = { ... ,features=["serial"]}
+/// write framed records to a serial device on its own thread,
+/// buffer at most `buffer_cap` records and drop by `drop_policy` when full
+pub struct SerialAppender {
+    sender: Option<Sender<Vec<u8>>>,
+    handle: Option<JoinHandle<()>>,
+    //used to drop the oldest frame
+    receiver: Receiver<Vec<u8>>,
+    framing: SerialFraming,
+    drop_policy: DropPolicy,
+    dropped: Arc<AtomicU64>,
+}
+
+impl SerialAppender {
+    /// path: for example "/dev/ttyUSB0" or "COM3"
+    pub fn new(
+        path: &str,
+        baud_rate: u32,
+        parity: Parity,
+        framing: SerialFraming,
+        buffer_cap: usize,
+        drop_policy: DropPolicy,
+    ) -> Result<SerialAppender, LogError> {
+        let open = {
+            let path = path.to_string();
+            move || {
+                serialport::new(path.as_str(), baud_rate)
+                    .parity(parity)
+                    .timeout(Duration::from_secs(1))
+                    .open()
+            }
+        };
+        let mut port = open().map_err(|e| LogError::from(format!("[fast_log] open serial port fail:{}", e)))?;
+        let (sender, receiver) = crossbeam::channel::bounded::<Vec<u8>>(buffer_cap.max(1));
+        let frames = receiver.clone();
+        let handle = std::thread::spawn(move || {
+            for frame in frames.iter() {
+                if port.write_all(&frame).is_err() {
+                    //device reset or unplugged, reopen and write again
+                    std::thread::sleep(Duration::from_secs(1));
+                    if let Ok(p) = open() {
+                        port = p;
+                        let _ = port.write_all(&frame);
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            sender: Some(sender),
+            handle: Some(handle),
+            receiver,
+            framing,
+            drop_policy,
+            dropped: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// records dropped because the buffer was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl LogAppender for SerialAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        let sender = match (&record.command, &self.sender) {
+            (Command::CommandRecord, Some(sender)) => sender,
+            _ => return,
+        };
+        let mut frame = match self.framing {
+            SerialFraming::Line => record.formated.as_bytes().to_vec(),
+            SerialFraming::StxEtx => {
+                let mut data = Vec::with_capacity(record.formated.len() + 2);
+                data.push(0x02);
+                data.extend_from_slice(record.formated.trim_end().as_bytes());
+                data.push(0x03);
+                data
+            }
+        };
+        loop {
+            match sender.try_send(frame) {
+                Err(TrySendError::Full(v)) => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    if self.drop_policy == DropPolicy::DropNewest {
+                        return;
+                    }
+                    let _ = self.receiver.try_recv();
+                    frame = v;
+                }
+                _ => return,
+            }
+        }
+    }
+}
+
+impl Drop for SerialAppender {
+    /// write the buffered frames, the pipeline drops appenders on exit
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            handle.join();
+        }
+    }
+}
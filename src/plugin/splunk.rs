@@ -0,0 +1,102 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::plugin::http::{HttpBatchConfig, HttpBatcher, HttpRequest};
+use serde_json::{json, Map, Value};
+use std::time::UNIX_EPOCH;
+
+/// you need enable fast_log = { ... ,features=["http"]}
+/// post records to the Splunk HTTP Event Collector, a batch is the events joined by '\n'.
+/// a busy collector answers 503, the batch is retried(see HttpBatchConfig::max_retries)
+pub struct SplunkHecAppender {
+    batcher: HttpBatcher,
+    source: String,
+    sourcetype: String,
+    index: Option<String>,
+    host: String,
+}
+
+impl SplunkHecAppender {
+    /// url: for example "https://splunk:8088", index: None = the default index of the token
+    pub fn new(
+        url: &str,
+        token: &str,
+        source: &str,
+        sourcetype: &str,
+        index: Option<&str>,
+        config: HttpBatchConfig,
+    ) -> SplunkHecAppender {
+        let url = format!("{}/services/collector/event", url.trim_end_matches('/'));
+        let authorization = format!("Splunk {}", token);
+        let batcher = HttpBatcher::new(
+            "splunk",
+            config,
+            Box::new(move |batch| {
+                let mut body = Vec::new();
+                for event in batch {
+                    if !body.is_empty() {
+                        body.push(b'\n');
+                    }
+                    serde_json::to_writer(&mut body, event);
+                }
+                HttpRequest {
+                    url: url.clone(),
+                    headers: vec![
+                        ("Authorization".to_string(), authorization.clone()),
+                        ("Content-Type".to_string(), "application/json".to_string()),
+                    ],
+                    body,
+                }
+            }),
+        );
+        Self {
+            batcher,
+            source: source.to_string(),
+            sourcetype: sourcetype.to_string(),
+            index: index.map(|v| v.to_string()),
+            host: crate::plugin::syslog::local_hostname(),
+        }
+    }
+
+    /// events dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.batcher.dropped()
+    }
+
+    fn event(&self, record: &FastLogRecord) -> Value {
+        let mut data = Map::new();
+        data.insert("level".to_string(), json!(record.level.as_str()));
+        data.insert("target".to_string(), json!(record.target));
+        data.insert("message".to_string(), json!(record.args));
+        data.insert("module_path".to_string(), json!(record.module_path));
+        data.insert("file".to_string(), json!(record.file));
+        data.insert("line".to_string(), json!(record.line));
+        if let Some(trace) = &record.trace {
+            data.insert("trace_id".to_string(), json!(trace.trace_id));
+            data.insert("span_id".to_string(), json!(trace.span_id));
+        }
+        let time = record.now.duration_since(UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or(0.0);
+        let mut event = Map::new();
+        event.insert("time".to_string(), json!((time * 1000.0).round() / 1000.0));
+        if !self.host.is_empty() {
+            event.insert("host".to_string(), json!(self.host));
+        }
+        event.insert("source".to_string(), json!(self.source));
+        event.insert("sourcetype".to_string(), json!(self.sourcetype));
+        if let Some(index) = &self.index {
+            event.insert("index".to_string(), json!(index));
+        }
+        event.insert("event".to_string(), Value::Object(data));
+        Value::Object(event)
+    }
+}
+
+impl LogAppender for SplunkHecAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if record.command.eq(&Command::CommandRecord) {
+            self.batcher.push(self.event(record));
+        }
+    }
+
+    fn flush(&self) {
+        self.batcher.flush();
+    }
+}
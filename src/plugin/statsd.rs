@@ -0,0 +1,120 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::error::LogError;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::net::UdpSocket;
+use std::time::{Duration, Instant};
+
+/// keep a udp packet under the common MTU
+const MAX_PACKET: usize = 1400;
+
+/// count records per level and per target, send the counters to statsd(udp) every `interval`.
+/// statsd:    {prefix}.level.error:3|c  and  {prefix}.target.my_app.db:3|c
+/// dogstatsd: {prefix}.records:3|c|#level:error,target:my_app::db
+pub struct StatsdAppender {
+    socket: UdpSocket,
+    prefix: String,
+    dogstatsd: bool,
+    interval: Duration,
+    cell: RefCell<StatsdData>,
+}
+
+struct StatsdData {
+    counts: HashMap<(log::Level, String), u64>,
+    last_send: Instant,
+}
+
+impl StatsdAppender {
+    /// addr: for example "127.0.0.1:8125"
+    pub fn new(addr: &str, prefix: &str, dogstatsd: bool, interval: Duration) -> Result<StatsdAppender, LogError> {
+        let socket = UdpSocket::bind("0.0.0.0:0")
+            .and_then(|s| s.connect(addr).map(|_| s))
+            .map_err(|e| LogError::from(format!("[fast_log] statsd connect({}) fail:{}", addr, e)))?;
+        socket.set_nonblocking(true);
+        Ok(Self {
+            socket,
+            prefix: prefix.to_string(),
+            dogstatsd,
+            interval,
+            cell: RefCell::new(StatsdData {
+                counts: HashMap::new(),
+                last_send: Instant::now(),
+            }),
+        })
+    }
+
+    fn lines(&self, counts: HashMap<(log::Level, String), u64>) -> Vec<String> {
+        let mut lines = vec![];
+        if self.dogstatsd {
+            for ((level, target), count) in counts {
+                lines.push(format!(
+                    "{}.records:{}|c|#level:{},target:{}",
+                    self.prefix,
+                    count,
+                    level.as_str().to_lowercase(),
+                    target
+                ));
+            }
+        } else {
+            let mut levels: HashMap<log::Level, u64> = HashMap::new();
+            let mut targets: HashMap<String, u64> = HashMap::new();
+            for ((level, target), count) in counts {
+                *levels.entry(level).or_insert(0) += count;
+                *targets.entry(target.replace("::", ".")).or_insert(0) += count;
+            }
+            for (level, count) in levels {
+                lines.push(format!("{}.level.{}:{}|c", self.prefix, level.as_str().to_lowercase(), count));
+            }
+            for (target, count) in targets {
+                lines.push(format!("{}.target.{}:{}|c", self.prefix, target, count));
+            }
+        }
+        lines
+    }
+
+    fn send(&self) {
+        let counts = {
+            let mut data = self.cell.borrow_mut();
+            data.last_send = Instant::now();
+            std::mem::take(&mut data.counts)
+        };
+        let mut packet = String::new();
+        for line in self.lines(counts) {
+            if !packet.is_empty() && packet.len() + line.len() + 1 > MAX_PACKET {
+                self.socket.send(packet.as_bytes());
+                packet.clear();
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(&line);
+        }
+        if !packet.is_empty() {
+            self.socket.send(packet.as_bytes());
+        }
+    }
+}
+
+impl LogAppender for StatsdAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        let need_send = {
+            let mut data = self.cell.borrow_mut();
+            if record.command.eq(&Command::CommandRecord) {
+                *data
+                    .counts
+                    .entry((record.level, record.target.clone()))
+                    .or_insert(0) += 1;
+            }
+            record.command.eq(&Command::CommandFlush) || data.last_send.elapsed() >= self.interval
+        };
+        if need_send {
+            self.send();
+        }
+    }
+}
+
+impl Drop for StatsdAppender {
+    fn drop(&mut self) {
+        self.send();
+    }
+}
@@ -0,0 +1,124 @@
+use crate::appender::{Command, FastLogFormatRecord, FastLogRecord, LogAppender, PipelineSender, RecordFormat};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// pass records to `inner` and count them per (level, target).
+/// every `window` a summary record per (level, target) is sent to `summary`, for example
+/// "... WARN fast_log::summary - my_app::db: 1,203 events in last 60s"
+pub struct SummaryAppender {
+    inner: Box<dyn LogAppender>,
+    summary: Box<dyn LogAppender>,
+    window: Duration,
+    format: FastLogFormatRecord,
+    cell: RefCell<SummaryData>,
+}
+
+struct SummaryData {
+    counts: BTreeMap<(log::Level, String), u64>,
+    start: Instant,
+}
+
+impl SummaryAppender {
+    pub fn new(inner: Box<dyn LogAppender>, summary: Box<dyn LogAppender>, window: Duration) -> Self {
+        Self {
+            inner,
+            summary,
+            window,
+            format: FastLogFormatRecord::new(),
+            cell: RefCell::new(SummaryData {
+                counts: BTreeMap::new(),
+                start: Instant::now(),
+            }),
+        }
+    }
+
+    fn emit(&self) {
+        let counts = {
+            let mut data = self.cell.borrow_mut();
+            data.start = Instant::now();
+            std::mem::take(&mut data.counts)
+        };
+        for ((level, target), count) in counts {
+            let mut record = FastLogRecord::new_command(Command::CommandRecord);
+            record.level = level;
+            record.target = "fast_log::summary".to_string();
+            record.module_path = "fast_log::summary".to_string();
+            record.args = format!(
+                "{}: {} events in last {}s",
+                target,
+                thousands(count),
+                self.window.as_secs()
+            )
+            .into();
+            self.format.do_format(&mut record);
+            self.summary.do_log(&mut record);
+        }
+    }
+}
+
+/// 1203 -> "1,203"
+fn thousands(n: u64) -> String {
+    let s = n.to_string();
+    let mut result = String::with_capacity(s.len() + s.len() / 3);
+    for (index, c) in s.chars().enumerate() {
+        if index != 0 && (s.len() - index) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(c);
+    }
+    result
+}
+
+impl LogAppender for SummaryAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        self.inner.do_log(record);
+        let elapsed = {
+            let mut data = self.cell.borrow_mut();
+            if record.command.eq(&Command::CommandRecord) {
+                *data
+                    .counts
+                    .entry((record.level, record.target.clone()))
+                    .or_insert(0) += 1;
+            }
+            data.start.elapsed() >= self.window
+        };
+        if elapsed {
+            self.emit();
+        }
+        if record.command.ne(&Command::CommandRecord) {
+            self.summary.do_log(record);
+        }
+    }
+
+    fn attach(&self, pipeline: &PipelineSender) {
+        self.inner.attach(pipeline);
+        self.summary.attach(pipeline);
+    }
+
+    fn on_init(&self) {
+        self.inner.on_init();
+        self.summary.on_init();
+    }
+
+    fn on_rotate(&self) {
+        self.inner.on_rotate();
+        self.summary.on_rotate();
+    }
+
+    fn on_config_change(&self) {
+        self.inner.on_config_change();
+        self.summary.on_config_change();
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+        self.summary.flush();
+    }
+
+    fn shutdown(&self) {
+        self.emit();
+        self.inner.shutdown();
+        self.summary.shutdown();
+    }
+}
@@ -0,0 +1,345 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+#[cfg(feature = "tls")]
+use crate::plugin::tls::{TlsOptions, TlsStream};
+use chrono::{DateTime, SecondsFormat, Utc};
+use crossbeam::channel::Sender;
+use std::io::{Read, Write};
+use crate::plugin::net::{connect_udp, resolve, EXIT_TIMEOUT, MAX_DATAGRAM, RECONNECT_INTERVAL, WRITE_TIMEOUT};
+use std::net::{TcpStream, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// syslog facility
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Facility {
+    Kern = 0,
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Lpr = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    AuthPriv = 10,
+    Ftp = 11,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// Error->err(3) Warn->warning(4) Info->info(6) Debug,Trace->debug(7)
+pub fn severity(level: log::Level) -> u8 {
+    match level {
+        log::Level::Error => 3,
+        log::Level::Warn => 4,
+        log::Level::Info => 6,
+        log::Level::Debug | log::Level::Trace => 7,
+    }
+}
+
+/// where the messages go
+#[derive(Clone, Debug)]
+pub enum SyslogTransport {
+    /// "host:port", octet-counted framing (RFC 6587)
+    Tcp(String),
+    /// "host:port"(usually 6514), RFC 5425 syslog over tls, octet-counted framing
+    #[cfg(feature = "tls")]
+    Tls(String, TlsOptions),
+    /// "host:port"(usually 514), one message per datagram (RFC 5426)
+    Udp(String),
+    /// the path of a unix datagram socket, usually "/dev/log", one message per datagram
+    #[cfg(unix)]
+    Unix(String),
+}
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(crate) enum SyslogConn {
+    Tcp(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<TlsStream>),
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+}
+
+impl SyslogTransport {
+    pub(crate) fn connect(&self) -> std::io::Result<SyslogConn> {
+        match self {
+            SyslogTransport::Tcp(addr) => {
+                let stream = TcpStream::connect_timeout(&resolve(addr)?, CONNECT_TIMEOUT)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(SyslogConn::Tcp(stream))
+            }
+            SyslogTransport::Udp(addr) => connect_udp(resolve(addr)?).map(SyslogConn::Udp),
+            #[cfg(unix)]
+            SyslogTransport::Unix(path) => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(path)?;
+                Ok(SyslogConn::Unix(socket))
+            }
+            #[cfg(feature = "tls")]
+            SyslogTransport::Tls(addr, options) => {
+                let stream = options.connect(addr, CONNECT_TIMEOUT)?;
+                stream.sock.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(SyslogConn::Tls(Box::new(stream)))
+            }
+        }
+    }
+}
+
+impl SyslogConn {
+    /// streams use octet counting: "{len} {msg}", datagrams carry one message.
+    /// an udp message is cut to MAX_DATAGRAM, a unix socket takes the whole message or fails
+    fn send(&mut self, msg: &str) -> std::io::Result<()> {
+        if let SyslogConn::Udp(s) = self {
+            return s.send(&msg.as_bytes()[..msg.len().min(MAX_DATAGRAM)]).map(|_| ());
+        }
+        if self.is_datagram() {
+            return self.write(msg.as_bytes()).map(|_| ());
+        }
+        let frame = format!("{} {}", msg.len(), msg);
+        self.write_all(frame.as_bytes())?;
+        self.flush()
+    }
+
+    /// Udp or Unix, they have no stream(RELP needs one)
+    pub(crate) fn is_datagram(&self) -> bool {
+        match self {
+            SyslogConn::Udp(_) => true,
+            #[cfg(unix)]
+            SyslogConn::Unix(_) => true,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            SyslogConn::Tcp(s) => s.set_read_timeout(timeout),
+            #[cfg(feature = "tls")]
+            SyslogConn::Tls(s) => s.sock.set_read_timeout(timeout),
+            SyslogConn::Udp(s) => s.set_read_timeout(timeout),
+            #[cfg(unix)]
+            SyslogConn::Unix(s) => s.set_read_timeout(timeout),
+        }
+    }
+}
+
+impl Read for SyslogConn {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            SyslogConn::Tcp(s) => s.read(buf),
+            #[cfg(feature = "tls")]
+            SyslogConn::Tls(s) => s.read(buf),
+            SyslogConn::Udp(s) => s.recv(buf),
+            #[cfg(unix)]
+            SyslogConn::Unix(s) => s.recv(buf),
+        }
+    }
+}
+
+impl Write for SyslogConn {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            SyslogConn::Tcp(s) => s.write(buf),
+            #[cfg(feature = "tls")]
+            SyslogConn::Tls(s) => s.write(buf),
+            SyslogConn::Udp(s) => s.send(buf),
+            #[cfg(unix)]
+            SyslogConn::Unix(s) => s.send(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            SyslogConn::Tcp(s) => s.flush(),
+            #[cfg(feature = "tls")]
+            SyslogConn::Tls(s) => s.flush(),
+            SyslogConn::Udp(_) => Ok(()),
+            #[cfg(unix)]
+            SyslogConn::Unix(_) => Ok(()),
+        }
+    }
+}
+
+/// the RFC 5424 header fields of this process
+#[derive(Clone, Debug)]
+pub struct SyslogFormat {
+    facility: Facility,
+    hostname: String,
+    app_name: String,
+    procid: String,
+}
+
+impl SyslogFormat {
+    /// hostname: None = the name of this host
+    pub fn new(facility: Facility, app_name: &str, hostname: Option<&str>) -> SyslogFormat {
+        let hostname = match hostname {
+            Some(v) => v.to_string(),
+            None => local_hostname(),
+        };
+        Self {
+            facility,
+            hostname: header_field(&hostname, 255),
+            app_name: header_field(app_name, 48),
+            procid: std::process::id().to_string(),
+        }
+    }
+
+    /// "<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID - MSG", the target is the MSGID
+    pub fn format(&self, record: &FastLogRecord) -> String {
+        let pri = (self.facility as u8) * 8 + severity(record.level);
+        let time: DateTime<Utc> = DateTime::from(record.now);
+        format!(
+            "<{}>1 {} {} {} {} {} - {}",
+            pri,
+            time.to_rfc3339_opts(SecondsFormat::Micros, true),
+            self.hostname,
+            self.app_name,
+            self.procid,
+            header_field(&record.target, 32),
+            record.args
+        )
+    }
+}
+
+/// send records as RFC 5424 messages(see SyslogFormat) over tcp, tls, udp or a unix socket(see SyslogTransport).
+/// messages are sent on its own thread, it reconnects every second while the server is down
+/// and keeps at most `buffer_cap` messages
+pub struct SyslogAppender {
+    sender: Option<Sender<String>>,
+    handle: Option<JoinHandle<()>>,
+    closing: Arc<AtomicBool>,
+    format: SyslogFormat,
+}
+
+impl SyslogAppender {
+    /// hostname: None = the name of this host
+    pub fn new(
+        transport: SyslogTransport,
+        facility: Facility,
+        app_name: &str,
+        hostname: Option<&str>,
+        buffer_cap: usize,
+    ) -> SyslogAppender {
+        let (sender, receiver) = crossbeam::channel::bounded::<String>(buffer_cap.max(1));
+        let closing = Arc::new(AtomicBool::new(false));
+        let thread_closing = closing.clone();
+        let handle = std::thread::spawn(move || {
+            let mut conn: Option<SyslogConn> = None;
+            let mut exit_deadline: Option<Instant> = None;
+            let mut reported = false;
+            for msg in receiver.iter() {
+                loop {
+                    //on exit the buffer is sent until EXIT_TIMEOUT, also to a collector which is only slow
+                    if thread_closing.load(Ordering::Relaxed) {
+                        let deadline = *exit_deadline.get_or_insert(Instant::now() + EXIT_TIMEOUT);
+                        if Instant::now() >= deadline {
+                            crate::health::report_error(
+                                "SyslogAppender",
+                                &format!("[fast_log] syslog send fail, {} messages lost", receiver.len() + 1),
+                            );
+                            return;
+                        }
+                    }
+                    let fresh = conn.is_none();
+                    if fresh {
+                        match transport.connect() {
+                            Ok(c) => {
+                                conn = Some(c);
+                                reported = false;
+                            }
+                            Err(e) => {
+                                if !reported {
+                                    reported = true;
+                                    crate::health::report_error(
+                                        "SyslogAppender",
+                                        &format!("[fast_log] syslog connect fail: {}", e),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    if let Some(c) = conn.as_mut() {
+                        match c.send(&msg) {
+                            Ok(_) => break,
+                            //a datagram refused by a new connection(for example too long) is not sent again
+                            Err(e) if fresh && c.is_datagram() => {
+                                crate::health::report_error(
+                                    "SyslogAppender",
+                                    &format!("[fast_log] syslog send fail, message dropped: {}", e),
+                                );
+                                conn = None;
+                                break;
+                            }
+                            //for example the write timed out, connect again
+                            Err(_) => conn = None,
+                        }
+                    }
+                    std::thread::sleep(RECONNECT_INTERVAL);
+                }
+            }
+        });
+        Self {
+            sender: Some(sender),
+            handle: Some(handle),
+            closing,
+            format: SyslogFormat::new(facility, app_name, hostname),
+        }
+    }
+}
+
+/// printable ascii without spaces, max `max` chars, "-" if empty
+fn header_field(v: &str, max: usize) -> String {
+    let v: String = v
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max)
+        .collect();
+    if v.is_empty() {
+        "-".to_string()
+    } else {
+        v
+    }
+}
+
+/// $HOSTNAME or /etc/hostname
+pub(crate) fn local_hostname() -> String {
+    if let Ok(v) = std::env::var("HOSTNAME") {
+        return v;
+    }
+    std::fs::read_to_string("/etc/hostname")
+        .map(|v| v.trim().to_string())
+        .unwrap_or_default()
+}
+
+impl LogAppender for SyslogAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if let (Command::CommandRecord, Some(sender)) = (&record.command, &self.sender) {
+            //full: the server is down too long, drop
+            sender.try_send(self.format.format(record));
+        }
+    }
+}
+
+impl Drop for SyslogAppender {
+    /// send the buffered messages, the pipeline drops appenders on exit
+    fn drop(&mut self) {
+        self.closing.store(true, Ordering::Relaxed);
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            handle.join();
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore, SignatureScheme, StreamOwned};
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// you need enable fast_log = { ... ,features=["tls"]}
+/// client side tls of the network appenders
+#[derive(Clone, Debug)]
+pub struct TlsOptions {
+    /// the name checked against the server certificate, None = the host of the address
+    pub server_name: Option<String>,
+    /// pem file of the trusted CAs, None = the webpki(mozilla) roots
+    pub ca_file: Option<String>,
+    /// pem files (cert chain, private key) for client authentication
+    pub client_cert: Option<(String, String)>,
+    /// false skips the certificate verification, only for testing
+    pub verify: bool,
+}
+
+impl Default for TlsOptions {
+    fn default() -> Self {
+        Self {
+            server_name: None,
+            ca_file: None,
+            client_cert: None,
+            verify: true,
+        }
+    }
+}
+
+pub type TlsStream = StreamOwned<ClientConnection, TcpStream>;
+
+fn invalid<E: std::fmt::Display>(e: E) -> Error {
+    Error::new(ErrorKind::InvalidInput, e.to_string())
+}
+
+impl TlsOptions {
+    pub fn client_config(&self) -> std::io::Result<ClientConfig> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(invalid)?;
+        let builder = if self.verify {
+            let mut roots = RootCertStore::empty();
+            match &self.ca_file {
+                Some(ca_file) => {
+                    for cert in CertificateDer::pem_file_iter(ca_file).map_err(invalid)? {
+                        roots.add(cert.map_err(invalid)?).map_err(invalid)?;
+                    }
+                }
+                None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+            }
+            builder.with_root_certificates(roots)
+        } else {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(NoVerifier(provider)))
+        };
+        match &self.client_cert {
+            Some((cert_file, key_file)) => {
+                let certs = CertificateDer::pem_file_iter(cert_file)
+                    .map_err(invalid)?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(invalid)?;
+                let key = PrivateKeyDer::from_pem_file(key_file).map_err(invalid)?;
+                builder.with_client_auth_cert(certs, key).map_err(invalid)
+            }
+            None => Ok(builder.with_no_client_auth()),
+        }
+    }
+
+    /// connect "host:port" and finish the handshake
+    pub fn connect(&self, addr: &str, timeout: Duration) -> std::io::Result<TlsStream> {
+        let host = match &self.server_name {
+            Some(v) => v.clone(),
+            None => addr
+                .rsplit_once(':')
+                .map(|(host, _)| host)
+                .unwrap_or(addr)
+                .trim_matches(|c| c == '[' || c == ']')
+                .to_string(),
+        };
+        let server_name = ServerName::try_from(host).map_err(invalid)?;
+        let conn = ClientConnection::new(Arc::new(self.client_config()?), server_name).map_err(invalid)?;
+        let tcp = TcpStream::connect(addr)?;
+        tcp.set_read_timeout(Some(timeout))?;
+        tcp.set_write_timeout(Some(timeout))?;
+        let mut stream = StreamOwned::new(conn, tcp);
+        while stream.conn.is_handshaking() {
+            stream.conn.complete_io(&mut stream.sock)?;
+        }
+        Ok(stream)
+    }
+}
+
+/// TlsOptions::verify = false
+#[derive(Debug)]
+struct NoVerifier(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
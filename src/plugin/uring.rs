@@ -0,0 +1,224 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::error::LogError;
+use crate::plugin::file::FileAppender;
+use crossbeam::channel::{Receiver, Sender, TryRecvError};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::rc::Rc;
+use std::thread::JoinHandle;
+
+/// the writes submitted and not completed, one more waits for the oldest
+const MAX_IN_FLIGHT: usize = 64;
+
+enum UringCommand {
+    Write(Vec<u8>),
+    //all writes completed
+    Flush(Sender<()>),
+    Reopen,
+}
+
+/// you need enable fast_log = { ... ,features=["uring"]}, only on linux
+/// append to a file with io_uring: the batches are submitted on a writer thread, so the worker never waits for the disk,
+/// up to 64 writes are in flight and the writer thread reaps their completions.
+/// the writes have their own offsets(no O_APPEND), the file must be written by this appender only.
+/// without io_uring(an old kernel or seccomp) it writes like a FileAppender
+pub struct UringFileAppender {
+    sender: Option<Sender<UringCommand>>,
+    handle: Option<JoinHandle<()>>,
+    fallback: Option<FileAppender>,
+}
+
+impl UringFileAppender {
+    pub fn new(log_file_path: &str) -> Result<UringFileAppender, LogError> {
+        let path = log_file_path.replace("\\", "/");
+        let file = open_file(&path).map_err(|e| LogError::from(format!("[fast_log] open {} fail:{}", path, e)))?;
+        let (sender, receiver) = crossbeam::channel::unbounded();
+        let (ready_send, ready) = crossbeam::channel::bounded(1);
+        let writer_path = path.clone();
+        let handle = std::thread::spawn(move || {
+            //the runtime is not Send, build it on the writer thread
+            let runtime = match tokio_uring::Runtime::new(&tokio_uring::builder()) {
+                Ok(v) => v,
+                Err(e) => {
+                    ready_send.send(Err(e));
+                    return;
+                }
+            };
+            ready_send.send(Ok(()));
+            runtime.block_on(run(writer_path, file, receiver));
+        });
+        let error = match ready.recv() {
+            Ok(Ok(())) => {
+                return Ok(Self {
+                    sender: Some(sender),
+                    handle: Some(handle),
+                    fallback: None,
+                })
+            }
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "writer thread exited".to_string(),
+        };
+        handle.join();
+        crate::health::report_error(
+            "UringFileAppender",
+            &format!("[fast_log] io_uring unavailable({}), {} uses write", error, path),
+        );
+        Ok(Self {
+            sender: None,
+            handle: None,
+            fallback: Some(FileAppender::new(&path)),
+        })
+    }
+
+    /// false if it fell back to write
+    pub fn is_uring(&self) -> bool {
+        self.fallback.is_none()
+    }
+}
+
+fn open_file(path: &str) -> std::io::Result<File> {
+    if let Some(right) = path.rfind('/') {
+        std::fs::create_dir_all(&path[0..right]);
+    }
+    OpenOptions::new().create(true).write(true).truncate(false).open(path)
+}
+
+type Write = tokio::task::JoinHandle<std::io::Result<()>>;
+
+async fn run(path: String, file: File, receiver: Receiver<UringCommand>) {
+    let mut offset = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut file = Rc::new(tokio_uring::fs::File::from_std(file));
+    let mut in_flight: VecDeque<Write> = VecDeque::new();
+    loop {
+        //nothing in flight: wait for a command, else reap the oldest write while no command waits
+        let command = if in_flight.is_empty() {
+            match receiver.recv() {
+                Ok(v) => v,
+                Err(_) => break,
+            }
+        } else {
+            match receiver.try_recv() {
+                Ok(v) => v,
+                Err(TryRecvError::Empty) => {
+                    reap(&path, in_flight.pop_front()).await;
+                    continue;
+                }
+                Err(TryRecvError::Disconnected) => break,
+            }
+        };
+        match command {
+            UringCommand::Write(buf) => {
+                let len = buf.len() as u64;
+                let writer = file.clone();
+                //the spawned writes are submitted together on the next await
+                in_flight.push_back(tokio_uring::spawn(async move { writer.write_all_at(buf, offset).await.0 }));
+                offset += len;
+                if in_flight.len() > MAX_IN_FLIGHT {
+                    reap(&path, in_flight.pop_front()).await;
+                }
+            }
+            UringCommand::Flush(done) => {
+                reap_all(&path, &mut in_flight).await;
+                done.send(());
+            }
+            UringCommand::Reopen => {
+                reap_all(&path, &mut in_flight).await;
+                match open_file(&path) {
+                    Ok(v) => {
+                        offset = v.metadata().map(|m| m.len()).unwrap_or(0);
+                        close(std::mem::replace(&mut file, Rc::new(tokio_uring::fs::File::from_std(v)))).await;
+                    }
+                    Err(e) => {
+                        crate::health::report_error("UringFileAppender", &format!("[fast_log] reopen {} fail:{}", path, e));
+                    }
+                }
+            }
+        }
+    }
+    reap_all(&path, &mut in_flight).await;
+    close(file).await;
+}
+
+async fn reap(path: &str, write: Option<Write>) {
+    let error = match write {
+        Some(write) => match write.await {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => e.to_string(),
+            Err(e) => e.to_string(),
+        },
+        None => return,
+    };
+    crate::health::report_error("UringFileAppender", &format!("[fast_log] write {} fail:{}", path, error));
+}
+
+async fn reap_all(path: &str, in_flight: &mut VecDeque<Write>) {
+    while !in_flight.is_empty() {
+        reap(path, in_flight.pop_front()).await;
+    }
+}
+
+//the writes are reaped, so this is the last handle
+async fn close(file: Rc<tokio_uring::fs::File>) {
+    if let Ok(file) = Rc::try_unwrap(file) {
+        file.close().await;
+    }
+}
+
+impl LogAppender for UringFileAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if let Some(fallback) = &self.fallback {
+            fallback.do_log(record);
+            return;
+        }
+        let sender = match &self.sender {
+            Some(v) => v,
+            None => return,
+        };
+        match record.command {
+            Command::CommandFlush => {
+                //flush_and_wait: the records are written when it returns
+                let (done_send, done) = crossbeam::channel::bounded(1);
+                if sender.send(UringCommand::Flush(done_send)).is_ok() {
+                    done.recv();
+                }
+            }
+            Command::CommandReopen => {
+                sender.send(UringCommand::Reopen);
+            }
+            _ => {
+                sender.send(UringCommand::Write(record.formated.as_bytes().to_vec()));
+            }
+        }
+    }
+
+    /// the whole batch in one write
+    fn do_logs(&self, records: &mut [FastLogRecord]) {
+        if let Some(fallback) = &self.fallback {
+            fallback.do_logs(records);
+            return;
+        }
+        let mut buf = Vec::with_capacity(records.iter().map(|v| v.formated.len()).sum());
+        for record in records.iter() {
+            buf.extend_from_slice(record.formated.as_bytes());
+        }
+        if let Some(sender) = &self.sender {
+            sender.send(UringCommand::Write(buf));
+        }
+    }
+
+    fn shutdown(&self) {
+        if let Some(fallback) = &self.fallback {
+            fallback.shutdown();
+        }
+    }
+}
+
+impl Drop for UringFileAppender {
+    /// the submitted writes complete, the pipeline drops appenders on exit
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            handle.join();
+        }
+    }
+}
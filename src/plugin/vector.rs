@@ -0,0 +1,145 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::relay::RelayConn;
+use chrono::{DateTime, SecondsFormat, Utc};
+use crossbeam::channel::Sender;
+use serde_json::json;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// on exit the buffered events are retried until this timeout
+const EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `framing.method` of the Vector socket source
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VectorFraming {
+    /// "newline_delimited"
+    NewlineDelimited,
+    /// "length_delimited", a 4 bytes big endian length before each event
+    LengthDelimited,
+}
+
+/// send records as json events to a local Vector agent, configure a `socket` source with the json codec:
+/// ```toml
+/// [sources.fast_log]
+/// type = "socket"
+/// mode = "tcp"            # or "unix_stream" with path = "/tmp/vector.sock"
+/// address = "127.0.0.1:9000"
+/// decoding.codec = "json"
+/// framing.method = "length_delimited"
+/// ```
+/// the event follows the Vector log schema(message, timestamp, host) plus
+/// level, target, module_path, file, line, trace_id and span_id
+pub struct VectorAppender {
+    sender: Option<Sender<Vec<u8>>>,
+    handle: Option<JoinHandle<()>>,
+    closing: Arc<AtomicBool>,
+    framing: VectorFraming,
+    host: String,
+}
+
+impl VectorAppender {
+    /// addr: "127.0.0.1:9000" or "unix:/tmp/vector.sock", at most `buffer_cap` events wait while Vector is down
+    pub fn new(addr: &str, framing: VectorFraming, buffer_cap: usize) -> VectorAppender {
+        let (sender, receiver) = crossbeam::channel::bounded::<Vec<u8>>(buffer_cap.max(1));
+        let addr = addr.to_string();
+        let closing = Arc::new(AtomicBool::new(false));
+        let thread_closing = closing.clone();
+        let handle = std::thread::spawn(move || {
+            let mut conn: Option<RelayConn> = None;
+            let mut exit_deadline: Option<Instant> = None;
+            for frame in receiver.iter() {
+                loop {
+                    if conn.is_none() {
+                        match RelayConn::connect(&addr) {
+                            Ok(c) => conn = Some(c),
+                            Err(_) => {
+                                if thread_closing.load(Ordering::Relaxed) {
+                                    let deadline = *exit_deadline.get_or_insert(Instant::now() + EXIT_TIMEOUT);
+                                    if Instant::now() >= deadline {
+                                        crate::health::report_error(
+                                            "VectorAppender",
+                                            &format!(
+                                                "[fast_log] vector connect({}) fail, {} events lost",
+                                                addr,
+                                                receiver.len() + 1
+                                            ),
+                                        );
+                                        return;
+                                    }
+                                }
+                                std::thread::sleep(Duration::from_millis(500));
+                                continue;
+                            }
+                        }
+                    }
+                    if conn.as_mut().unwrap().write_all(&frame).is_ok() {
+                        break;
+                    }
+                    conn = None;
+                }
+            }
+        });
+        Self {
+            sender: Some(sender),
+            handle: Some(handle),
+            closing,
+            framing,
+            host: crate::plugin::syslog::local_hostname(),
+        }
+    }
+
+    fn frame(&self, record: &FastLogRecord) -> Vec<u8> {
+        let time: DateTime<Utc> = DateTime::from(record.now);
+        let mut event = json!({
+            "message": record.args,
+            "timestamp": time.to_rfc3339_opts(SecondsFormat::Nanos, true),
+            "host": self.host,
+            "level": record.level.as_str(),
+            "target": record.target,
+            "module_path": record.module_path,
+            "file": record.file,
+            "line": record.line,
+        });
+        if let Some(trace) = &record.trace {
+            event["trace_id"] = json!(trace.trace_id);
+            event["span_id"] = json!(trace.span_id);
+        }
+        let body = serde_json::to_vec(&event).unwrap_or_default();
+        match self.framing {
+            VectorFraming::NewlineDelimited => {
+                let mut frame = body;
+                frame.push(b'\n');
+                frame
+            }
+            VectorFraming::LengthDelimited => {
+                let mut frame = Vec::with_capacity(body.len() + 4);
+                frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+                frame.extend_from_slice(&body);
+                frame
+            }
+        }
+    }
+}
+
+impl LogAppender for VectorAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if let (Command::CommandRecord, Some(sender)) = (&record.command, &self.sender) {
+            //full: vector is down too long, drop
+            sender.try_send(self.frame(record));
+        }
+    }
+}
+
+impl Drop for VectorAppender {
+    /// send the buffered events, the pipeline drops appenders on exit
+    fn drop(&mut self) {
+        self.closing.store(true, Ordering::Relaxed);
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            handle.join();
+        }
+    }
+}
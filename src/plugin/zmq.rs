@@ -0,0 +1,246 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::error::LogError;
+use crate::relay::{RelayRecord, ACCEPT_POLL};
+use crossbeam::channel::{Receiver, Sender};
+use parking_lot::Mutex;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// the payload frame of a published record
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ZmqPayload {
+    /// the formatted line
+    Formatted,
+    /// a relay::RelayRecord as json
+    Json,
+}
+
+/// a ZeroMQ PUB socket (ZMTP 3.0 over tcp, NULL security) publishing every record as a two frames message:
+/// topic "{topic_prefix}{LEVEL}.{target}" and the payload, so `SUB` sockets filter on "{topic_prefix}ERROR" etc.
+/// like libzmq PUB, a subscriber which can not keep up is disconnected and records are dropped, never blocking the logger
+pub struct ZmqAppender {
+    sender: Option<Sender<(Vec<u8>, Vec<u8>)>>,
+    //stops the accept thread, the port is closed on drop
+    stop: Arc<AtomicBool>,
+    accept: Option<JoinHandle<()>>,
+    publisher: Option<JoinHandle<()>>,
+    topic_prefix: String,
+    payload: ZmqPayload,
+}
+
+struct Peer {
+    stream: TcpStream,
+    subscriptions: Arc<Mutex<Vec<Vec<u8>>>>,
+    //set when the reader thread exits(the subscriber closed the connection)
+    closed: Arc<AtomicBool>,
+}
+
+impl Drop for Peer {
+    /// the reader thread exits too
+    fn drop(&mut self) {
+        self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+impl ZmqAppender {
+    /// bind_addr: for example "0.0.0.0:5556", subscribers connect "tcp://host:5556".
+    /// hwm: messages queued for the publisher thread, more are dropped
+    pub fn new(bind_addr: &str, topic_prefix: &str, payload: ZmqPayload, hwm: usize) -> Result<ZmqAppender, LogError> {
+        let listener = TcpListener::bind(bind_addr)
+            .map_err(|e| LogError::from(format!("[fast_log] zmq bind({}) fail:{}", bind_addr, e)))?;
+        //non blocking, so the accept thread sees stop
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| LogError::from(format!("[fast_log] zmq listen fail:{}", e)))?;
+        let (peer_sender, peer_recv) = crossbeam::channel::unbounded::<Peer>();
+        let stop = Arc::new(AtomicBool::new(false));
+        let accept_stop = stop.clone();
+        let accept = std::thread::spawn(move || {
+            while !accept_stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let peer_sender = peer_sender.clone();
+                        std::thread::spawn(move || {
+                            //an accepted socket may inherit the non blocking mode(bsd)
+                            stream.set_nonblocking(false);
+                            if let Ok(peer) = handshake(stream) {
+                                peer_sender.send(peer);
+                            }
+                        });
+                    }
+                    Err(_) => std::thread::sleep(ACCEPT_POLL),
+                }
+            }
+        });
+        let (sender, receiver) = crossbeam::channel::bounded(hwm.max(1));
+        let publisher = std::thread::spawn(move || publish_loop(receiver, peer_recv));
+        Ok(Self {
+            sender: Some(sender),
+            stop,
+            accept: Some(accept),
+            publisher: Some(publisher),
+            topic_prefix: topic_prefix.to_string(),
+            payload,
+        })
+    }
+}
+
+impl LogAppender for ZmqAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        let sender = match (&record.command, &self.sender) {
+            (Command::CommandRecord, Some(sender)) => sender,
+            _ => return,
+        };
+        let topic = format!("{}{}.{}", self.topic_prefix, record.level, record.target).into_bytes();
+        let body = match self.payload {
+            ZmqPayload::Formatted => record.formated.as_bytes().to_vec(),
+            ZmqPayload::Json => serde_json::to_vec(&RelayRecord::from_record(record, "")).unwrap_or_default(),
+        };
+        sender.try_send((topic, body));
+    }
+}
+
+impl Drop for ZmqAppender {
+    /// close the port and the connections of the subscribers
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.sender.take();
+        if let Some(accept) = self.accept.take() {
+            accept.join();
+        }
+        if let Some(publisher) = self.publisher.take() {
+            publisher.join();
+        }
+    }
+}
+
+fn publish_loop(receiver: Receiver<(Vec<u8>, Vec<u8>)>, peer_recv: Receiver<Peer>) {
+    let mut peers: Vec<Peer> = vec![];
+    let mut buf = Vec::new();
+    for (topic, body) in receiver.iter() {
+        while let Ok(peer) = peer_recv.try_recv() {
+            peers.push(peer);
+        }
+        if peers.is_empty() {
+            continue;
+        }
+        buf.clear();
+        write_frame(&mut buf, 0x01, &topic);
+        write_frame(&mut buf, 0x00, &body);
+        peers.retain_mut(|peer| {
+            if peer.closed.load(Ordering::Relaxed) {
+                return false;
+            }
+            let subscribed = peer.subscriptions.lock().iter().any(|s| topic.starts_with(s));
+            !subscribed || peer.stream.write_all(&buf).is_ok()
+        });
+    }
+}
+
+/// flags: 0x01 more, 0x02 long, 0x04 command
+fn write_frame(buf: &mut Vec<u8>, flags: u8, body: &[u8]) {
+    if body.len() > 255 {
+        buf.push(flags | 0x02);
+        buf.extend_from_slice(&(body.len() as u64).to_be_bytes());
+    } else {
+        buf.push(flags);
+        buf.push(body.len() as u8);
+    }
+    buf.extend_from_slice(body);
+}
+
+/// (flags, body)
+fn read_frame(stream: &mut TcpStream) -> std::io::Result<(u8, Vec<u8>)> {
+    let mut flags = [0u8; 1];
+    stream.read_exact(&mut flags)?;
+    let len = if flags[0] & 0x02 != 0 {
+        let mut len = [0u8; 8];
+        stream.read_exact(&mut len)?;
+        u64::from_be_bytes(len) as usize
+    } else {
+        let mut len = [0u8; 1];
+        stream.read_exact(&mut len)?;
+        len[0] as usize
+    };
+    if len > 1024 * 1024 {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "zmq frame too large"));
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body)?;
+    Ok((flags[0], body))
+}
+
+fn handshake(mut stream: TcpStream) -> std::io::Result<Peer> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    //greeting: signature, version 3.0, mechanism NULL, as-server 0, filler
+    let mut greeting = [0u8; 64];
+    greeting[0] = 0xFF;
+    greeting[9] = 0x7F;
+    greeting[10] = 3;
+    greeting[12..16].copy_from_slice(b"NULL");
+    stream.write_all(&greeting)?;
+    let mut peer_greeting = [0u8; 64];
+    stream.read_exact(&mut peer_greeting)?;
+    if peer_greeting[0] != 0xFF || peer_greeting[9] != 0x7F || peer_greeting[10] < 3 {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "not a zmtp 3 peer"));
+    }
+    //READY with Socket-Type PUB
+    let mut ready = Vec::new();
+    ready.push(5u8);
+    ready.extend_from_slice(b"READY");
+    ready.push(11u8);
+    ready.extend_from_slice(b"Socket-Type");
+    ready.extend_from_slice(&3u32.to_be_bytes());
+    ready.extend_from_slice(b"PUB");
+    let mut buf = Vec::new();
+    write_frame(&mut buf, 0x04, &ready);
+    stream.write_all(&buf)?;
+    let (flags, body) = read_frame(&mut stream)?;
+    if flags & 0x04 == 0 || !body.starts_with(b"\x05READY") {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "zmq peer not ready"));
+    }
+    stream.set_read_timeout(None)?;
+    //a subscriber which can not keep up is dropped
+    stream.set_write_timeout(Some(Duration::from_millis(500)))?;
+    let subscriptions = Arc::new(Mutex::new(vec![]));
+    let closed = Arc::new(AtomicBool::new(false));
+    let mut reader = stream.try_clone()?;
+    let subs = subscriptions.clone();
+    let reader_closed = closed.clone();
+    std::thread::spawn(move || {
+        while let Ok((flags, body)) = read_frame(&mut reader) {
+            //zmtp 3.0: message [1|0, topic], zmtp 3.1: command SUBSCRIBE/CANCEL
+            let (subscribe, topic) = if flags & 0x04 != 0 {
+                if let Some(topic) = body.strip_prefix(b"\x09SUBSCRIBE") {
+                    (true, topic.to_vec())
+                } else if let Some(topic) = body.strip_prefix(b"\x06CANCEL") {
+                    (false, topic.to_vec())
+                } else {
+                    continue;
+                }
+            } else if let Some((first, topic)) = body.split_first() {
+                (*first == 1, topic.to_vec())
+            } else {
+                continue;
+            };
+            let mut subs = subs.lock();
+            if subscribe {
+                subs.push(topic);
+            } else if let Some(index) = subs.iter().position(|s| *s == topic) {
+                subs.remove(index);
+            }
+        }
+        //closed: the publisher drops the peer on its next record
+        reader_closed.store(true, Ordering::Relaxed);
+        reader.shutdown(std::net::Shutdown::Both);
+    });
+    Ok(Peer {
+        stream,
+        subscriptions,
+        closed,
+    })
+}
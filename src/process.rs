@@ -0,0 +1,63 @@
+use crate::emit::LevelRecordBuilder;
+use log::Level;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread::JoinHandle;
+
+/// a child process whose stdout/stderr lines are logged, see spawn
+pub struct LoggedChild {
+    pub child: Child,
+    readers: Vec<JoinHandle<()>>,
+}
+
+impl LoggedChild {
+    /// wait for the process to exit and for its output to be logged
+    pub fn wait(&mut self) -> std::io::Result<ExitStatus> {
+        let status = self.child.wait()?;
+        for x in self.readers.drain(..) {
+            x.join();
+        }
+        Ok(status)
+    }
+}
+
+/// spawn the command with piped stdout/stderr, every line is a record with the program name as target, for example
+/// fast_log::process::spawn(Command::new("pg_dump").arg("db"), Level::Info, Level::Warn)?.wait()?;
+pub fn spawn(command: &mut Command, stdout_level: Level, stderr_level: Level) -> std::io::Result<LoggedChild> {
+    let name = std::path::Path::new(command.get_program())
+        .file_name()
+        .map(|v| v.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let child = command.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    Ok(capture(child, &name, stdout_level, stderr_level))
+}
+
+/// log the piped stdout/stderr of a spawned child with the target `name`
+pub fn capture(mut child: Child, name: &str, stdout_level: Level, stderr_level: Level) -> LoggedChild {
+    let mut readers = vec![];
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(spawn_reader(stdout, name.to_string(), stdout_level));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(spawn_reader(stderr, name.to_string(), stderr_level));
+    }
+    LoggedChild { child, readers }
+}
+
+fn spawn_reader<R: Read + Send + 'static>(pipe: R, name: String, level: Level) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut line = vec![];
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let text = String::from_utf8_lossy(&line);
+                    let text = text.trim_end_matches(['\r', '\n']);
+                    crate::emit::emit(LevelRecordBuilder::new(level, text).target(&name));
+                }
+            }
+        }
+    })
+}
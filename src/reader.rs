@@ -0,0 +1,366 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::error::LogError;
+use crate::plugin::file::{parse_meta, META_PREFIX};
+use crate::relay::RelayRecord;
+use crate::trace::TraceContext;
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use log::Level;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+#[cfg(feature = "zip")]
+use std::io::{Cursor, Read};
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+/// one line of a log file
+#[derive(Clone, Debug)]
+pub struct LogLine {
+    /// the log file(or archive) the line comes from
+    pub file: PathBuf,
+    /// byte offset of the line in the (decompressed) log data
+    pub offset: u64,
+    /// line number in the log file, start from 1
+    pub line_no: u64,
+    /// line data without the line end
+    pub line: String,
+}
+
+impl LogLine {
+    /// parse the line as json, for logs written with a json RecordFormat
+    pub fn json(&self) -> Option<serde_json::Value> {
+        serde_json::from_str(&self.line).ok()
+    }
+}
+
+/// parse a json line of the record schema(see relay::RelayRecord, consts::SCHEMA_VERSION) or of
+/// JsonFormatRecord back into a record.
+/// lines of version 1 without "v" parse too, the fields of a newer version are skipped
+pub fn json_record(line: &str) -> Option<FastLogRecord> {
+    match serde_json::from_str::<RelayRecord>(line) {
+        Ok(v) => Some(v.into_record()),
+        Err(_) => json_format_record(line),
+    }
+}
+
+/// a line of JsonFormatRecord, formated is the line
+fn json_format_record(line: &str) -> Option<FastLogRecord> {
+    let json: serde_json::Value = serde_json::from_str(line).ok()?;
+    let text = |key: &str| json[key].as_str().unwrap_or("").to_string();
+    let time = chrono::DateTime::parse_from_rfc3339(json["timestamp"].as_str()?).ok()?;
+    let mut record = FastLogRecord::new_command(Command::CommandRecord);
+    record.level = Level::from_str(json["level"].as_str()?).ok()?;
+    record.target = text("target");
+    record.module_path = text("module_path");
+    record.file = text("file");
+    record.line = json["line"].as_u64().map(|v| v as u32);
+    record.args = text("message").into();
+    record.now = SystemTime::from(time);
+    record.monotonic = None;
+    if let (Some(trace_id), Some(span_id)) = (json["trace_id"].as_str(), json["span_id"].as_str()) {
+        record.trace = Some(TraceContext {
+            trace_id: trace_id.to_string(),
+            span_id: span_id.to_string(),
+        });
+    }
+    if let Some(fields) = json["fields"].as_object() {
+        record.kv = fields.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    }
+    if let Some(tags) = json["tags"].as_array() {
+        record.tags = tags.iter().filter_map(|v| v.as_str().map(|v| v.to_string())).collect();
+    }
+    record.formated = format!("{}\n", line).into();
+    Some(record)
+}
+
+/// list the files of a split log dir in chronological order.
+/// archives `temp{time}.{log,zip,lz4,gz}` sorted by time, then the active `temp.log`
+pub fn log_files(dir: &str) -> Result<Vec<PathBuf>, LogError> {
+    let paths = std::fs::read_dir(dir)
+        .map_err(|e| LogError::from(format!("[fast_log] read_dir({}) fail:{}", dir, e)))?;
+    let mut archives = vec![];
+    let mut temp = None;
+    for path in paths.flatten() {
+        let name = path.file_name().to_str().unwrap_or("").to_string();
+        if name == "temp.log" {
+            temp = Some(path.path());
+        } else if name.starts_with("temp") && is_log_file(&name) {
+            archives.push((name, path.path()));
+        }
+    }
+    archives.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut files: Vec<PathBuf> = archives.into_iter().map(|(_, p)| p).collect();
+    if let Some(temp) = temp {
+        files.push(temp);
+    }
+    Ok(files)
+}
+
+fn is_log_file(name: &str) -> bool {
+    name.ends_with(".log") || name.ends_with(".zip") || name.ends_with(".lz4") || name.ends_with(".gz")
+}
+
+/// open a log file, archives are decompressed by extension
+pub fn open(path: &Path) -> Result<Box<dyn BufRead>, LogError> {
+    let file = File::open(path).map_err(|e| {
+        LogError::from(format!("[fast_log] open({}) fail:{}", path.display(), e))
+    })?;
+    let extension = path.extension().and_then(|v| v.to_str()).unwrap_or("");
+    match extension {
+        #[cfg(feature = "zip")]
+        "zip" => {
+            let mut archive = zip::ZipArchive::new(file)
+                .map_err(|e| LogError::from(format!("[fast_log] read zip fail:{}", e)))?;
+            let mut data = vec![];
+            for index in 0..archive.len() {
+                let mut item = archive
+                    .by_index(index)
+                    .map_err(|e| LogError::from(format!("[fast_log] read zip fail:{}", e)))?;
+                item.read_to_end(&mut data)
+                    .map_err(|e| LogError::from(format!("[fast_log] read zip fail:{}", e)))?;
+            }
+            Ok(Box::new(Cursor::new(data)))
+        }
+        #[cfg(feature = "lz4")]
+        "lz4" => {
+            let decoder = lz4::Decoder::new(file)
+                .map_err(|e| LogError::from(format!("[fast_log] read lz4 fail:{}", e)))?;
+            Ok(Box::new(BufReader::new(decoder)))
+        }
+        #[cfg(feature = "gzip")]
+        "gz" => Ok(Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file)))),
+        "log" => Ok(Box::new(BufReader::new(file))),
+        _ => Err(LogError::from(format!(
+            "[fast_log] not support file:{}, enable the packer feature",
+            path.display()
+        ))),
+    }
+}
+
+/// iterate the lines of log files in order
+pub struct LogReader {
+    files: std::vec::IntoIter<PathBuf>,
+    current: Option<(PathBuf, Box<dyn BufRead>)>,
+    offset: u64,
+    line_no: u64,
+}
+
+impl LogReader {
+    pub fn new(files: Vec<PathBuf>) -> Self {
+        Self {
+            files: files.into_iter(),
+            current: None,
+            offset: 0,
+            line_no: 0,
+        }
+    }
+}
+
+impl Iterator for LogReader {
+    type Item = Result<LogLine, LogError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.current.is_none() {
+                let path = self.files.next()?;
+                self.offset = 0;
+                self.line_no = 0;
+                match open(&path) {
+                    Ok(r) => self.current = Some((path, r)),
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            let (path, r) = self.current.as_mut().unwrap();
+            let mut buf = vec![];
+            match r.read_until(b'\n', &mut buf) {
+                Ok(0) => {
+                    self.current = None;
+                }
+                Ok(len) => {
+                    let offset = self.offset;
+                    self.offset += len as u64;
+                    self.line_no += 1;
+                    while buf.last() == Some(&b'\n') || buf.last() == Some(&b'\r') {
+                        buf.pop();
+                    }
+                    return Some(Ok(LogLine {
+                        file: path.clone(),
+                        offset,
+                        line_no: self.line_no,
+                        line: String::from_utf8_lossy(&buf).to_string(),
+                    }));
+                }
+                Err(e) => {
+                    let path = path.display().to_string();
+                    self.current = None;
+                    return Some(Err(LogError::from(format!(
+                        "[fast_log] read({}) fail:{}",
+                        path, e
+                    ))));
+                }
+            }
+        }
+    }
+}
+
+/// read all lines of a split log dir in chronological order
+/// for example:
+/// for line in fast_log::reader::read_dir("target/logs/")? { println!("{}", line?.line); }
+pub fn read_dir(dir: &str) -> Result<LogReader, LogError> {
+    Ok(LogReader::new(log_files(dir)?))
+}
+
+/// the header of a log file written with FileAppender::meta or FileSplitAppender::meta, None if it has none
+pub fn file_header(path: &Path) -> Option<serde_json::Value> {
+    let mut line = String::new();
+    open(path).ok()?.read_line(&mut line).ok()?;
+    parse_meta(line.trim_end())
+}
+
+/// parse the time of archive name `temp{%Y_%m_%dT%H_%M_%S%.f}.zip`
+pub fn file_name_time(name: &str) -> Option<NaiveDateTime> {
+    if !name.starts_with("temp") {
+        return None;
+    }
+    let mut time_str = &name[4..];
+    if let Some(v) = time_str.rfind('.') {
+        time_str = &time_str[0..v];
+    }
+    NaiveDateTime::parse_from_str(time_str.trim_end_matches('_'), "%Y_%m_%dT%H_%M_%S%.f").ok()
+}
+
+/// parse the time at the start of a line written by FastLogFormatRecord
+pub fn line_time(line: &str) -> Option<NaiveDateTime> {
+    let mut items = line.split_whitespace();
+    let date = items.next()?;
+    let time = items.next()?;
+    NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M:%S%.f").ok()
+}
+
+/// search the active temp file and archives of a split log dir,
+/// return the lines contains `pattern` and record time in `time_range`.
+/// lines without a time (for example a backtrace) use the time of the line before.
+/// for example:
+/// fast_log::reader::grep("target/logs/", "ERROR", ..)
+pub fn grep<R: RangeBounds<NaiveDateTime>>(
+    dir: &str,
+    pattern: &str,
+    time_range: R,
+) -> Result<Vec<LogLine>, LogError> {
+    let mut files = log_files(dir)?;
+    //an archive only holds records before its rotation time
+    files.retain(|path| {
+        let name = path.file_name().and_then(|v| v.to_str()).unwrap_or("");
+        match (file_name_time(name), time_range.start_bound()) {
+            (Some(time), Bound::Included(start)) | (Some(time), Bound::Excluded(start)) => {
+                time >= *start
+            }
+            _ => true,
+        }
+    });
+    let mut result = vec![];
+    let mut last_time = None;
+    let mut last_file = PathBuf::new();
+    for line in LogReader::new(files) {
+        let line = line?;
+        if line.file != last_file {
+            last_file = line.file.clone();
+            last_time = None;
+        }
+        if let Some(time) = line_time(&line.line) {
+            last_time = Some(time);
+        }
+        let in_range = match &last_time {
+            Some(time) => time_range.contains(time),
+            None => true,
+        };
+        if in_range && line.line.contains(pattern) {
+            result.push(line);
+        }
+    }
+    Ok(result)
+}
+
+/// parse the level of a line written by FastLogFormatRecord
+pub fn line_level(line: &str) -> Option<Level> {
+    Level::from_str(line.split_whitespace().nth(2)?).ok()
+}
+
+/// parse the module path of a line written by FastLogFormatRecord
+pub fn line_module(line: &str) -> Option<&str> {
+    let module = line.split_whitespace().nth(3)?;
+    match module.rfind(':') {
+        Some(v) if module[v + 1..].chars().all(|c| c.is_ascii_digit()) => Some(&module[0..v]),
+        _ => Some(module),
+    }
+}
+
+/// parse a line written by FastLogFormatRecord back into a record, formated is the line
+pub fn parse_record(line: &str) -> Option<FastLogRecord> {
+    let time = line_time(line)?;
+    let level = line_level(line)?;
+    let module = line.split_whitespace().nth(3).unwrap_or("");
+    let module_path = line_module(line).unwrap_or("");
+    //"module:line", the line is after the module path
+    let line_no = module.get(module_path.len() + 1..).and_then(|v| v.parse::<u32>().ok());
+    let args = match line.find(" - ") {
+        Some(v) => line[v + 3..].to_string(),
+        None => String::new(),
+    };
+    let mut record = FastLogRecord::new_command(Command::CommandRecord);
+    record.level = level;
+    record.target = module_path.to_string();
+    record.module_path = module_path.to_string();
+    record.args = args.into();
+    record.line = line_no;
+    record.monotonic = None;
+    record.now = match Local.from_local_datetime(&time).earliest() {
+        Some(v) => SystemTime::from(v),
+        None => SystemTime::from(Utc.from_utc_datetime(&time)),
+    };
+    record.formated = format!("{}\n", line).into();
+    Some(record)
+}
+
+/// read the logs of a split log dir and send them to `sink` again, keeping the time between records.
+/// speed: 1.0 is the original timing, 2.0 is twice as fast, 0 sends without waiting.
+/// lines without a time (for example a backtrace) are part of the record before
+pub fn replay(dir: &str, speed: f64, sink: &dyn LogAppender) -> Result<u64, LogError> {
+    let mut count = 0;
+    let mut last: Option<FastLogRecord> = None;
+    let mut last_time: Option<SystemTime> = None;
+    let mut send = |record: &mut FastLogRecord| {
+        if speed > 0.0 {
+            if let Some(Ok(d)) = last_time.map(|t| record.now.duration_since(t)) {
+                std::thread::sleep(d.div_f64(speed));
+            }
+        }
+        last_time = Some(record.now);
+        sink.do_log(record);
+        count += 1;
+    };
+    for line in read_dir(dir)? {
+        let line = line?;
+        if line.line.starts_with(META_PREFIX) {
+            continue;
+        }
+        match parse_record(&line.line) {
+            Some(record) => {
+                if let Some(mut record) = last.replace(record) {
+                    send(&mut record);
+                }
+            }
+            None => {
+                if let Some(record) = last.as_mut() {
+                    record.formated.push_str(&line.line);
+                    record.formated.push('\n');
+                }
+            }
+        }
+    }
+    if let Some(mut record) = last.take() {
+        send(&mut record);
+    }
+    Ok(count)
+}
@@ -0,0 +1,397 @@
+use crate::appender::{Command, FastLogRecord, LogAppender, PipelineSender};
+use crate::consts::SCHEMA_VERSION;
+use crate::error::LogError;
+use crate::plugin::net::{EXIT_TIMEOUT, RECONNECT_INTERVAL, WRITE_TIMEOUT};
+use crate::trace::TraceContext;
+use crossbeam::channel::{Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use std::io::{BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+/// how often a stopped server notices close()
+pub(crate) const ACCEPT_POLL: Duration = Duration::from_millis(100);
+
+/// max frame body, bigger frames close the connection
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// a record on the wire. a frame is a 4 bytes big endian length + json body
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RelayRecord {
+    /// schema version(consts::SCHEMA_VERSION), 1 if the sender did not set it
+    #[serde(default = "schema_v1")]
+    pub v: u32,
+    pub level: String,
+    pub target: String,
+    pub args: String,
+    #[serde(default)]
+    pub module_path: String,
+    #[serde(default)]
+    pub file: String,
+    #[serde(default)]
+    pub line: Option<u32>,
+    /// unix time nanos
+    pub now: u128,
+    #[serde(default)]
+    pub formated: String,
+    #[serde(default)]
+    pub trace: Option<TraceContext>,
+    /// name of the sending process
+    #[serde(default)]
+    pub source: String,
+    #[serde(default)]
+    pub kv: Vec<(String, serde_json::Value)>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+fn schema_v1() -> u32 {
+    1
+}
+
+impl RelayRecord {
+    pub fn from_record(record: &FastLogRecord, source: &str) -> Self {
+        Self {
+            v: SCHEMA_VERSION,
+            level: record.level.to_string(),
+            target: record.target.clone(),
+            args: record.args.to_string(),
+            module_path: record.module_path.clone(),
+            file: record.file.clone(),
+            line: record.line,
+            now: record.now.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0),
+            formated: record.formated.to_string(),
+            trace: record.trace.clone(),
+            source: source.to_string(),
+            kv: record.kv.clone(),
+            tags: record.tags.clone(),
+        }
+    }
+
+    pub fn into_record(self) -> FastLogRecord {
+        let mut record = FastLogRecord::new_command(Command::CommandRecord);
+        record.level = log::Level::from_str(&self.level).unwrap_or(log::Level::Info);
+        record.target = self.target;
+        record.args = self.args.into();
+        record.module_path = self.module_path;
+        record.file = self.file;
+        record.line = self.line;
+        record.now = UNIX_EPOCH + Duration::from_nanos(self.now as u64);
+        record.monotonic = None;
+        record.formated = self.formated.into();
+        record.trace = self.trace;
+        record.kv = self.kv;
+        record.tags = self.tags;
+        record
+    }
+}
+
+/// write one frame
+pub fn write_frame<W: Write>(w: &mut W, record: &RelayRecord) -> std::io::Result<()> {
+    let body = serde_json::to_vec(record).map_err(std::io::Error::other)?;
+    w.write_all(&(body.len() as u32).to_be_bytes())?;
+    w.write_all(&body)
+}
+
+/// read one frame, Ok(None) on a closed connection
+pub fn read_frame<R: Read>(r: &mut R) -> std::io::Result<Option<RelayRecord>> {
+    let mut len = [0u8; 4];
+    if let Err(e) = r.read_exact(&mut len) {
+        if e.kind() == std::io::ErrorKind::UnexpectedEof {
+            return Ok(None);
+        }
+        return Err(e);
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "frame too large"));
+    }
+    let mut body = vec![0u8; len];
+    r.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+fn read_conn<R: Read>(stream: R, sender: Sender<FastLogRecord>) {
+    let mut reader = BufReader::new(stream);
+    while let Ok(Some(record)) = read_frame(&mut reader) {
+        if sender.send(record.into_record()).is_err() {
+            return;
+        }
+    }
+}
+
+/// a running relay server(see serve). dropping it keeps the server running
+pub struct RelayServer {
+    stop: Arc<AtomicBool>,
+    commands: Sender<FastLogRecord>,
+    accept: Option<JoinHandle<()>>,
+    dispatch: Option<JoinHandle<()>>,
+}
+
+impl RelayServer {
+    /// close the listener, write the records already received and drop the appenders.
+    /// open connections are read until the sender closes them, their records are dropped
+    pub fn close(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(accept) = self.accept.take() {
+            accept.join();
+        }
+        self.commands.send(FastLogRecord::new_command(Command::CommandExit));
+        if let Some(dispatch) = self.dispatch.take() {
+            dispatch.join();
+        }
+    }
+}
+
+/// accept until `stop` is set, the listener is non blocking so the flag is polled
+macro_rules! spawn_accept {
+    ($listener:expr, $sender:expr, $stop:expr) => {{
+        let (listener, sender, stop) = ($listener, $sender, $stop);
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| LogError::from(format!("[fast_log] relay listen fail:{}", e)))?;
+        std::thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        //an accepted socket may inherit the non blocking mode(bsd)
+                        stream.set_nonblocking(false);
+                        let sender = sender.clone();
+                        std::thread::spawn(move || read_conn(stream, sender));
+                    }
+                    Err(_) => std::thread::sleep(ACCEPT_POLL),
+                }
+            }
+        })
+    }};
+}
+
+/// run the relay server: accept framed records from RelayAppender of other processes
+/// and feed them into `appenders` (the records are already formatted by the sender).
+/// addr: "127.0.0.1:9002" or "unix:/tmp/fast_log.sock"(an exist socket file is replaced, an other file is an error)
+pub fn serve(addr: &str, appenders: Vec<Box<dyn LogAppender>>) -> Result<RelayServer, LogError> {
+    let (sender, receiver) = crossbeam::channel::unbounded::<FastLogRecord>();
+    let commands = sender.clone();
+    let stop = Arc::new(AtomicBool::new(false));
+    let accept_stop = stop.clone();
+    let accept = if let Some(path) = addr.strip_prefix("unix:") {
+        #[cfg(unix)]
+        {
+            crate::admin::remove_stale_socket(path)?;
+            let listener = std::os::unix::net::UnixListener::bind(path)
+                .map_err(|e| LogError::from(format!("[fast_log] relay bind({}) fail:{}", addr, e)))?;
+            spawn_accept!(listener, sender, accept_stop)
+        }
+        #[cfg(not(unix))]
+        return Err(LogError::from(format!("[fast_log] relay not support {}", path)));
+    } else {
+        let listener = TcpListener::bind(addr)
+            .map_err(|e| LogError::from(format!("[fast_log] relay bind({}) fail:{}", addr, e)))?;
+        spawn_accept!(listener, sender, accept_stop)
+    };
+    let dispatch = spawn_dispatch(receiver, commands.clone(), appenders);
+    Ok(RelayServer {
+        stop,
+        commands,
+        accept: Some(accept),
+        dispatch: Some(dispatch),
+    })
+}
+
+/// appenders are not Sync, one thread owns them until CommandExit
+fn spawn_dispatch(
+    receiver: Receiver<FastLogRecord>,
+    commands: Sender<FastLogRecord>,
+    appenders: Vec<Box<dyn LogAppender>>,
+) -> JoinHandle<()> {
+    //the commands of the appenders(split timers, rotations) come back to this thread
+    let pipeline = PipelineSender::new(move |record| commands.send(record).is_ok());
+    for x in &appenders {
+        x.attach(&pipeline);
+    }
+    std::thread::spawn(move || {
+        for x in &appenders {
+            x.on_init();
+        }
+        //records already received go as one batch, a command goes alone
+        let mut next = None;
+        while let Some(first) = next.take().or_else(|| receiver.recv().ok()) {
+            if first.command.eq(&Command::CommandExit) {
+                for x in &appenders {
+                    x.shutdown();
+                }
+                break;
+            }
+            if first.command.ne(&Command::CommandRecord) {
+                let mut command = first;
+                crate::fast_log::dispatch_formatted(&appenders, &mut command);
+                continue;
+            }
+            let mut batch = vec![first];
+            while batch.len() < crate::fast_log::MAX_BATCH_SIZE {
+                match receiver.try_recv() {
+                    Ok(v) if v.command.eq(&Command::CommandRecord) => batch.push(v),
+                    Ok(v) => {
+                        next = Some(v);
+                        break;
+                    }
+                    Err(_) => break,
+                }
+            }
+            for x in &appenders {
+                x.do_logs(&mut batch);
+            }
+        }
+    })
+}
+
+/// a tcp or unix socket connection of "host:port" or "unix:/path"
+pub(crate) enum RelayConn {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixStream),
+}
+
+impl RelayConn {
+    pub(crate) fn connect(addr: &str) -> std::io::Result<RelayConn> {
+        if let Some(path) = addr.strip_prefix("unix:") {
+            #[cfg(unix)]
+            return std::os::unix::net::UnixStream::connect(path).map(RelayConn::Unix);
+            #[cfg(not(unix))]
+            return Err(std::io::Error::other(format!("not support {}", path)));
+        }
+        TcpStream::connect(addr).map(RelayConn::Tcp)
+    }
+
+    pub(crate) fn set_write_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            RelayConn::Tcp(s) => s.set_write_timeout(timeout),
+            #[cfg(unix)]
+            RelayConn::Unix(s) => s.set_write_timeout(timeout),
+        }
+    }
+
+    /// copy the rest of the file, std::io::copy uses sendfile/splice on linux for a File to a socket
+    pub(crate) fn send_file(&mut self, file: &mut std::fs::File) -> std::io::Result<u64> {
+        match self {
+            RelayConn::Tcp(s) => std::io::copy(file, s),
+            #[cfg(unix)]
+            RelayConn::Unix(s) => std::io::copy(file, s),
+        }
+    }
+}
+
+impl Write for RelayConn {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            RelayConn::Tcp(s) => s.write(buf),
+            #[cfg(unix)]
+            RelayConn::Unix(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            RelayConn::Tcp(s) => s.flush(),
+            #[cfg(unix)]
+            RelayConn::Unix(s) => s.flush(),
+        }
+    }
+}
+
+/// ship records to a relay server (fast_log::relay::serve) on its own thread.
+/// it reconnects every second while the server is down, buffering at most `buffer_cap` records
+pub struct RelayAppender {
+    sender: Option<Sender<RelayRecord>>,
+    handle: Option<JoinHandle<()>>,
+    closing: Arc<AtomicBool>,
+    source: String,
+}
+
+impl RelayAppender {
+    /// addr: "127.0.0.1:9002" or "unix:/tmp/fast_log.sock"; source: name of this process
+    pub fn new(addr: &str, source: &str, buffer_cap: usize) -> RelayAppender {
+        let (sender, receiver) = crossbeam::channel::bounded::<RelayRecord>(buffer_cap.max(1));
+        let addr = addr.to_string();
+        let closing = Arc::new(AtomicBool::new(false));
+        let thread_closing = closing.clone();
+        let handle = std::thread::spawn(move || {
+            let mut conn: Option<RelayConn> = None;
+            let mut exit_deadline: Option<Instant> = None;
+            let mut reported = false;
+            for record in receiver.iter() {
+                loop {
+                    //on exit the buffer is sent until EXIT_TIMEOUT, also to a server which is only slow
+                    if thread_closing.load(Ordering::Relaxed) {
+                        let deadline = *exit_deadline.get_or_insert(Instant::now() + EXIT_TIMEOUT);
+                        if Instant::now() >= deadline {
+                            crate::health::report_error(
+                                "RelayAppender",
+                                &format!("[fast_log] relay send({}) fail, {} records lost", addr, receiver.len() + 1),
+                            );
+                            return;
+                        }
+                    }
+                    if conn.is_none() {
+                        match RelayConn::connect(&addr) {
+                            Ok(c) => {
+                                c.set_write_timeout(Some(WRITE_TIMEOUT));
+                                conn = Some(c);
+                                reported = false;
+                            }
+                            Err(e) => {
+                                if !reported {
+                                    reported = true;
+                                    crate::health::report_error(
+                                        "RelayAppender",
+                                        &format!("[fast_log] relay connect({}) fail: {}", addr, e),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    if let Some(c) = conn.as_mut() {
+                        if write_frame(c, &record).is_ok() {
+                            break;
+                        }
+                        conn = None;
+                    }
+                    std::thread::sleep(RECONNECT_INTERVAL);
+                }
+            }
+        });
+        Self {
+            sender: Some(sender),
+            handle: Some(handle),
+            closing,
+            source: source.to_string(),
+        }
+    }
+}
+
+impl LogAppender for RelayAppender {
+    fn do_log(&self, record: &mut FastLogRecord) {
+        if let (Command::CommandRecord, Some(sender)) = (&record.command, &self.sender) {
+            //full: the server is down too long, drop
+            sender.try_send(RelayRecord::from_record(record, &self.source));
+        }
+    }
+}
+
+impl Drop for RelayAppender {
+    /// send the buffered records, the pipeline drops appenders on exit
+    fn drop(&mut self) {
+        self.closing.store(true, Ordering::Relaxed);
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            handle.join();
+        }
+    }
+}
+
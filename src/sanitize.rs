@@ -0,0 +1,84 @@
+use crate::appender::{FastLogRecord, RecordFormat};
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::Write;
+
+/// characters which forge lines or control a terminal: control characters except tab
+/// (newlines, ESC of the ANSI sequences...), DEL, the C1 controls and the bidi overrides
+fn is_unsafe(c: char) -> bool {
+    (c.is_control() && c != '\t') || matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// escape the unsafe characters: "\n", "\r", "\x1b", "\u{202e}"... a safe text is borrowed as is
+pub fn escape(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_unsafe) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if is_unsafe(c) && (c as u32) < 0x80 => {
+                let _ = write!(escaped, "\\x{:02x}", c as u32);
+            }
+            c if is_unsafe(c) => {
+                let _ = write!(escaped, "\\u{{{:x}}}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+fn escape_value(value: &serde_json::Value) -> Option<serde_json::Value> {
+    match value {
+        serde_json::Value::String(v) => match escape(v) {
+            Cow::Owned(v) => Some(v.into()),
+            Cow::Borrowed(_) => None,
+        },
+        _ => None,
+    }
+}
+
+/// escape the unsafe characters(see escape) of the message, key-values and tags before the inner
+/// format writes them, so untrusted input can not forge records or send escape sequences to a terminal
+/// tailing the file. for example SanitizeFormat::new(FastLogFormatRecord::new())
+pub struct SanitizeFormat<F: RecordFormat> {
+    inner: F,
+}
+
+impl<F: RecordFormat> SanitizeFormat<F> {
+    pub fn new(inner: F) -> Self {
+        Self { inner }
+    }
+}
+
+impl<F: RecordFormat> RecordFormat for SanitizeFormat<F> {
+    fn format(&self, record: &FastLogRecord, sink: &mut dyn fmt::Write) -> fmt::Result {
+        let dirty = matches!(escape(&record.args), Cow::Owned(_))
+            || record.kv.iter().any(|(k, v)| matches!(escape(k), Cow::Owned(_)) || escape_value(v).is_some())
+            || record.tags.iter().any(|v| matches!(escape(v), Cow::Owned(_)));
+        if !dirty {
+            return self.inner.format(record, sink);
+        }
+        let mut record = record.clone();
+        if let Cow::Owned(v) = escape(&record.args) {
+            record.args = v.into();
+        }
+        for (k, v) in record.kv.iter_mut() {
+            if let Cow::Owned(escaped) = escape(k) {
+                *k = escaped;
+            }
+            if let Some(escaped) = escape_value(v) {
+                *v = escaped;
+            }
+        }
+        for tag in record.tags.iter_mut() {
+            if let Cow::Owned(escaped) = escape(tag) {
+                *tag = escaped;
+            }
+        }
+        self.inner.format(&record, sink)
+    }
+}
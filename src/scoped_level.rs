@@ -0,0 +1,62 @@
+use log::LevelFilter;
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// guards alive in all threads/coroutines, while any is alive `log` lets every level through to the logger
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(not(target_arch = "wasm32"))]
+may::coroutine_local!(static SCOPE_LEVEL: Cell<Option<LevelFilter>> = Cell::new(None));
+
+#[cfg(target_arch = "wasm32")]
+thread_local!(static SCOPE_LEVEL: Cell<Option<LevelFilter>> = Cell::new(None));
+
+/// restores the level of the scope on drop, see with_level
+#[must_use = "the level is restored when the guard is dropped"]
+pub struct LevelGuard {
+    prev: Option<LevelFilter>,
+    /// the level belongs to the current thread/coroutine
+    _not_send: PhantomData<*const ()>,
+}
+
+/// raise the level of the current thread(or coroutine) until the guard is dropped,
+/// for example to trace one request handler: `let _g = fast_log::with_level(log::Level::Trace);`.
+/// the global level of other threads does not change
+pub fn with_level(level: log::Level) -> LevelGuard {
+    let prev = SCOPE_LEVEL.with(|v| v.replace(Some(level.to_level_filter())));
+    if ACTIVE.fetch_add(1, Ordering::SeqCst) == 0 {
+        log::set_max_level(LevelFilter::Trace);
+    }
+    LevelGuard {
+        prev,
+        _not_send: PhantomData,
+    }
+}
+
+impl Drop for LevelGuard {
+    fn drop(&mut self) {
+        SCOPE_LEVEL.with(|v| v.set(self.prev));
+        if ACTIVE.fetch_sub(1, Ordering::SeqCst) == 1 {
+            let level = crate::fast_log::logger().get_level();
+            log::set_max_level(crate::fast_log::max_level(level));
+        }
+    }
+}
+
+/// the level of the current scope, Off without a guard
+pub(crate) fn scope_level() -> LevelFilter {
+    if ACTIVE.load(Ordering::Relaxed) == 0 {
+        return LevelFilter::Off;
+    }
+    SCOPE_LEVEL.with(|v| v.get()).unwrap_or(LevelFilter::Off)
+}
+
+/// Trace while any guard is alive, so `log` passes the records of the scope to the logger
+pub(crate) fn max_level() -> LevelFilter {
+    if ACTIVE.load(Ordering::Relaxed) == 0 {
+        LevelFilter::Off
+    } else {
+        LevelFilter::Trace
+    }
+}
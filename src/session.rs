@@ -0,0 +1,59 @@
+use crate::appender::{Command, FastLogRecord};
+use log::LevelFilter;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// the target of the session start/stop records
+pub const SESSION_TARGET: &str = "fast_log::session";
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// every pipeline logs "logger started" after its appenders start and "logger stopping" before they
+/// shut down, so restarts can be found in the logs. on by default
+pub fn set_session_markers(enable: bool) {
+    ENABLED.store(enable, Ordering::Relaxed);
+}
+
+fn pid() -> u32 {
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::process::id();
+    #[cfg(target_arch = "wasm32")]
+    return 0;
+}
+
+fn marker(args: String) -> FastLogRecord {
+    let mut record = FastLogRecord::new_command(Command::CommandRecord);
+    record.target = SESSION_TARGET.to_string();
+    record.module_path = SESSION_TARGET.to_string();
+    record.args = args.into();
+    record
+}
+
+/// "logger started (version, pid, level, appenders)", None if the markers are off
+pub(crate) fn started(level: LevelFilter, appenders: &[String]) -> Option<FastLogRecord> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let appenders: Vec<&str> = appenders.iter().map(|v| crate::health::short_name(v)).collect();
+    let version = env!("CARGO_PKG_VERSION");
+    Some(marker(format!(
+        "logger started (version {}, pid {}, level {}, appenders {})",
+        version,
+        pid(),
+        level,
+        appenders.join(",")
+    )))
+}
+
+/// "logger stopping (records written, dropped)", None if the markers are off.
+/// dropped is the records dropped over the memory budget or by a full channel, by all loggers of the process
+pub(crate) fn stopping(written: u64) -> Option<FastLogRecord> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let metrics = crate::metrics::metrics();
+    let dropped = metrics.budget_dropped + metrics.channel_dropped;
+    Some(marker(format!(
+        "logger stopping (records written {}, dropped {})",
+        written, dropped
+    )))
+}
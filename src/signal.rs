@@ -0,0 +1,41 @@
+use crate::error::LogError;
+use log::LevelFilter;
+use signal_hook::consts::{SIGHUP, SIGUSR1, SIGUSR2};
+use signal_hook::iterator::Signals;
+
+/// you need enable fast_log = { ... ,features=["signal"]} (unix only)
+/// handle the signals operators expect from daemons:
+/// SIGHUP  reopen log files (logrotate `postrotate kill -HUP`)
+/// SIGUSR1 flush, the split log appender rotates
+/// SIGUSR2 toggle Debug level on/off
+pub fn install_signal_handler() -> Result<(), LogError> {
+    let mut signals = Signals::new([SIGHUP, SIGUSR1, SIGUSR2])
+        .map_err(|e| LogError::from(format!("[fast_log] install signal handler fail:{}", e)))?;
+    std::thread::spawn(move || {
+        //level before SIGUSR2 turned Debug on
+        let mut saved_level = None;
+        for signal in signals.forever() {
+            match signal {
+                SIGHUP => {
+                    crate::fast_log::reopen();
+                }
+                SIGUSR1 => {
+                    crate::fast_log::flush();
+                }
+                SIGUSR2 => {
+                    let logger = crate::fast_log::logger();
+                    let level = match saved_level.take() {
+                        Some(level) => level,
+                        None => {
+                            saved_level = Some(logger.get_level());
+                            LevelFilter::Debug.max(logger.get_level())
+                        }
+                    };
+                    crate::fast_log::set_level_filter(level);
+                }
+                _ => {}
+            }
+        }
+    });
+    Ok(())
+}
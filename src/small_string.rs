@@ -0,0 +1,226 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+/// bytes kept inline, longer strings move to the heap
+pub const INLINE_CAP: usize = 64;
+
+/// a string which keeps up to INLINE_CAP bytes inline, so the message of a typical record
+/// needs no heap allocation. it derefs to str, build it with push_str, write! or From<&str>
+#[derive(Clone)]
+pub struct SmallString(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline(u8, [u8; INLINE_CAP]),
+    Heap(String),
+}
+
+impl SmallString {
+    pub const fn new() -> Self {
+        SmallString(Repr::Inline(0, [0; INLINE_CAP]))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            //only whole strs are copied into buf
+            Repr::Inline(len, buf) => std::str::from_utf8(&buf[..*len as usize]).unwrap_or_default(),
+            Repr::Heap(s) => s.as_str(),
+        }
+    }
+
+    /// format the arguments, a literal message is copied without formatting
+    pub fn from_args(args: &fmt::Arguments) -> Self {
+        match args.as_str() {
+            Some(s) => SmallString::from(s),
+            None => {
+                let mut v = SmallString::new();
+                fmt::Write::write_fmt(&mut v, *args);
+                v
+            }
+        }
+    }
+
+    /// false: the string is on the heap
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline(..))
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        match &mut self.0 {
+            Repr::Inline(len, buf) => {
+                let start = *len as usize;
+                let end = start + s.len();
+                if end <= INLINE_CAP {
+                    buf[start..end].copy_from_slice(s.as_bytes());
+                    *len = end as u8;
+                } else {
+                    let mut heap = String::with_capacity(end.max(INLINE_CAP * 2));
+                    heap.push_str(self.as_str());
+                    heap.push_str(s);
+                    self.0 = Repr::Heap(heap);
+                }
+            }
+            Repr::Heap(heap) => heap.push_str(s),
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// empty the string, a heap string keeps its capacity
+    pub fn clear(&mut self) {
+        match &mut self.0 {
+            Repr::Inline(len, _) => *len = 0,
+            Repr::Heap(heap) => heap.clear(),
+        }
+    }
+
+    /// panics if `new_len` is not on a char boundary, like String::truncate
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len >= self.len() {
+            return;
+        }
+        assert!(self.is_char_boundary(new_len), "new_len is not on a char boundary");
+        match &mut self.0 {
+            Repr::Inline(len, _) => *len = new_len as u8,
+            Repr::Heap(heap) => heap.truncate(new_len),
+        }
+    }
+
+    pub fn into_string(self) -> String {
+        match self.0 {
+            Repr::Inline(..) => self.as_str().to_string(),
+            Repr::Heap(heap) => heap,
+        }
+    }
+}
+
+impl Default for SmallString {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for SmallString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for SmallString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<[u8]> for SmallString {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl Borrow<str> for SmallString {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Write for SmallString {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl fmt::Display for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Debug for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl From<&str> for SmallString {
+    fn from(s: &str) -> Self {
+        let mut v = SmallString::new();
+        v.push_str(s);
+        v
+    }
+}
+
+/// a long string keeps its allocation
+impl From<String> for SmallString {
+    fn from(s: String) -> Self {
+        if s.len() <= INLINE_CAP {
+            SmallString::from(s.as_str())
+        } else {
+            SmallString(Repr::Heap(s))
+        }
+    }
+}
+
+impl From<&String> for SmallString {
+    fn from(s: &String) -> Self {
+        SmallString::from(s.as_str())
+    }
+}
+
+impl From<SmallString> for String {
+    fn from(s: SmallString) -> Self {
+        s.into_string()
+    }
+}
+
+impl PartialEq for SmallString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SmallString {}
+
+impl PartialEq<str> for SmallString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for SmallString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for SmallString {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Hash for SmallString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl Serialize for SmallString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for SmallString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(SmallString::from)
+    }
+}
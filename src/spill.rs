@@ -0,0 +1,69 @@
+use crate::appender::FastLogRecord;
+use crate::error::LogError;
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::UNIX_EPOCH;
+
+/// the first chars of a spilled message kept in the log line
+const PREVIEW_CHARS: usize = 80;
+
+struct Spill {
+    dir: String,
+    threshold: usize,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SEQ: AtomicU64 = AtomicU64::new(0);
+
+lazy_static! {
+    static ref SPILL: RwLock<Option<Spill>> = RwLock::new(None);
+}
+
+/// messages longer than `threshold` bytes are written to their own file in `dir`,
+/// the log line keeps the start of the message and the key-value "spill_file" with the path.
+/// the log files stay line oriented when someone logs a megabyte of json
+pub fn spill_large(dir: &str, threshold: usize) -> Result<(), LogError> {
+    let mut dir = dir.replace("\\", "/");
+    if !dir.is_empty() && !dir.ends_with('/') {
+        dir.push('/');
+    }
+    if !dir.is_empty() {
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| LogError::from(format!("[fast_log] create spill dir({}) fail:{}", dir, e)))?;
+    }
+    *SPILL.write() = Some(Spill { dir, threshold });
+    ENABLED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// log large messages inline again
+pub fn disable_spill() {
+    ENABLED.store(false, Ordering::SeqCst);
+    *SPILL.write() = None;
+}
+
+/// move a large message into a side file, run on the log worker before the record is formatted
+pub(crate) fn apply(record: &mut FastLogRecord) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let spill = SPILL.read();
+    let spill = match spill.as_ref() {
+        Some(v) if record.args.len() > v.threshold => v,
+        _ => return,
+    };
+    let nanos = record.now.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let path = format!(
+        "{}spill-{}-{}.log",
+        spill.dir,
+        nanos,
+        SEQ.fetch_add(1, Ordering::Relaxed)
+    );
+    if let Err(e) = std::fs::write(&path, record.args.as_bytes()) {
+        crate::health::report_error("spill_large", &format!("[fast_log] write spill file({}) fail:{}", path, e));
+        return;
+    }
+    let preview: String = record.args.chars().take(PREVIEW_CHARS).collect();
+    record.args = format!("{}... ({} bytes spilled)", preview, record.args.len()).into();
+    record.kv.push(("spill_file".to_string(), path.into()));
+}
@@ -0,0 +1,87 @@
+use crate::appender::FastLogRecord;
+use crossbeam::channel::{Receiver, Sender, TrySendError};
+use parking_lot::RwLock;
+use std::ops::Deref;
+use std::sync::{Arc, Weak};
+
+/// default capacity of a subscribe channel
+pub const DEFAULT_SUBSCRIBE_CAP: usize = 1000;
+
+lazy_static! {
+    static ref SUBSCRIBERS: RwLock<Vec<Subscriber>> = RwLock::new(vec![]);
+}
+
+struct Subscriber {
+    sender: Sender<Arc<FastLogRecord>>,
+    //used to drop the oldest record when full
+    receiver: Receiver<Arc<FastLogRecord>>,
+    alive: Weak<()>,
+}
+
+/// receive formatted records from the log pipeline.
+/// drop it to unsubscribe
+pub struct LogReceiver {
+    inner: Receiver<Arc<FastLogRecord>>,
+    #[allow(dead_code)]
+    alive: Arc<()>,
+}
+
+impl Deref for LogReceiver {
+    type Target = Receiver<Arc<FastLogRecord>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// tap formatted records from the back worker, for example a live log view.
+/// the channel is bounded, when full the oldest record is dropped
+pub fn subscribe() -> LogReceiver {
+    subscribe_cap(DEFAULT_SUBSCRIBE_CAP)
+}
+
+/// subscribe with channel capacity
+pub fn subscribe_cap(cap: usize) -> LogReceiver {
+    let (sender, receiver) = crossbeam::channel::bounded(cap.max(1));
+    let alive = Arc::new(());
+    SUBSCRIBERS.write().push(Subscriber {
+        sender,
+        receiver: receiver.clone(),
+        alive: Arc::downgrade(&alive),
+    });
+    LogReceiver {
+        inner: receiver,
+        alive,
+    }
+}
+
+pub(crate) fn has_subscribers() -> bool {
+    !SUBSCRIBERS.read().is_empty()
+}
+
+/// send record to all subscribers, call on the back worker
+pub(crate) fn publish(record: &FastLogRecord) {
+    let mut has_closed = false;
+    {
+        let subscribers = SUBSCRIBERS.read();
+        if subscribers.is_empty() {
+            return;
+        }
+        let record = Arc::new(record.clone());
+        for x in subscribers.iter() {
+            if x.alive.strong_count() == 0 {
+                has_closed = true;
+                continue;
+            }
+            let mut data = record.clone();
+            while let Err(TrySendError::Full(v)) = x.sender.try_send(data) {
+                //drop oldest
+                let _ = x.receiver.try_recv();
+                data = v;
+            }
+        }
+    }
+    if has_closed {
+        SUBSCRIBERS.write().retain(|x| x.alive.strong_count() != 0);
+    }
+}
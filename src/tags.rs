@@ -0,0 +1,46 @@
+use std::cell::RefCell;
+
+#[cfg(not(target_arch = "wasm32"))]
+may::coroutine_local!(static TAGS: RefCell<Vec<String>> = RefCell::new(vec![]));
+
+#[cfg(target_arch = "wasm32")]
+thread_local!(static TAGS: RefCell<Vec<String>> = RefCell::new(vec![]));
+
+/// log a record with tags, for example
+/// `fast_log::tagged!(["audit", "billing"], log::Level::Info, "invoice {} paid", id);`.
+/// the tags are in FastLogRecord::tags, for filters and appenders, for example
+/// `FileAppender::new("audit.log").filtered(|r| r.has_tag("audit"))`
+#[macro_export]
+macro_rules! tagged {
+    ([$($tag:expr),+ $(,)?], $($arg:tt)+) => {{
+        let _tags = $crate::tags::with_tags(&[$($tag),+]);
+        $crate::__log::log!($($arg)+);
+    }};
+}
+
+/// removes the tags of with_tags on drop
+#[must_use = "the tags are removed when the guard is dropped"]
+pub struct TagGuard {
+    len: usize,
+}
+
+/// add tags to the records of the current thread(or coroutine) until the guard is dropped
+pub fn with_tags(tags: &[&str]) -> TagGuard {
+    TAGS.with(|v| {
+        let mut v = v.borrow_mut();
+        let len = v.len();
+        v.extend(tags.iter().map(|x| x.to_string()));
+        TagGuard { len }
+    })
+}
+
+impl Drop for TagGuard {
+    fn drop(&mut self) {
+        TAGS.with(|v| v.borrow_mut().truncate(self.len));
+    }
+}
+
+/// the tags of the current thread(or coroutine)
+pub fn current_tags() -> Vec<String> {
+    TAGS.with(|v| v.borrow().clone())
+}
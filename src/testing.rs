@@ -0,0 +1,81 @@
+use crate::appender::{FastLogFormatRecord, RecordFormat};
+use log::{LevelFilter, Metadata, Record};
+use std::cell::RefCell;
+use std::sync::Once;
+
+thread_local! {
+    /// the formatted records of the running test, None without a TestLogGuard
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+static INIT: Once = Once::new();
+
+/// logs records into the test thread which logged them
+struct TestLogger {
+    format: FastLogFormatRecord,
+}
+
+impl log::Log for TestLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        if crate::fast_log::is_ignore_target(record.target()) {
+            return;
+        }
+        let mut record = crate::fast_log::new_record(record);
+        self.format.do_format(&mut record);
+        let formated = CAPTURED.with(|v| match v.borrow_mut().as_mut() {
+            Some(captured) => {
+                captured.push(std::mem::take(&mut record.formated).into_string());
+                None
+            }
+            None => Some(record.formated),
+        });
+        //a thread without guard, the test harness captures print! as well
+        if let Some(formated) = formated {
+            print!("{}", formated);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// capture the records of the current test, printed only if the test fails. can be called by every test:
+/// #[test]
+/// fn test_login() {
+///     let _log = fast_log::test_init();
+///     ...
+/// }
+/// the first call installs a synchronous test logger(Trace) as the `log` logger, when an other logger is
+/// installed already the records go to it
+pub fn test_init() -> TestLogGuard {
+    INIT.call_once(|| {
+        let logger = TestLogger {
+            format: FastLogFormatRecord::new(),
+        };
+        if log::set_boxed_logger(Box::new(logger)).is_ok() {
+            log::set_max_level(LevelFilter::Trace);
+        }
+    });
+    let prev = CAPTURED.with(|v| v.borrow_mut().replace(vec![]));
+    TestLogGuard { prev }
+}
+
+/// prints the captured records on drop if the test is panicking
+#[must_use = "the records are captured until the guard is dropped"]
+pub struct TestLogGuard {
+    prev: Option<Vec<String>>,
+}
+
+impl Drop for TestLogGuard {
+    fn drop(&mut self) {
+        let captured = CAPTURED.with(|v| std::mem::replace(&mut *v.borrow_mut(), self.prev.take()));
+        if std::thread::panicking() {
+            for x in captured.unwrap_or_default() {
+                print!("{}", x);
+            }
+        }
+    }
+}
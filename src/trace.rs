@@ -0,0 +1,52 @@
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+/// trace context captured on the producer side, for log <-> trace correlation
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TraceContext {
+    /// 32 hex chars
+    pub trace_id: String,
+    /// 16 hex chars
+    pub span_id: String,
+}
+
+/// read the trace context of the current thread
+pub type TraceContextProvider = fn() -> Option<TraceContext>;
+
+lazy_static! {
+    static ref PROVIDER: RwLock<Option<TraceContextProvider>> = RwLock::new(None);
+}
+
+/// set how the current trace context is read, for example from a `tracing` span.
+/// with the `opentelemetry` feature the active OpenTelemetry span is used when no provider is set
+pub fn set_trace_context_provider(provider: TraceContextProvider) {
+    *PROVIDER.write() = Some(provider);
+}
+
+/// the trace context of the current thread
+pub fn current_trace_context() -> Option<TraceContext> {
+    if let Some(provider) = PROVIDER.read().as_ref() {
+        return provider();
+    }
+    opentelemetry_context()
+}
+
+#[cfg(feature = "opentelemetry")]
+fn opentelemetry_context() -> Option<TraceContext> {
+    use opentelemetry::trace::TraceContextExt;
+    let context = opentelemetry::Context::current();
+    let span = context.span();
+    let span_context = span.span_context();
+    if !span_context.is_valid() {
+        return None;
+    }
+    Some(TraceContext {
+        trace_id: span_context.trace_id().to_string(),
+        span_id: span_context.span_id().to_string(),
+    })
+}
+
+#[cfg(not(feature = "opentelemetry"))]
+fn opentelemetry_context() -> Option<TraceContext> {
+    None
+}